@@ -130,17 +130,27 @@ export async function exportConfigYaml(): Promise<boolean> {
   return true;
 }
 
-export async function importConfigYaml(): Promise<boolean> {
+export interface ConfigProblem {
+  key: string;
+  message: string;
+}
+
+export interface ConfigImportReport {
+  applied: boolean;
+  problems: ConfigProblem[];
+}
+
+/// Returns `null` if the file dialog was cancelled.
+export async function importConfigYaml(): Promise<ConfigImportReport | null> {
   const filePath = await open({
     filters: [{ name: 'YAML Files', extensions: ['yaml', 'yml'] }],
     multiple: false,
     directory: false,
   });
 
-  if (!filePath) return false;
+  if (!filePath) return null;
 
-  await invoke<void>('import_config_yaml', { path: filePath });
-  return true;
+  return invoke<ConfigImportReport>('import_config_yaml', { path: filePath });
 }
 
 export default function useBackend() {
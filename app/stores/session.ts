@@ -5,6 +5,7 @@
 /// ----
 
 import { defineStore } from 'pinia';
+import type { ConfigImportReport } from '@/composables/useBackend';
 
 export const useSessionStore = defineStore('session', {
   state: () => ({
@@ -67,14 +68,14 @@ export const useSessionStore = defineStore('session', {
       return this.memoryLayout;
     },
 
-    async importConfigFromYaml(): Promise<boolean> {
+    async importConfigFromYaml(): Promise<ConfigImportReport | null> {
       const { store } = useBackend();
-      const imported = await store.importConfigYaml();
-      if (!imported) return false;
+      const report = await store.importConfigYaml();
+      if (!report?.applied) return report;
 
       await this.loadSavedCommandConfig();
       await this.loadSavedMemoryLayout();
-      return true;
+      return report;
     },
 
     async close() {
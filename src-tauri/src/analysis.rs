@@ -0,0 +1,201 @@
+/// This file groups the analyses that run over the entries of a loaded trace.
+/// Every analysis is a plain function over a slice of entries, so the Tauri commands only have
+/// to fetch the entries from the loader and hand them over. Results are serializable and
+/// returned to the frontend as-is.
+use serde::{Deserialize, Serialize};
+
+use crate::trace::entry::Entry;
+
+pub mod classifier;
+pub mod compare;
+pub mod density;
+pub mod hazards;
+pub mod histogram;
+pub mod quality;
+pub mod rank;
+pub mod rows;
+pub mod timing;
+
+/// Full address of a bank. Used as the key of per-bank state when no memory layout is needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct BankKey {
+    pub channel: i16,
+    pub rank: i16,
+    pub bankgroup: i32,
+    pub bank: i32,
+}
+
+impl BankKey {
+    pub fn of(entry: &Entry) -> Self {
+        Self {
+            channel: entry.channel.get(),
+            rank: entry.rank.get(),
+            bankgroup: entry.bankgroup.get(),
+            bank: entry.bank.get(),
+        }
+    }
+
+    /// Whether any component is invalid (-1), as for commands addressing several banks at once,
+    /// e.g. a PREA with bank -1 covers every bank of its bank group or rank.
+    pub fn is_wildcard(&self) -> bool {
+        self.channel == -1 || self.rank == -1 || self.bankgroup == -1 || self.bank == -1
+    }
+
+    /// Whether `other` is one of the banks this key addresses: every component is equal or
+    /// invalid (-1) in this key.
+    pub fn covers(&self, other: &BankKey) -> bool {
+        (self.channel == -1 || self.channel == other.channel)
+            && (self.rank == -1 || self.rank == other.rank)
+            && (self.bankgroup == -1 || self.bankgroup == other.bankgroup)
+            && (self.bank == -1 || self.bank == other.bank)
+    }
+}
+
+/// A set of command ids, indexable by `cmd_id` without hashing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommandSet([bool; 256]);
+
+impl CommandSet {
+    pub fn from_ids(ids: impl IntoIterator<Item = u8>) -> Self {
+        let mut set = [false; 256];
+        for cmd_id in ids {
+            set[cmd_id as usize] = true;
+        }
+        Self(set)
+    }
+
+    pub fn contains(&self, cmd_id: u8) -> bool {
+        self.0[cmd_id as usize]
+    }
+
+    pub fn union(&self, other: &CommandSet) -> Self {
+        Self::from_ids((0..=u8::MAX).filter(|id| self.contains(*id) || other.contains(*id)))
+    }
+}
+
+/// Clk of the first and last entry. Entries are clk-sorted, so these are the trace's bounds.
+pub fn clk_bounds(entries: &[Entry]) -> Option<(i64, i64)> {
+    Some((entries.first()?.clk.get(), entries.last()?.clk.get()))
+}
+
+/// Clk of the first and last entry of a command in `commands`, scanning in from both ends.
+/// Unlike `clk_bounds` this leaves out leading and trailing stretches of other commands.
+pub fn active_span(entries: &[Entry], commands: &CommandSet) -> Option<(i64, i64)> {
+    let first = entries.iter().find(|e| commands.contains(e.cmd_id))?;
+    let last = entries.iter().rfind(|e| commands.contains(e.cmd_id))?;
+    Some((first.clk.get(), last.clk.get()))
+}
+
+/// Upper bound of time buckets one analysis call may produce. Bucket counts come straight from
+/// the frontend and every analysis allocates per bucket, so larger requests are rejected.
+pub const MAX_BUCKETS: usize = 1 << 16;
+
+/// Rejects bucket counts over `MAX_BUCKETS`.
+pub fn check_bucket_count(num_buckets: usize) -> Result<(), String> {
+    if num_buckets > MAX_BUCKETS {
+        return Err(format!(
+            "Requested {} buckets, more than the maximum of {}",
+            num_buckets, MAX_BUCKETS
+        ));
+    }
+    Ok(())
+}
+
+/// Splits the clk range of a trace into `count` equally wide buckets.
+/// Widths are whole cycles (at least 1), so the last buckets may extend past the final clk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeBuckets {
+    pub start_clk: i64,
+    pub width: i64,
+    pub count: usize,
+}
+
+impl TimeBuckets {
+    pub fn new(entries: &[Entry], count: usize) -> Self {
+        let (min, max) = clk_bounds(entries).unwrap_or((0, 0));
+        Self::over(min, max, count)
+    }
+
+    /// Buckets covering the inclusive clk range `[min, max]`.
+    pub fn over(min: i64, max: i64, count: usize) -> Self {
+        let span = (max as i128 - min as i128 + 1).max(1);
+        let width = (span + count.max(1) as i128 - 1) / count.max(1) as i128;
+
+        Self {
+            start_clk: min,
+            width: width.clamp(1, i64::MAX as i128) as i64,
+            count,
+        }
+    }
+
+    /// Bucket index of a clk, clamped to the valid range. Must not be called with zero buckets.
+    pub fn index(&self, clk: i64) -> usize {
+        let offset = (clk as i128 - self.start_clk as i128).max(0) / self.width as i128;
+        (offset as usize).min(self.count - 1)
+    }
+
+    /// The buckets overlapped by the half-open interval `[start, end)` and the number of cycles of
+    /// each covered by it. Parts of the interval outside all buckets are dropped.
+    pub fn overlaps(&self, start: f64, end: f64) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let origin = self.start_clk as f64;
+        let width = self.width as f64;
+        let first = ((start - origin) / width).floor().max(0.0) as usize;
+
+        (first..self.count)
+            .map(move |i| (i, origin + i as f64 * width))
+            .take_while(move |(_, bucket_start)| *bucket_start < end)
+            .map(move |(i, bucket_start)| {
+                let overlap = end.min(bucket_start + width) - start.max(bucket_start);
+                (i, overlap)
+            })
+            .filter(|(_, overlap)| *overlap > 0.0)
+    }
+
+    /// First clk covered by bucket `i`.
+    pub fn start(&self, i: usize) -> i64 {
+        self.start_clk
+            .saturating_add((i as i64).saturating_mul(self.width))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_span_trims_idle_commands_at_both_ends() {
+        let (act, pre, rd, wr, refresh) = (0, 1, 2, 3, 4);
+        let at = |clk, cmd_id| Entry::new(clk, [0, 0, 1, 2, 30, 0], cmd_id);
+        let entries = [
+            at(0, refresh),
+            at(40, act),
+            at(52, rd),
+            at(60, pre),
+            at(75, wr),
+            at(80, pre),
+            at(200, refresh),
+        ];
+        let data = CommandSet::from_ids([rd, wr]);
+
+        assert_eq!(clk_bounds(&entries), Some((0, 200)));
+        assert_eq!(active_span(&entries, &data), Some((52, 75)));
+        assert_eq!(active_span(&entries[2..3], &data), Some((52, 52)));
+        assert_eq!(active_span(&entries, &CommandSet::from_ids([9])), None);
+        assert_eq!(active_span(&[], &data), None);
+    }
+
+    #[test]
+    fn bucket_counts_are_capped() {
+        assert!(check_bucket_count(0).is_ok());
+        assert!(check_bucket_count(MAX_BUCKETS).is_ok());
+        assert_eq!(
+            check_bucket_count(MAX_BUCKETS + 1).unwrap_err(),
+            format!(
+                "Requested {} buckets, more than the maximum of {}",
+                MAX_BUCKETS + 1,
+                MAX_BUCKETS
+            )
+        );
+        assert!(check_bucket_count(usize::MAX).is_err());
+    }
+}
@@ -0,0 +1,149 @@
+/// Maps command ids to semantic categories (read, write, activate, ...) by matching dictionary
+/// names against configurable patterns, so analyses keep working with traces that name their
+/// commands differently (e.g. "READ" instead of "RD").
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::CommandSet;
+use crate::trace::dictionary::Dictionary;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CommandCategory {
+    Read,
+    Write,
+    Activate,
+    Precharge,
+    Refresh,
+    Other,
+}
+
+/// Name patterns per category. Matching is case-insensitive; a trailing `*` matches any suffix,
+/// otherwise the whole name has to match. If several categories match, the first one in field
+/// order wins.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CategoryPatterns {
+    #[serde(default)]
+    pub read: Vec<String>,
+    #[serde(default)]
+    pub write: Vec<String>,
+    #[serde(default)]
+    pub activate: Vec<String>,
+    #[serde(default)]
+    pub precharge: Vec<String>,
+    #[serde(default)]
+    pub refresh: Vec<String>,
+}
+
+impl Default for CategoryPatterns {
+    /// Covers Ramulator's DDR/LPDDR/HBM naming (RD, RDA, WR, WRA, ACT, PRE, PREA, REFab, ...).
+    fn default() -> Self {
+        let patterns = |p: &[&str]| p.iter().map(|s| s.to_string()).collect();
+        Self {
+            read: patterns(&["RD*", "READ*"]),
+            write: patterns(&["WR*", "WRITE*"]),
+            activate: patterns(&["ACT*"]),
+            precharge: patterns(&["PRE*"]),
+            refresh: patterns(&["REF*"]),
+        }
+    }
+}
+
+fn matches_pattern(name: &str, pattern: &str) -> bool {
+    let name = name.to_lowercase();
+    let pattern = pattern.to_lowercase();
+
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+impl CategoryPatterns {
+    pub fn categorize(&self, name: &str) -> CommandCategory {
+        let categories = [
+            (&self.read, CommandCategory::Read),
+            (&self.write, CommandCategory::Write),
+            (&self.activate, CommandCategory::Activate),
+            (&self.precharge, CommandCategory::Precharge),
+            (&self.refresh, CommandCategory::Refresh),
+        ];
+
+        categories
+            .iter()
+            .find(|(patterns, _)| patterns.iter().any(|p| matches_pattern(name, p)))
+            .map_or(CommandCategory::Other, |(_, category)| *category)
+    }
+}
+
+/// The category of every command id of one dictionary.
+#[derive(Debug, Clone)]
+pub struct CommandClassifier {
+    categories: [CommandCategory; 256],
+}
+
+impl CommandClassifier {
+    pub fn new(dictionary: &Dictionary, patterns: &CategoryPatterns) -> Self {
+        let mut categories = [CommandCategory::Other; 256];
+        for (cmd_id, name) in &dictionary.commands {
+            categories[*cmd_id as usize] = patterns.categorize(name);
+        }
+        Self { categories }
+    }
+
+    pub fn category(&self, cmd_id: u8) -> CommandCategory {
+        self.categories[cmd_id as usize]
+    }
+
+    /// All command ids of a category.
+    pub fn set(&self, category: CommandCategory) -> CommandSet {
+        CommandSet::from_ids((0..=u8::MAX).filter(|cmd_id| self.category(*cmd_id) == category))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_patterns_cover_ramulator_names() {
+        let patterns = CategoryPatterns::default();
+
+        assert_eq!(patterns.categorize("RDA"), CommandCategory::Read);
+        assert_eq!(patterns.categorize("wr"), CommandCategory::Write);
+        assert_eq!(patterns.categorize("PREA"), CommandCategory::Precharge);
+        assert_eq!(patterns.categorize("REFab"), CommandCategory::Refresh);
+        assert_eq!(patterns.categorize("NOP"), CommandCategory::Other);
+    }
+
+    #[test]
+    fn custom_patterns_classify_other_naming_schemes() {
+        let patterns = CategoryPatterns {
+            read: vec!["LOAD".to_string()],
+            write: vec!["store_*".to_string()],
+            activate: vec!["open".to_string()],
+            // Overlaps with `read`, which comes first.
+            precharge: vec!["close".to_string(), "load".to_string()],
+            refresh: Vec::new(),
+        };
+        let dictionary = Dictionary::from_names(&["OPEN", "load", "STORE_SYNC", "CLOSE", "LOADX"]);
+        let classifier = CommandClassifier::new(&dictionary, &patterns);
+
+        let categories: Vec<CommandCategory> = (0..6).map(|id| classifier.category(id)).collect();
+        assert_eq!(
+            categories,
+            [
+                CommandCategory::Activate,
+                CommandCategory::Read,
+                CommandCategory::Write,
+                CommandCategory::Precharge,
+                CommandCategory::Other,
+                CommandCategory::Other,
+            ]
+        );
+
+        let reads = classifier.set(CommandCategory::Read);
+        assert!(reads.contains(1));
+        assert!(!reads.contains(4));
+        assert!(!classifier.set(CommandCategory::Refresh).contains(0));
+    }
+}
@@ -0,0 +1,114 @@
+/// Side-by-side statistics of two clk windows of the same trace ("before vs after").
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::histogram::command_counts;
+use crate::analysis::CommandSet;
+use crate::trace::entry::Entry;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowStats {
+    pub start_clk: i64,
+    pub end_clk: i64,
+    pub counts: BTreeMap<u8, u64>,
+    /// Reads and writes per cycle of the window. There is no burst size in the trace, so this
+    /// counts data commands rather than bytes.
+    pub bandwidth: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RangeComparison {
+    pub a: WindowStats,
+    pub b: WindowStats,
+    /// `b - a` per command, over the ids occurring in either window.
+    pub count_deltas: BTreeMap<u8, i64>,
+    pub bandwidth_delta: f64,
+}
+
+/// Stats of the entries of one window. `entries` must already be limited to the inclusive
+/// clk range `[start_clk, end_clk]`.
+pub fn window_stats(
+    entries: &[Entry],
+    start_clk: i64,
+    end_clk: i64,
+    accesses: &CommandSet,
+) -> WindowStats {
+    let cycles = (end_clk as f64 - start_clk as f64 + 1.0).max(1.0);
+    let data_commands = entries
+        .iter()
+        .filter(|e| accesses.contains(e.cmd_id))
+        .count();
+
+    WindowStats {
+        start_clk,
+        end_clk,
+        counts: command_counts(entries),
+        bandwidth: data_commands as f64 / cycles,
+    }
+}
+
+pub fn compare(a: WindowStats, b: WindowStats) -> RangeComparison {
+    let mut count_deltas = BTreeMap::new();
+    for cmd_id in a.counts.keys().chain(b.counts.keys()) {
+        let count_a = a.counts.get(cmd_id).copied().unwrap_or(0) as i64;
+        let count_b = b.counts.get(cmd_id).copied().unwrap_or(0) as i64;
+        count_deltas.insert(*cmd_id, count_b - count_a);
+    }
+
+    RangeComparison {
+        bandwidth_delta: b.bandwidth - a.bandwidth,
+        count_deltas,
+        a,
+        b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACT: u8 = 0;
+    const RD: u8 = 1;
+    const WR: u8 = 2;
+
+    fn window(commands: &[(i64, u8)], start_clk: i64, end_clk: i64) -> WindowStats {
+        let entries: Vec<Entry> = commands
+            .iter()
+            .map(|&(clk, cmd_id)| Entry::new(clk, [0, 0, 2, 1, 300, 0], cmd_id))
+            .collect();
+        window_stats(
+            &entries,
+            start_clk,
+            end_clk,
+            &CommandSet::from_ids([RD, WR]),
+        )
+    }
+
+    #[test]
+    fn deltas_cover_commands_of_either_window() {
+        let a = window(&[(0, ACT), (2, RD), (3, RD), (9, WR)], 0, 9);
+        let b = window(&[(100, RD), (101, WR), (102, WR), (104, WR)], 100, 104);
+        assert_eq!(a.bandwidth, 0.3);
+        assert_eq!(b.bandwidth, 0.8);
+
+        let comparison = compare(a, b);
+        assert_eq!(
+            comparison.count_deltas,
+            BTreeMap::from([(ACT, -1), (RD, -1), (WR, 2)])
+        );
+        assert!((comparison.bandwidth_delta - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn identical_windows_have_zero_deltas() {
+        let a = window(&[(5, RD), (6, ACT)], 5, 6);
+        let comparison = compare(a.clone(), a);
+
+        assert_eq!(comparison.count_deltas, BTreeMap::from([(ACT, 0), (RD, 0)]));
+        assert_eq!(comparison.bandwidth_delta, 0.0);
+        assert_eq!(window(&[], 7, 3).bandwidth, 0.0);
+    }
+}
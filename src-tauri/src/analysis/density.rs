@@ -0,0 +1,1504 @@
+/// Command density over time.
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{clk_bounds, BankKey, CommandSet, TimeBuckets};
+use crate::session::MemoryLayout;
+use crate::trace::entry::Entry;
+
+/// Reported as the dominant command of an empty bucket. Never a valid id, since a dictionary holds at most 255 commands.
+pub const NO_COMMAND: u8 = u8::MAX;
+
+/// Reported as the most contended bank of an empty bucket. Never a valid flat bank index, since a
+/// layout has at most 255^3 banks.
+pub const NO_BANK: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DominantBucket {
+    pub clk_start: i64,
+    pub dominant_cmd_id: u8,
+    /// Total number of entries in the bucket, not just those of the dominant command.
+    pub count: u64,
+}
+
+/// The most frequent command per time bucket, in one scan.
+/// Ties resolve to the lowest cmd_id; empty buckets report `NO_COMMAND` with a count of 0.
+pub fn dominant_commands(entries: &[Entry], num_buckets: usize) -> Vec<DominantBucket> {
+    if num_buckets == 0 {
+        return Vec::new();
+    }
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+    let mut counts: Vec<HashMap<u8, u64>> = vec![HashMap::new(); num_buckets];
+
+    for entry in entries {
+        *counts[buckets.index(entry.clk.get())]
+            .entry(entry.cmd_id)
+            .or_insert(0) += 1;
+    }
+
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, bucket)| {
+            let dominant = bucket
+                .iter()
+                .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+                .map_or(NO_COMMAND, |(cmd_id, _)| *cmd_id);
+
+            DominantBucket {
+                clk_start: buckets.start(i),
+                dominant_cmd_id: dominant,
+                count: bucket.values().sum(),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RwBucket {
+    pub clk_start: i64,
+    pub reads: u64,
+    pub writes: u64,
+    /// reads / (reads + writes), or `None` if the bucket holds neither.
+    pub ratio: Option<f64>,
+}
+
+/// Read and write counts per time bucket and the resulting read fraction.
+pub fn rw_ratio(
+    entries: &[Entry],
+    num_buckets: usize,
+    reads: &CommandSet,
+    writes: &CommandSet,
+) -> Vec<RwBucket> {
+    if num_buckets == 0 {
+        return Vec::new();
+    }
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+    let mut counts = vec![(0u64, 0u64); num_buckets];
+
+    for entry in entries {
+        let bucket = &mut counts[buckets.index(entry.clk.get())];
+        if reads.contains(entry.cmd_id) {
+            bucket.0 += 1;
+        } else if writes.contains(entry.cmd_id) {
+            bucket.1 += 1;
+        }
+    }
+
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, &(reads, writes))| RwBucket {
+            clk_start: buckets.start(i),
+            reads,
+            writes,
+            ratio: (reads + writes > 0).then(|| reads as f64 / (reads + writes) as f64),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivateBucket {
+    pub clk_start: i64,
+    pub activates: u64,
+    pub accesses: u64,
+    /// activates / accesses, or `None` if the bucket holds no access (infinite or undefined).
+    pub ratio: Option<f64>,
+}
+
+impl ActivateBucket {
+    fn new(clk_start: i64, activates: u64, accesses: u64) -> Self {
+        Self {
+            clk_start,
+            activates,
+            accesses,
+            ratio: (accesses > 0).then(|| activates as f64 / accesses as f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivateRatio {
+    /// The whole trace as one bucket, starting at its first clk.
+    pub overall: ActivateBucket,
+    pub buckets: Vec<ActivateBucket>,
+}
+
+/// Activations per access over time: a ratio near 1 means almost every access needed its own
+/// activation (no row reuse), lower is better.
+pub fn activate_ratio(
+    entries: &[Entry],
+    num_buckets: usize,
+    activates: &CommandSet,
+    accesses: &CommandSet,
+) -> ActivateRatio {
+    let buckets = TimeBuckets::new(entries, num_buckets.max(1));
+    let mut counts = vec![(0u64, 0u64); num_buckets];
+    let mut overall = (0u64, 0u64);
+
+    for entry in entries {
+        let (activate, access) = if activates.contains(entry.cmd_id) {
+            (1, 0)
+        } else if accesses.contains(entry.cmd_id) {
+            (0, 1)
+        } else {
+            continue;
+        };
+
+        overall = (overall.0 + activate, overall.1 + access);
+        if num_buckets > 0 {
+            let bucket = &mut counts[buckets.index(entry.clk.get())];
+            *bucket = (bucket.0 + activate, bucket.1 + access);
+        }
+    }
+
+    ActivateRatio {
+        overall: ActivateBucket::new(buckets.start_clk, overall.0, overall.1),
+        buckets: counts
+            .iter()
+            .enumerate()
+            .map(|(i, &(activates, accesses))| {
+                ActivateBucket::new(buckets.start(i), activates, accesses)
+            })
+            .collect(),
+    }
+}
+
+/// Per time bucket, a bitset of the banks that saw any command in it.
+///
+/// Layout: `num_buckets` rows of `ceil(flat_bank_count / 8)` bytes each. Bit `i` of a row is
+/// bit `i % 8` of byte `i / 8` and is set if the bank with flat index `i` was active.
+/// Entries whose bank is invalid or outside the layout are ignored. `None` if the bitsets don't
+/// fit in memory at all.
+pub fn bank_activity_bitset(
+    entries: &[Entry],
+    num_buckets: usize,
+    layout: &MemoryLayout,
+) -> Option<Vec<u8>> {
+    let row_bytes = layout.flat_bank_count().div_ceil(8);
+    let mut bytes = vec![0u8; num_buckets.checked_mul(row_bytes)?];
+
+    if num_buckets == 0 || row_bytes == 0 {
+        return Some(bytes);
+    }
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+
+    for entry in entries {
+        if let Some(bank) = layout.flat_bank_index(entry) {
+            let row = buckets.index(entry.clk.get()) * row_bytes;
+            bytes[row + bank / 8] |= 1 << (bank % 8);
+        }
+    }
+
+    Some(bytes)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParallelismBucket {
+    pub clk_start: i64,
+    pub distinct_bankgroups: u64,
+}
+
+/// Number of distinct bank group values touched per time bucket, a proxy for bank-group
+/// parallelism. Invalid bank groups (-1) are ignored.
+pub fn bankgroup_parallelism(entries: &[Entry], num_buckets: usize) -> Vec<ParallelismBucket> {
+    if num_buckets == 0 {
+        return Vec::new();
+    }
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+    let mut groups: Vec<HashSet<i32>> = vec![HashSet::new(); num_buckets];
+
+    for entry in entries {
+        let bankgroup = entry.bankgroup.get();
+        if bankgroup != -1 {
+            groups[buckets.index(entry.clk.get())].insert(bankgroup);
+        }
+    }
+
+    groups
+        .iter()
+        .enumerate()
+        .map(|(i, bucket)| ParallelismBucket {
+            clk_start: buckets.start(i),
+            distinct_bankgroups: bucket.len() as u64,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterleavingScore {
+    pub window_size: usize,
+    pub windows: u64,
+    /// Average number of distinct banks per window, between 1 (bank-serial) and `window_size`
+    /// (every command on another bank). `None` if fewer commands than `window_size` remain.
+    pub mean_distinct_banks: Option<f64>,
+}
+
+/// How well consecutive commands are spread over banks: the number of distinct banks in every
+/// window of `window_size` consecutive commands, averaged over all windows. Entries with any
+/// invalid bank address component (-1), such as all-bank refreshes, are skipped entirely, so they
+/// neither count as a bank nor take a slot in the window.
+pub fn interleaving_score(entries: &[Entry], window_size: usize) -> InterleavingScore {
+    let mut window: VecDeque<BankKey> = VecDeque::with_capacity(window_size.min(entries.len()));
+    let mut in_window: HashMap<BankKey, usize> = HashMap::new();
+    let mut windows = 0u64;
+    let mut total_distinct = 0u64;
+
+    let banks = entries.iter().map(BankKey::of).filter(|b| !b.is_wildcard());
+
+    for bank in banks.filter(|_| window_size > 0) {
+        window.push_back(bank);
+        *in_window.entry(bank).or_insert(0) += 1;
+
+        if window.len() > window_size {
+            if let Some(leaving) = window.pop_front() {
+                if let Some(count) = in_window.get_mut(&leaving) {
+                    *count -= 1;
+                    if *count == 0 {
+                        in_window.remove(&leaving);
+                    }
+                }
+            }
+        }
+        if window.len() == window_size {
+            windows += 1;
+            total_distinct += in_window.len() as u64;
+        }
+    }
+
+    InterleavingScore {
+        window_size,
+        windows,
+        mean_distinct_banks: (windows > 0).then(|| total_distinct as f64 / windows as f64),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateWindow {
+    pub clk_start: i64,
+    /// Length of the window. Only the last window can be shorter than requested.
+    pub cycles: i64,
+    pub count: u64,
+    /// Commands per 1000 cycles.
+    pub rate: f64,
+}
+
+/// Upper bound of windows one `issue_rate` call may produce.
+pub const MAX_ISSUE_WINDOWS: u64 = 1 << 20;
+
+/// Number of windows `issue_rate` produces for a trace spanning `[min, max]`.
+pub fn issue_window_count(min: i64, max: i64, window_cycles: i64) -> u64 {
+    if window_cycles <= 0 {
+        return 0;
+    }
+    let span = max as i128 - min as i128 + 1;
+    u64::try_from((span + window_cycles as i128 - 1) / window_cycles as i128).unwrap_or(u64::MAX)
+}
+
+/// Command issue rate in consecutive windows of `window_cycles`, starting at the first entry.
+/// The last window ends at the last entry, so it is rate-normalized over its actual length.
+/// Empty windows report a rate of 0. Callers bound the window count with `issue_window_count`.
+pub fn issue_rate(entries: &[Entry], window_cycles: i64) -> Vec<RateWindow> {
+    let Some((min, max)) = clk_bounds(entries) else {
+        return Vec::new();
+    };
+    if window_cycles <= 0 {
+        return Vec::new();
+    }
+
+    let num_windows = issue_window_count(min, max, window_cycles) as usize;
+    let mut counts = vec![0u64; num_windows];
+
+    for entry in entries {
+        let offset = (entry.clk.get() as i128 - min as i128) / window_cycles as i128;
+        counts[(offset as usize).min(num_windows - 1)] += 1;
+    }
+
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            let start = min as i128 + i as i128 * window_cycles as i128;
+            let cycles = (max as i128 - start + 1).min(window_cycles as i128) as i64;
+
+            RateWindow {
+                clk_start: start as i64,
+                cycles,
+                count,
+                rate: count as f64 / cycles as f64 * 1000.0,
+            }
+        })
+        .collect()
+}
+
+/// Upper bound of windows one `rolling_issue_rate` call may produce.
+pub const MAX_ROLLING_WINDOWS: u64 = 1 << 20;
+
+/// Number of windows `rolling_issue_rate` produces for a trace spanning `[min, max]`, saturating
+/// at `u64::MAX`.
+pub fn rolling_window_count(min: i64, max: i64, step_cycles: i64) -> u64 {
+    if step_cycles <= 0 {
+        return 0;
+    }
+    u64::try_from((max as i128 - min as i128) / step_cycles as i128 + 1).unwrap_or(u64::MAX)
+}
+
+/// Issue rate in overlapping windows of `window_cycles`, starting at the first entry and moving
+/// by `step_cycles`, for a smoothed throughput line. A two-pointer sweep, so O(entries + windows).
+/// Windows reaching past the last entry are partial and rate-normalized over their actual length.
+pub fn rolling_issue_rate(
+    entries: &[Entry],
+    window_cycles: i64,
+    step_cycles: i64,
+) -> Vec<RateWindow> {
+    let Some((min, max)) = clk_bounds(entries) else {
+        return Vec::new();
+    };
+    if window_cycles <= 0 || step_cycles <= 0 {
+        return Vec::new();
+    }
+
+    let (mut lo, mut hi) = (0, 0);
+    (0..rolling_window_count(min, max, step_cycles))
+        .map(|i| {
+            let start = min as i128 + i as i128 * step_cycles as i128;
+            let end = start + window_cycles as i128;
+
+            while lo < entries.len() && (entries[lo].clk.get() as i128) < start {
+                lo += 1;
+            }
+            hi = hi.max(lo);
+            while hi < entries.len() && (entries[hi].clk.get() as i128) < end {
+                hi += 1;
+            }
+
+            let count = (hi - lo) as u64;
+            let cycles = (max as i128 - start + 1).min(window_cycles as i128) as i64;
+            RateWindow {
+                clk_start: start as i64,
+                cycles,
+                count,
+                rate: count as f64 / cycles as f64 * 1000.0,
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DensestWindow {
+    pub clk_start: i64,
+    /// Entry indices `[first, end)` inside the window.
+    pub first: u64,
+    pub end: u64,
+    pub count: u64,
+}
+
+/// The window `[clk_start, clk_start + window_cycles)` holding the most entries. Some densest
+/// window always starts at an entry, so only those starts are tried, in a two-pointer sweep. Ties
+/// resolve to the earliest window. `None` for an empty trace or a non-positive length.
+pub fn densest_window(entries: &[Entry], window_cycles: i64) -> Option<DensestWindow> {
+    if entries.is_empty() || window_cycles <= 0 {
+        return None;
+    }
+
+    let mut best: Option<DensestWindow> = None;
+    let mut hi = 0;
+    for lo in 0..entries.len() {
+        let start = entries[lo].clk.get();
+        // Later entries of the same clk start the same window with fewer entries.
+        if lo > 0 && entries[lo - 1].clk.get() == start {
+            continue;
+        }
+
+        let end = start as i128 + window_cycles as i128;
+        while hi < entries.len() && (entries[hi].clk.get() as i128) < end {
+            hi += 1;
+        }
+
+        let count = (hi - lo) as u64;
+        if best.map_or(true, |best| count > best.count) {
+            best = Some(DensestWindow {
+                clk_start: start,
+                first: lo as u64,
+                end: hi as u64,
+                count,
+            });
+        }
+    }
+
+    best
+}
+
+/// Number of commands per time bucket, as `(clk_start, count)` for every bucket including empty ones.
+pub fn bucket_counts(entries: &[Entry], num_buckets: usize) -> Vec<(i64, u64)> {
+    if num_buckets == 0 {
+        return Vec::new();
+    }
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+    let mut counts = vec![0u64; num_buckets];
+    for entry in entries {
+        counts[buckets.index(entry.clk.get())] += 1;
+    }
+
+    counts
+        .into_iter()
+        .enumerate()
+        .map(|(i, count)| (buckets.start(i), count))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OccupancyBucket {
+    pub clk_start: i64,
+    /// Fraction of the bucket's cycles covered by at least one command, in `[0, 1]`.
+    pub occupancy: f64,
+}
+
+/// Per time bucket, the fraction of cycles during which some command is in flight, where a command
+/// occupies `[clk, clk + period)`. Overlapping commands count once: the intervals are merged into
+/// their union first (entries are clk-sorted, so one sweep suffices) and then split over the buckets.
+pub fn occupancy(
+    entries: &[Entry],
+    num_buckets: usize,
+    periods: &[f32; 256],
+) -> Vec<OccupancyBucket> {
+    if num_buckets == 0 {
+        return Vec::new();
+    }
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+    let mut covered = vec![0f64; num_buckets];
+
+    let mut add_interval = |start: f64, end: f64| {
+        for (i, cycles) in buckets.overlaps(start, end) {
+            covered[i] += cycles;
+        }
+    };
+
+    let mut current: Option<(f64, f64)> = None;
+    for entry in entries {
+        let start = entry.clk.get() as f64;
+        let end = start + periods[entry.cmd_id as usize] as f64;
+
+        current = match current {
+            Some((cur_start, cur_end)) if start <= cur_end => Some((cur_start, cur_end.max(end))),
+            Some((cur_start, cur_end)) => {
+                add_interval(cur_start, cur_end);
+                Some((start, end))
+            }
+            None => Some((start, end)),
+        };
+    }
+    if let Some((start, end)) = current {
+        add_interval(start, end);
+    }
+
+    covered
+        .iter()
+        .enumerate()
+        .map(|(i, cycles)| OccupancyBucket {
+            clk_start: buckets.start(i),
+            occupancy: (cycles / buckets.width as f64).clamp(0.0, 1.0),
+        })
+        .collect()
+}
+
+/// Union of `[clk, clk + period)` over the entries in `commands`, clipped to `[lo, hi)` and in
+/// clk order.
+fn busy_intervals(
+    entries: &[Entry],
+    commands: &CommandSet,
+    periods: &[f32; 256],
+    lo: f64,
+    hi: f64,
+) -> Vec<(f64, f64)> {
+    let mut intervals: Vec<(f64, f64)> = Vec::new();
+
+    for entry in entries.iter().filter(|e| commands.contains(e.cmd_id)) {
+        let start = (entry.clk.get() as f64).max(lo);
+        let end = (entry.clk.get() as f64 + periods[entry.cmd_id as usize] as f64).min(hi);
+        if end <= start {
+            continue;
+        }
+
+        match intervals.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => intervals.push((start, end)),
+        }
+    }
+
+    intervals
+}
+
+fn total_length(intervals: &[(f64, f64)]) -> f64 {
+    intervals.iter().map(|(start, end)| end - start).sum()
+}
+
+/// Length of the overlap of two sorted, disjoint interval lists.
+fn overlap_length(a: &[(f64, f64)], b: &[(f64, f64)]) -> f64 {
+    let (mut i, mut j) = (0, 0);
+    let mut overlap = 0.0;
+
+    while i < a.len() && j < b.len() {
+        let start = a[i].0.max(b[j].0);
+        let end = a[i].1.min(b[j].1);
+        if end > start {
+            overlap += end - start;
+        }
+        if a[i].1 < b[j].1 {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    overlap
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Efficiency {
+    /// `useful_cycles / (span_cycles - refresh_cycles)`, `None` if refreshes cover the whole span.
+    pub efficiency: Option<f64>,
+    pub span_cycles: f64,
+    pub refresh_cycles: f64,
+    pub useful_cycles: f64,
+}
+
+/// The fraction of the cycles not spent refreshing during which a data command is in flight.
+///
+/// With the span `[first clk, last clk)` of the trace and every command occupying
+/// `[clk, clk + period)` clipped to it:
+/// - `refresh_cycles` is the length of the union of all refresh intervals,
+/// - `useful_cycles` is the length of the union of all `accesses` intervals minus its overlap
+///   with the refresh union,
+/// - `efficiency = useful_cycles / (span_cycles - refresh_cycles)`.
+///
+/// Overlapping commands count once, as in `occupancy`.
+pub fn efficiency(
+    entries: &[Entry],
+    accesses: &CommandSet,
+    refreshes: &CommandSet,
+    periods: &[f32; 256],
+) -> Efficiency {
+    let (min, max) = clk_bounds(entries).unwrap_or((0, 0));
+    let (lo, hi) = (min as f64, max as f64);
+
+    let refresh = busy_intervals(entries, refreshes, periods, lo, hi);
+    let useful = busy_intervals(entries, accesses, periods, lo, hi);
+
+    let span_cycles = hi - lo;
+    let refresh_cycles = total_length(&refresh);
+    let useful_cycles = total_length(&useful) - overlap_length(&useful, &refresh);
+
+    let available = span_cycles - refresh_cycles;
+    Efficiency {
+        efficiency: (available > 0.0).then(|| (useful_cycles / available).clamp(0.0, 1.0)),
+        span_cycles,
+        refresh_cycles,
+        useful_cycles,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContentionBucket {
+    pub clk_start: i64,
+    /// Flat bank index (see `MemoryLayout::flat_bank_index`) with the most commands.
+    pub bank: u32,
+    pub count: u64,
+}
+
+/// The bank with the most commands per time bucket. Ties resolve to the lowest bank index;
+/// empty buckets report `NO_BANK` with a count of 0. Entries outside the layout are ignored.
+pub fn bank_contention(
+    entries: &[Entry],
+    num_buckets: usize,
+    layout: &MemoryLayout,
+) -> Vec<ContentionBucket> {
+    if num_buckets == 0 {
+        return Vec::new();
+    }
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+    let mut counts: Vec<HashMap<usize, u64>> = vec![HashMap::new(); num_buckets];
+
+    for entry in entries {
+        if let Some(bank) = layout.flat_bank_index(entry) {
+            *counts[buckets.index(entry.clk.get())]
+                .entry(bank)
+                .or_insert(0) += 1;
+        }
+    }
+
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, bucket)| {
+            let (bank, count) = bucket
+                .iter()
+                .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+                .map_or((NO_BANK, 0), |(bank, count)| (*bank as u32, *count));
+
+            ContentionBucket {
+                clk_start: buckets.start(i),
+                bank,
+                count,
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankLoadImbalance {
+    /// Number of banks in the layout, i.e. the population size.
+    pub banks: usize,
+    pub mean: f64,
+    pub std_dev: f64,
+    /// `std_dev / mean`, 0 for a perfectly balanced trace. `None` if no entry maps to a bank.
+    pub coefficient_of_variation: Option<f64>,
+}
+
+/// How unevenly the commands are spread over the banks: the coefficient of variation of the
+/// per-bank command counts. The population is every bank of the layout, so banks that are never
+/// accessed count as 0 and raise the imbalance. Entries outside the layout are ignored.
+pub fn bank_load_imbalance(entries: &[Entry], layout: &MemoryLayout) -> BankLoadImbalance {
+    let mut counts = vec![0u64; layout.flat_bank_count()];
+    for entry in entries {
+        if let Some(bank) = layout.flat_bank_index(entry) {
+            counts[bank] += 1;
+        }
+    }
+
+    let banks = counts.len();
+    let n = banks.max(1) as f64;
+    let mean = counts.iter().sum::<u64>() as f64 / n;
+    let variance = counts
+        .iter()
+        .map(|count| (*count as f64 - mean).powi(2))
+        .sum::<f64>()
+        / n;
+    let std_dev = variance.sqrt();
+
+    BankLoadImbalance {
+        banks,
+        mean,
+        std_dev,
+        coefficient_of_variation: (mean > 0.0).then(|| std_dev / mean),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressCoverage {
+    pub touched_banks: u64,
+    /// Every bank of the layout, touched or not.
+    pub total_banks: u64,
+    /// `touched_banks / total_banks`, 0 for an empty layout.
+    pub bank_coverage: f64,
+}
+
+/// How much of the layout's address space the trace touches. The layout has no row count, so
+/// coverage is only measured down to banks. Entries outside the layout are ignored.
+pub fn address_coverage(entries: &[Entry], layout: &MemoryLayout) -> AddressCoverage {
+    let mut touched = vec![false; layout.flat_bank_count()];
+    for entry in entries {
+        if let Some(bank) = layout.flat_bank_index(entry) {
+            touched[bank] = true;
+        }
+    }
+
+    let touched_banks = touched.iter().filter(|t| **t).count() as u64;
+    let total_banks = touched.len() as u64;
+    AddressCoverage {
+        touched_banks,
+        total_banks,
+        bank_coverage: touched_banks as f64 / total_banks.max(1) as f64,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankLifetime {
+    /// Flat bank index, see `MemoryLayout::flat_bank_index`.
+    pub index: u32,
+    pub channel: i16,
+    pub bankgroup: i32,
+    pub bank: i32,
+    pub first_clk: i64,
+    pub last_clk: i64,
+    pub commands: u64,
+}
+
+/// Clk of the first and last command of every bank that has one, by flat bank index. Entries
+/// are clk-sorted, so that is simply the first and last entry seen per bank. Entries outside the
+/// layout are skipped.
+pub fn bank_lifetimes(entries: &[Entry], layout: &MemoryLayout) -> Vec<BankLifetime> {
+    let mut lifetimes: Vec<Option<BankLifetime>> = vec![None; layout.flat_bank_count()];
+
+    for entry in entries {
+        let Some(index) = layout.flat_bank_index(entry) else {
+            continue;
+        };
+        let clk = entry.clk.get();
+
+        let lifetime = lifetimes[index].get_or_insert(BankLifetime {
+            index: index as u32,
+            channel: entry.channel.get(),
+            bankgroup: entry.bankgroup.get(),
+            bank: entry.bank.get(),
+            first_clk: clk,
+            last_clk: clk,
+            commands: 0,
+        });
+        lifetime.last_clk = clk;
+        lifetime.commands += 1;
+    }
+
+    lifetimes.into_iter().flatten().collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConcurrencyBucket {
+    pub clk_start: i64,
+    /// Time-weighted average number of commands in flight.
+    pub avg: f64,
+    pub max: u64,
+}
+
+/// Number of commands in flight over time as a queue-depth proxy, where a command is in flight
+/// during `[clk, clk + period)`. A sweep over the +1 (start) and -1 (end) events in clk order;
+/// at equal clks ends go first, so back-to-back commands don't count as overlapping.
+pub fn concurrency(
+    entries: &[Entry],
+    num_buckets: usize,
+    periods: &[f32; 256],
+) -> Vec<ConcurrencyBucket> {
+    if num_buckets == 0 {
+        return Vec::new();
+    }
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+    let mut area = vec![0f64; num_buckets];
+    let mut max = vec![0u64; num_buckets];
+
+    // Commands without a positive period are never in flight. Starts are already sorted since
+    // the entries are; ends are not once periods differ.
+    let in_flight = entries.iter().filter(|e| periods[e.cmd_id as usize] > 0.0);
+    let starts: Vec<f64> = in_flight.clone().map(|e| e.clk.get() as f64).collect();
+    let mut ends: Vec<f64> = in_flight
+        .map(|e| e.clk.get() as f64 + periods[e.cmd_id as usize] as f64)
+        .collect();
+    ends.sort_by(f64::total_cmp);
+
+    let (mut next_start, mut next_end) = (0, 0);
+    let mut level = 0u64;
+    let mut time = starts.first().copied().unwrap_or(0.0);
+
+    while next_end < ends.len() {
+        let is_end = next_start == starts.len() || ends[next_end] <= starts[next_start];
+        let event_time = if is_end {
+            ends[next_end]
+        } else {
+            starts[next_start]
+        };
+
+        if level > 0 {
+            for (i, cycles) in buckets.overlaps(time, event_time) {
+                area[i] += level as f64 * cycles;
+                max[i] = max[i].max(level);
+            }
+        }
+        time = event_time;
+
+        if is_end {
+            level -= 1;
+            next_end += 1;
+        } else {
+            level += 1;
+            next_start += 1;
+        }
+    }
+
+    area.iter()
+        .zip(&max)
+        .enumerate()
+        .map(|(i, (area, max))| ConcurrencyBucket {
+            clk_start: buckets.start(i),
+            avg: area / buckets.width as f64,
+            max: *max,
+        })
+        .collect()
+}
+
+/// Length of the time series `dominant_period` correlates. The autocorrelation is quadratic in it.
+pub const PERIOD_SERIES_LEN: usize = 4096;
+
+/// Minimum autocorrelation for a lag to count as a period.
+const MIN_PERIOD_CONFIDENCE: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DominantPeriod {
+    pub period_cycles: i64,
+    /// Normalized autocorrelation at the period, in (0, 1].
+    pub confidence: f64,
+}
+
+/// The strongest repeating period of the command stream, e.g. a loop iteration.
+///
+/// The trace is binned into at most `PERIOD_SERIES_LEN` equally wide buckets of command counts.
+/// For every lag up to half the series, the mean-removed autocorrelation is normalized by the
+/// series' variance, and the highest local maximum is the period. Only local maxima count, since
+/// the autocorrelation of any smooth series is highest at the smallest lags. `None` if the series
+/// is constant or no lag reaches a correlation of 0.3.
+pub fn dominant_period(entries: &[Entry]) -> Option<DominantPeriod> {
+    let (min, max) = clk_bounds(entries)?;
+    let span = (max as i128 - min as i128 + 1) as u128;
+    let buckets = TimeBuckets::over(min, max, span.min(PERIOD_SERIES_LEN as u128) as usize);
+
+    let mut series = vec![0f64; buckets.count];
+    for entry in entries {
+        series[buckets.index(entry.clk.get())] += 1.0;
+    }
+
+    let mean = series.iter().sum::<f64>() / series.len() as f64;
+    for value in &mut series {
+        *value -= mean;
+    }
+    let variance: f64 = series.iter().map(|v| v * v).sum();
+    if variance <= 0.0 {
+        return None;
+    }
+
+    let correlation: Vec<f64> = (0..=series.len() / 2)
+        .map(|lag| {
+            series
+                .iter()
+                .zip(&series[lag..])
+                .map(|(a, b)| a * b)
+                .sum::<f64>()
+                / variance
+        })
+        .collect();
+
+    (1..correlation.len().saturating_sub(1))
+        .filter(|&lag| {
+            correlation[lag] > correlation[lag - 1] && correlation[lag] >= correlation[lag + 1]
+        })
+        .max_by(|&a, &b| correlation[a].total_cmp(&correlation[b]))
+        .filter(|&lag| correlation[lag] >= MIN_PERIOD_CONFIDENCE)
+        .map(|lag| DominantPeriod {
+            period_cycles: lag as i64 * buckets.width,
+            confidence: correlation[lag].min(1.0),
+        })
+}
+
+/// Positions of at most `max_points` entries representing `entries` at a coarser zoom: the clk
+/// range is split into `max_points / 2` buckets and each non-empty one is represented by its first
+/// and last entry. Unlike every-n-th sampling, a burst confined to one bucket always survives and
+/// keeps its extent, and empty stretches stay empty. Windows that already fit are returned in full.
+pub fn minmax_decimate(entries: &[Entry], max_points: usize) -> Vec<usize> {
+    if entries.len() <= max_points {
+        return (0..entries.len()).collect();
+    }
+
+    let num_buckets = max_points / 2;
+    if num_buckets == 0 {
+        return Vec::new();
+    }
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+    let mut picked = Vec::with_capacity(num_buckets * 2);
+    let mut current: Option<(usize, usize, usize)> = None;
+
+    for (position, entry) in entries.iter().enumerate() {
+        let bucket = buckets.index(entry.clk.get());
+        current = match current {
+            Some((b, first, _)) if b == bucket => Some((b, first, position)),
+            Some((_, first, last)) => {
+                picked.push(first);
+                if last != first {
+                    picked.push(last);
+                }
+                Some((bucket, position, position))
+            }
+            None => Some((bucket, position, position)),
+        };
+    }
+    if let Some((_, first, last)) = current {
+        picked.push(first);
+        if last != first {
+            picked.push(last);
+        }
+    }
+
+    picked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bank_activity_bitset_sets_bits_of_active_banks() {
+        let layout = MemoryLayout {
+            num_channels: 1,
+            num_bankgroups: 2,
+            num_banks: 5,
+        };
+        let entries = [
+            Entry::new(0, [0, 0, 0, 0, 7, -1], 0),
+            Entry::new(5, [0, 0, 1, 4, 7, -1], 0),
+            Entry::new(5, [0, 0, 1, 4, 8, -1], 1),
+            Entry::new(12, [0, 0, 0, 3, 7, -1], 0),
+            Entry::new(15, [1, 0, 0, 0, 7, -1], 0),
+            Entry::new(19, [0, 0, 0, -1, -1, -1], 2),
+        ];
+
+        // 10 banks need 2 bytes per bucket; the buckets are [0, 10) and [10, 20).
+        let bits = bank_activity_bitset(&entries, 2, &layout).unwrap();
+        assert_eq!(bits, [0b0000_0001, 0b0000_0010, 0b0000_1000, 0]);
+
+        // A byte size that overflows is refused instead of wrapping to a short buffer.
+        assert_eq!(bank_activity_bitset(&entries, usize::MAX, &layout), None);
+    }
+
+    #[test]
+    fn bank_activity_bitset_of_empty_layout_is_empty() {
+        let layout = MemoryLayout {
+            num_channels: 2,
+            num_bankgroups: 0,
+            num_banks: 4,
+        };
+        let entries = [Entry::new(3, [0, 0, 0, 0, 0, 0], 0)];
+
+        assert_eq!(bank_activity_bitset(&entries, 4, &layout), Some(Vec::new()));
+        assert_eq!(bank_activity_bitset(&entries, 0, &layout), Some(Vec::new()));
+    }
+
+    #[test]
+    fn issue_rate_normalizes_the_partial_last_window() {
+        let entries: Vec<Entry> = [100, 101, 105, 130, 134]
+            .into_iter()
+            .map(|clk| Entry::new(clk, [0; 6], 0))
+            .collect();
+
+        let windows = issue_rate(&entries, 10);
+        let summary: Vec<(i64, i64, u64)> = windows
+            .iter()
+            .map(|w| (w.clk_start, w.cycles, w.count))
+            .collect();
+        assert_eq!(
+            summary,
+            [(100, 10, 3), (110, 10, 0), (120, 10, 0), (130, 5, 2)]
+        );
+        assert_eq!(windows[0].rate, 300.0);
+        assert_eq!(windows[3].rate, 400.0);
+        assert_eq!(issue_window_count(100, 134, 10), 4);
+    }
+
+    #[test]
+    fn issue_window_count_flags_tiny_windows_over_long_traces() {
+        assert_eq!(issue_window_count(i64::MIN, i64::MAX, 1), u64::MAX);
+        assert!(issue_window_count(0, 1 << 30, 64) > MAX_ISSUE_WINDOWS);
+        assert_eq!(issue_window_count(0, 0, 1), 1);
+        assert_eq!(issue_window_count(0, 9, 0), 0);
+        assert!(issue_rate(&[Entry::new(0, [0; 6], 0)], -5).is_empty());
+    }
+
+    #[test]
+    fn rolling_windows_overlap_and_end_partial() {
+        let entries: Vec<Entry> = [0, 1, 2, 5, 6, 11]
+            .into_iter()
+            .map(|clk| Entry::new(clk, [0; 6], 0))
+            .collect();
+
+        let windows = rolling_issue_rate(&entries, 4, 2);
+        let summary: Vec<(i64, i64, u64)> = windows
+            .iter()
+            .map(|w| (w.clk_start, w.cycles, w.count))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (0, 4, 3),
+                (2, 4, 2),
+                (4, 4, 2),
+                (6, 4, 1),
+                (8, 4, 1),
+                (10, 2, 1)
+            ]
+        );
+        assert_eq!(windows[5].rate, 500.0);
+        assert!(rolling_issue_rate(&entries, 4, 0).is_empty());
+        assert!(rolling_issue_rate(&[], 4, 2).is_empty());
+    }
+
+    #[test]
+    fn rolling_window_count_saturates() {
+        assert_eq!(rolling_window_count(0, 11, 2), 6);
+        assert_eq!(rolling_window_count(i64::MIN, i64::MAX, 1), u64::MAX);
+        assert_eq!(rolling_window_count(0, 9, -1), 0);
+    }
+
+    #[test]
+    fn minmax_decimation_keeps_a_burst_stride_sampling_drops() {
+        // Evenly spaced RDs with an idle stretch holding a short burst of ACTs.
+        let mut entries: Vec<Entry> = (0..5000).map(|i| Entry::new(i * 10, [0; 6], 0)).collect();
+        entries.extend((0..5).map(|i| Entry::new(60_000 + i, [0; 6], 1)));
+        entries.extend((0..4000).map(|i| Entry::new(70_000 + i * 10, [0; 6], 0)));
+        let max_points = 40;
+
+        let stride = entries.len() / max_points;
+        assert!(!(0..entries.len())
+            .step_by(stride)
+            .any(|i| entries[i].cmd_id == 1));
+
+        let picked = minmax_decimate(&entries, max_points);
+        assert!(picked.len() <= max_points);
+        assert!(picked.windows(2).all(|w| w[0] < w[1]));
+        let burst: Vec<usize> = picked
+            .into_iter()
+            .filter(|&i| entries[i].cmd_id == 1)
+            .collect();
+        assert_eq!(burst, [5000, 5004]);
+    }
+
+    #[test]
+    fn minmax_decimation_returns_small_windows_in_full() {
+        let entries: Vec<Entry> = (0..10).map(|i| Entry::new(i, [0; 6], 0)).collect();
+
+        assert_eq!(minmax_decimate(&entries, 10), (0..10).collect::<Vec<_>>());
+        assert!(minmax_decimate(&entries, 1).is_empty());
+    }
+
+    #[test]
+    fn densest_window_finds_the_burst() {
+        let entries: Vec<Entry> = [0, 10, 20, 21, 22, 22, 24, 40, 41]
+            .into_iter()
+            .map(|clk| Entry::new(clk, [0; 6], 0))
+            .collect();
+
+        assert_eq!(
+            densest_window(&entries, 5),
+            Some(DensestWindow {
+                clk_start: 20,
+                first: 2,
+                end: 7,
+                count: 5
+            })
+        );
+        assert_eq!(densest_window(&entries, 1).map(|w| w.clk_start), Some(22));
+        // Ties keep the earliest window.
+        assert_eq!(
+            densest_window(&entries[..2], 3).map(|w| w.clk_start),
+            Some(0)
+        );
+        assert_eq!(densest_window(&entries, 1000).map(|w| w.count), Some(9));
+    }
+
+    #[test]
+    fn densest_window_needs_entries_and_a_length() {
+        assert_eq!(densest_window(&[], 5), None);
+        assert_eq!(densest_window(&[Entry::new(0, [0; 6], 0)], 0), None);
+        let last = Entry::new(i64::MAX, [0; 6], 0);
+        assert_eq!(densest_window(&[last], i64::MAX).map(|w| w.count), Some(1));
+    }
+
+    #[test]
+    fn interleaving_counts_distinct_banks_per_window() {
+        let on = |clk: i64, bank: i64| Entry::new(clk, [0, 0, 0, bank, 3, -1], 0);
+        let serial: Vec<Entry> = (0..8).map(|clk| on(clk, 2)).collect();
+        let spread: Vec<Entry> = (0..8).map(|clk| on(clk, clk % 4)).collect();
+
+        let score = interleaving_score(&serial, 4);
+        assert_eq!((score.windows, score.mean_distinct_banks), (5, Some(1.0)));
+        assert_eq!(
+            interleaving_score(&spread, 4).mean_distinct_banks,
+            Some(4.0)
+        );
+
+        // Banks 0 0 1 1 0: windows of 3 hold {0, 1} three times.
+        let mixed = [on(0, 0), on(1, 0), on(2, 1), on(3, 1), on(4, 0)];
+        assert_eq!(interleaving_score(&mixed, 3).mean_distinct_banks, Some(2.0));
+    }
+
+    #[test]
+    fn interleaving_skips_all_bank_commands_and_short_traces() {
+        let on = |clk: i64, bank: i64| Entry::new(clk, [0, 0, 0, bank, 3, -1], 0);
+        let refresh = |clk: i64| Entry::new(clk, [0, 0, -1, -1, -1, -1], 1);
+        let entries = [on(0, 0), refresh(1), on(2, 1), refresh(3)];
+
+        let score = interleaving_score(&entries, 2);
+        assert_eq!((score.windows, score.mean_distinct_banks), (1, Some(2.0)));
+        assert_eq!(interleaving_score(&entries, 3).mean_distinct_banks, None);
+        assert_eq!(interleaving_score(&entries, 0).windows, 0);
+    }
+
+    #[test]
+    fn dominant_command_per_bucket_with_ties_to_the_lowest_id() {
+        let at = |clk, cmd_id| Entry::new(clk, [0, 1, 0, 0, 40, 2], cmd_id);
+        let entries = [
+            at(0, 5),
+            at(2, 3),
+            at(3, 5),
+            // A tie between 7 and 4 goes to 4.
+            at(11, 7),
+            at(12, 4),
+            at(14, 7),
+            at(18, 4),
+            // Nothing in [20, 30).
+            at(39, 9),
+        ];
+
+        let buckets = dominant_commands(&entries, 4);
+        let summary: Vec<(i64, u8, u64)> = buckets
+            .iter()
+            .map(|b| (b.clk_start, b.dominant_cmd_id, b.count))
+            .collect();
+        assert_eq!(
+            summary,
+            [(0, 5, 3), (10, 4, 4), (20, NO_COMMAND, 0), (30, 9, 1)]
+        );
+        assert!(dominant_commands(&entries, 0).is_empty());
+    }
+
+    #[test]
+    fn rw_ratio_per_bucket() {
+        const RD: u8 = 1;
+        const WR: u8 = 2;
+        const REF: u8 = 6;
+        let entries: Vec<Entry> = [(0, RD), (1, RD), (3, WR), (4, REF), (8, REF), (11, WR)]
+            .iter()
+            .map(|&(clk, cmd_id)| Entry::new(clk, [1, 1, 0, 2, 90, 0], cmd_id))
+            .collect();
+
+        let buckets = rw_ratio(
+            &entries,
+            3,
+            &CommandSet::from_ids([RD]),
+            &CommandSet::from_ids([WR]),
+        );
+        let summary: Vec<(i64, u64, u64, Option<f64>)> = buckets
+            .iter()
+            .map(|b| (b.clk_start, b.reads, b.writes, b.ratio))
+            .collect();
+        // A bucket with only a refresh has no ratio rather than 0.
+        assert_eq!(
+            summary,
+            [
+                (0, 2, 1, Some(2.0 / 3.0)),
+                (4, 0, 0, None),
+                (8, 0, 1, Some(0.0))
+            ]
+        );
+    }
+
+    #[test]
+    fn bankgroup_parallelism_counts_distinct_groups() {
+        let on = |clk, channel, bankgroup| Entry::new(clk, [channel, 0, bankgroup, 1, 4, 0], 2);
+        let entries = [
+            on(0, 0, 0),
+            on(1, 0, 1),
+            on(2, 0, 1),
+            on(3, 0, 3),
+            // The same bank group value on another channel is not a new group.
+            on(5, 1, 0),
+            on(6, 0, -1),
+            on(11, 0, 2),
+        ];
+
+        let distinct: Vec<(i64, u64)> = bankgroup_parallelism(&entries, 3)
+            .iter()
+            .map(|b| (b.clk_start, b.distinct_bankgroups))
+            .collect();
+        assert_eq!(distinct, [(0, 3), (4, 1), (8, 1)]);
+        assert!(bankgroup_parallelism(&entries, 0).is_empty());
+    }
+
+    #[test]
+    fn occupancy_merges_overlapping_commands() {
+        let mut periods = [1.0; 256];
+        periods[0] = 6.0;
+        periods[1] = 2.0;
+        // Buckets of 10 cycles over [0, 20). The ACT at 0 covers [0, 6) and swallows the RD at 3;
+        // the RD at 5 extends the busy span to 7. The RD at 19 spills past the last bucket.
+        let entries = [
+            Entry::new(0, [0, 0, 0, 1, 8, -1], 0),
+            Entry::new(3, [0, 0, 0, 1, 8, 0], 1),
+            Entry::new(5, [0, 0, 0, 1, 8, 4], 1),
+            Entry::new(12, [0, 0, 0, 1, 8, 8], 1),
+            Entry::new(19, [0, 0, 0, 1, 8, 12], 1),
+        ];
+
+        let occupancy: Vec<(i64, f64)> = occupancy(&entries, 2, &periods)
+            .iter()
+            .map(|b| (b.clk_start, b.occupancy))
+            .collect();
+        assert_eq!(occupancy, [(0, 0.7), (10, 0.3)]);
+    }
+
+    #[test]
+    fn most_contended_bank_per_bucket() {
+        let layout = MemoryLayout {
+            num_channels: 2,
+            num_bankgroups: 2,
+            num_banks: 2,
+        };
+        let bank =
+            |clk, channel, bankgroup, bank| Entry::new(clk, [channel, 0, bankgroup, bank, 1, 0], 1);
+        let entries = [
+            bank(0, 1, 1, 0),
+            bank(2, 1, 1, 0),
+            bank(4, 0, 0, 1),
+            // Ties between flat banks 3 and 5 go to 3; the out-of-layout entry is ignored.
+            bank(10, 1, 0, 1),
+            bank(11, 0, 1, 1),
+            bank(12, 0, 3, 0),
+            bank(25, 0, 0, -1),
+            bank(29, 0, 0, 0),
+        ];
+
+        let contention: Vec<(i64, u32, u64)> = bank_contention(&entries, 3, &layout)
+            .iter()
+            .map(|b| (b.clk_start, b.bank, b.count))
+            .collect();
+        assert_eq!(contention, [(0, 6, 2), (10, 3, 1), (20, 0, 1)]);
+
+        let idle = bank_contention(&entries[6..7], 1, &layout);
+        assert_eq!((idle[0].bank, idle[0].count), (NO_BANK, 0));
+    }
+
+    #[test]
+    fn concurrency_sweeps_overlapping_commands() {
+        let mut periods = [0.0; 256];
+        periods[0] = 10.0;
+        periods[1] = 4.0;
+        let entries = [
+            Entry::new(0, [0, 0, 0, 0, 3, -1], 0),
+            Entry::new(2, [0, 0, 0, 1, 3, 0], 1),
+            Entry::new(3, [0, 0, 0, 2, 3, 0], 1),
+            // Starts exactly when the first command ends, so it doesn't overlap it.
+            Entry::new(10, [0, 0, 0, 0, 3, 0], 1),
+            // A zero period is never in flight.
+            Entry::new(19, [0, 0, 0, -1, -1, -1], 2),
+        ];
+
+        let buckets: Vec<(i64, f64, u64)> = concurrency(&entries, 2, &periods)
+            .iter()
+            .map(|b| (b.clk_start, b.avg, b.max))
+            .collect();
+        assert_eq!(buckets, [(0, 1.8, 3), (10, 0.4, 1)]);
+    }
+
+    #[test]
+    fn dominant_period_of_periodic_bursts() {
+        // Three commands at the start of every 50-cycle iteration.
+        let entries: Vec<Entry> = (0..40)
+            .flat_map(|iteration| (0..3).map(move |i| iteration * 50 + i))
+            .map(|clk| Entry::new(clk, [0, 0, 1, 0, 12, 0], 1))
+            .collect();
+
+        let period = dominant_period(&entries).unwrap();
+        assert_eq!(period.period_cycles, 50);
+        assert!(period.confidence > 0.9 && period.confidence <= 1.0);
+    }
+
+    #[test]
+    fn steady_streams_have_no_period() {
+        let steady: Vec<Entry> = (0..500)
+            .map(|clk| Entry::new(clk, [0, 0, 1, 0, 12, 0], 1))
+            .collect();
+
+        assert_eq!(dominant_period(&steady), None);
+        assert_eq!(dominant_period(&[]), None);
+    }
+
+    #[test]
+    fn efficiency_excludes_refresh_cycles_from_both_sides() {
+        let (act, rd, wr, refresh) = (0, 2, 3, 4);
+        let mut periods = [1.0; 256];
+        periods[rd as usize] = 4.0;
+        periods[wr as usize] = 4.0;
+        periods[refresh as usize] = 10.0;
+        // Over the span [0, 40): the reads at 0 and 2 merge into [0, 6), the refresh holds
+        // [10, 20) and hides half of the read at 18, and the write covers [30, 34).
+        let entries = [
+            Entry::new(0, [0, 0, 0, 0, 3, 0], rd),
+            Entry::new(2, [0, 0, 0, 0, 3, 8], rd),
+            Entry::new(10, [0, 0, -1, -1, -1, -1], refresh),
+            Entry::new(18, [0, 0, 1, 0, 5, 0], rd),
+            Entry::new(30, [0, 0, 1, 0, 5, 0], wr),
+            Entry::new(40, [0, 0, 1, 2, 9, -1], act),
+        ];
+        let accesses = CommandSet::from_ids([rd, wr]);
+        let refreshes = CommandSet::from_ids([refresh]);
+
+        let result = efficiency(&entries, &accesses, &refreshes, &periods);
+        assert_eq!(result.span_cycles, 40.0);
+        assert_eq!(result.refresh_cycles, 10.0);
+        assert_eq!(result.useful_cycles, 6.0 + 2.0 + 4.0);
+        assert_eq!(result.efficiency, Some(12.0 / 30.0));
+
+        // Nothing is left to be efficient with once refreshes cover the span.
+        let always_refreshing = [entries[2], Entry::new(20, [0, 0, 1, 0, 5, 0], rd)];
+        let result = efficiency(&always_refreshing, &accesses, &refreshes, &periods);
+        assert_eq!(result.refresh_cycles, result.span_cycles);
+        assert_eq!(result.efficiency, None);
+    }
+
+    #[test]
+    fn imbalance_counts_untouched_banks_as_zero() {
+        let layout = MemoryLayout {
+            num_channels: 1,
+            num_bankgroups: 2,
+            num_banks: 2,
+        };
+        let to_bank = |clk, bankgroup, bank| Entry::new(clk, [0, 0, bankgroup, bank, 1, 0], 2);
+
+        let even: Vec<Entry> = (0..8).map(|i| to_bank(i, i % 2, i / 4)).collect();
+        let balanced = bank_load_imbalance(&even, &layout);
+        assert_eq!((balanced.banks, balanced.mean), (4, 2.0));
+        assert_eq!(balanced.coefficient_of_variation, Some(0.0));
+
+        // Counts [4, 0, 2, 2]; the refresh and the bank outside the layout don't count.
+        let mut skewed: Vec<Entry> = (0..4).map(|i| to_bank(i, 0, 0)).collect();
+        skewed.extend([to_bank(4, 1, 0), to_bank(5, 1, 0), to_bank(6, 1, 1)]);
+        skewed.extend([to_bank(7, 1, 1), to_bank(8, 0, 5)]);
+        skewed.push(Entry::new(9, [0, 0, -1, -1, -1, -1], 4));
+        let imbalance = bank_load_imbalance(&skewed, &layout);
+        assert_eq!(imbalance.mean, 2.0);
+        assert_eq!(imbalance.std_dev, 2f64.sqrt());
+        assert_eq!(imbalance.coefficient_of_variation, Some(2f64.sqrt() / 2.0));
+
+        assert_eq!(
+            bank_load_imbalance(&[], &layout).coefficient_of_variation,
+            None
+        );
+    }
+
+    #[test]
+    fn lifetimes_span_each_bank_first_to_last_command() {
+        let layout = MemoryLayout {
+            num_channels: 2,
+            num_bankgroups: 2,
+            num_banks: 4,
+        };
+        let entries = [
+            Entry::new(3, [1, 0, 0, 2, 9, -1], 0),
+            Entry::new(8, [0, 0, 1, 1, 4, -1], 0),
+            Entry::new(15, [1, 0, 0, 2, 9, 0], 2),
+            Entry::new(20, [0, 0, -1, -1, -1, -1], 4),
+            Entry::new(24, [0, 0, 1, 9, 4, 0], 2),
+            Entry::new(31, [1, 0, 0, 2, 9, -1], 1),
+        ];
+
+        assert_eq!(
+            bank_lifetimes(&entries, &layout),
+            [
+                BankLifetime {
+                    index: 5,
+                    channel: 0,
+                    bankgroup: 1,
+                    bank: 1,
+                    first_clk: 8,
+                    last_clk: 8,
+                    commands: 1,
+                },
+                BankLifetime {
+                    index: 10,
+                    channel: 1,
+                    bankgroup: 0,
+                    bank: 2,
+                    first_clk: 3,
+                    last_clk: 31,
+                    commands: 3,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn coverage_counts_each_touched_bank_once() {
+        let layout = MemoryLayout {
+            num_channels: 1,
+            num_bankgroups: 4,
+            num_banks: 4,
+        };
+        // Three distinct banks, one of them hit twice, plus a refresh and an out-of-layout bank.
+        let entries = [
+            Entry::new(0, [0, 0, 0, 0, 12, -1], 0),
+            Entry::new(2, [0, 0, 0, 0, 12, 4], 2),
+            Entry::new(5, [0, 0, 2, 1, 60, -1], 0),
+            Entry::new(6, [0, 0, 3, 3, 8, -1], 0),
+            Entry::new(9, [0, 0, -1, -1, -1, -1], 4),
+            Entry::new(9, [3, 0, 0, 0, 12, -1], 0),
+        ];
+
+        let coverage = address_coverage(&entries, &layout);
+        assert_eq!((coverage.touched_banks, coverage.total_banks), (3, 16));
+        assert_eq!(coverage.bank_coverage, 3.0 / 16.0);
+
+        let empty_layout = MemoryLayout {
+            num_channels: 0,
+            ..layout
+        };
+        assert_eq!(address_coverage(&entries, &empty_layout).bank_coverage, 0.0);
+    }
+
+    #[test]
+    fn activate_ratio_counts_activations_per_access() {
+        let (act, pre, rd, wr) = (0, 1, 2, 3);
+        let at = |clk, cmd_id| Entry::new(clk, [0, 0, 1, 1, 70, 0], cmd_id);
+        // Buckets of 10 cycles: four reads from one row, then a bare activation, then a
+        // row opened for one read and one write. Precharges are neither.
+        let entries = [
+            at(0, act),
+            at(1, rd),
+            at(2, rd),
+            at(3, rd),
+            at(4, rd),
+            at(12, act),
+            at(15, pre),
+            at(20, act),
+            at(22, rd),
+            at(25, pre),
+            at(29, wr),
+        ];
+        let result = activate_ratio(
+            &entries,
+            3,
+            &CommandSet::from_ids([act]),
+            &CommandSet::from_ids([rd, wr]),
+        );
+
+        let summary = |b: &ActivateBucket| (b.clk_start, b.activates, b.accesses, b.ratio);
+        assert_eq!(
+            result.buckets.iter().map(summary).collect::<Vec<_>>(),
+            [
+                (0, 1, 4, Some(0.25)),
+                (10, 1, 0, None),
+                (20, 1, 2, Some(0.5)),
+            ]
+        );
+        assert_eq!(summary(&result.overall), (0, 3, 6, Some(0.5)));
+    }
+}
@@ -0,0 +1,129 @@
+/// Read-after-write hazards: a read of the exact address (bank, row and column) a write went to
+/// shortly before.
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{BankKey, CommandSet};
+use crate::trace::entry::Entry;
+
+/// Number of entries between two sweeps of stale writes.
+const PRUNE_INTERVAL: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RawHazard {
+    pub wr_index: u64,
+    pub rd_index: u64,
+    /// Cycles from the write to the read.
+    pub delta: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct CellKey {
+    bank: BankKey,
+    row: i32,
+    column: i32,
+}
+
+impl CellKey {
+    fn of(entry: &Entry) -> Self {
+        Self {
+            bank: BankKey::of(entry),
+            row: entry.row.get(),
+            column: entry.column.get(),
+        }
+    }
+}
+
+/// Pairs every read with the latest write to the same address if it lies at most
+/// `window_cycles` back. Several reads of one write each produce a hazard.
+/// Writes older than the window are dropped periodically, bounding the tracked addresses.
+pub fn raw_hazards(
+    entries: &[Entry],
+    window_cycles: i64,
+    writes: &CommandSet,
+    reads: &CommandSet,
+) -> Vec<RawHazard> {
+    let mut recent: HashMap<CellKey, (u64, i64)> = HashMap::new();
+    let mut hazards = Vec::new();
+
+    for (i, entry) in entries.iter().enumerate() {
+        let clk = entry.clk.get();
+
+        if i % PRUNE_INTERVAL == 0 {
+            recent.retain(|_, (_, wr_clk)| clk.saturating_sub(*wr_clk) <= window_cycles);
+        }
+
+        if reads.contains(entry.cmd_id) {
+            if let Some((wr_index, wr_clk)) = recent.get(&CellKey::of(entry)) {
+                let delta = clk.saturating_sub(*wr_clk);
+                if delta <= window_cycles {
+                    hazards.push(RawHazard {
+                        wr_index: *wr_index,
+                        rd_index: i as u64,
+                        delta,
+                    });
+                }
+            }
+        }
+
+        if writes.contains(entry.cmd_id) {
+            recent.insert(CellKey::of(entry), (i as u64, clk));
+        }
+    }
+
+    hazards
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RD: u8 = 1;
+    const WR: u8 = 2;
+
+    fn at(clk: i64, cmd_id: u8, row: i64, column: i64) -> Entry {
+        Entry::new(clk, [0, 0, 1, 3, row, column], cmd_id)
+    }
+
+    fn hazards(entries: &[Entry], window_cycles: i64) -> Vec<(u64, u64, i64)> {
+        raw_hazards(
+            entries,
+            window_cycles,
+            &CommandSet::from_ids([WR]),
+            &CommandSet::from_ids([RD]),
+        )
+        .iter()
+        .map(|h| (h.wr_index, h.rd_index, h.delta))
+        .collect()
+    }
+
+    #[test]
+    fn reads_pair_with_the_latest_write_to_their_address() {
+        let entries = [
+            at(0, WR, 5, 16),
+            at(4, WR, 5, 16),
+            // Same row, other column: no hazard.
+            at(6, RD, 5, 24),
+            at(10, RD, 5, 16),
+            at(12, RD, 5, 16),
+            at(40, RD, 5, 16),
+        ];
+
+        assert_eq!(hazards(&entries, 10), [(1, 3, 6), (1, 4, 8)]);
+        assert_eq!(hazards(&entries, 36), [(1, 3, 6), (1, 4, 8), (1, 5, 36)]);
+        assert!(hazards(&entries, 5).is_empty());
+    }
+
+    #[test]
+    fn sweeps_only_drop_writes_outside_the_window() {
+        let mut entries = vec![at(0, WR, 9, 0)];
+        entries.extend((1..PRUNE_INTERVAL as i64).map(|clk| at(clk, RD, 1, 0)));
+        // Runs into the sweep at index PRUNE_INTERVAL with the write still pending.
+        entries.push(at(100_000, RD, 9, 0));
+
+        assert!(hazards(&entries, 50).is_empty());
+        assert_eq!(hazards(&entries, 100_000).len(), 1);
+    }
+}
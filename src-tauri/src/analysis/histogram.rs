@@ -0,0 +1,376 @@
+/// Occurrence counts of a trace, per command and per column range.
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::CommandSet;
+use crate::trace::entry::Entry;
+
+/// Number of entries per command id, computed in a single scan.
+/// Only ids that actually occur are present in the map.
+pub fn command_counts(entries: &[Entry]) -> BTreeMap<u8, u64> {
+    let mut counts = [0u64; 256];
+    for entry in entries {
+        counts[entry.cmd_id as usize] += 1;
+    }
+
+    counts
+        .iter()
+        .enumerate()
+        .filter(|(_, count)| **count > 0)
+        .map(|(cmd_id, count)| (cmd_id as u8, *count))
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandTime {
+    pub cmd_id: u8,
+    pub count: u64,
+    /// Sum of the configured durations of all occurrences, in cycles.
+    pub total_cycles: f64,
+    pub avg_cycles: f64,
+    /// Fraction of the summed durations of all commands.
+    pub share: f64,
+}
+
+/// Busy time per command that occurs: its count times its duration from `periods`, sorted by id.
+/// Overlapping commands are not merged, see `density::occupancy` for that.
+pub fn command_time_breakdown(entries: &[Entry], periods: &[f32; 256]) -> Vec<CommandTime> {
+    let counts = command_counts(entries);
+    let totals: Vec<(u8, u64, f64)> = counts
+        .iter()
+        .map(|(cmd_id, count)| {
+            let total = *count as f64 * periods[*cmd_id as usize] as f64;
+            (*cmd_id, *count, total)
+        })
+        .collect();
+    let busy: f64 = totals.iter().map(|(_, _, total)| total).sum();
+
+    totals
+        .into_iter()
+        .map(|(cmd_id, count, total_cycles)| CommandTime {
+            cmd_id,
+            count,
+            total_cycles,
+            avg_cycles: total_cycles / count as f64,
+            share: if busy > 0.0 { total_cycles / busy } else { 0.0 },
+        })
+        .collect()
+}
+
+/// Shannon entropy in bits of the distribution given by `counts`.
+fn entropy_bits(counts: impl Iterator<Item = u64> + Clone) -> f64 {
+    let total: u64 = counts.clone().sum();
+    if total == 0 {
+        return 0.0;
+    }
+
+    counts
+        .filter(|count| *count > 0)
+        .map(|count| {
+            let p = count as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandEntropy {
+    /// Entropy of the command-id distribution; 0 for single-command traces.
+    pub entropy: f64,
+    /// Entropy of the next command given the current one. `None` with fewer than two entries.
+    pub conditional_entropy: Option<f64>,
+}
+
+/// How varied the command stream is, in bits. The conditional entropy is computed from the
+/// transition counts as H(current, next) - H(current).
+pub fn command_entropy(entries: &[Entry]) -> CommandEntropy {
+    let entropy = entropy_bits(command_counts(entries).values().copied());
+
+    let conditional_entropy = (entries.len() >= 2).then(|| {
+        let mut transitions: HashMap<(u8, u8), u64> = HashMap::new();
+        let mut current = [0u64; 256];
+        for pair in entries.windows(2) {
+            *transitions
+                .entry((pair[0].cmd_id, pair[1].cmd_id))
+                .or_insert(0) += 1;
+            current[pair[0].cmd_id as usize] += 1;
+        }
+
+        let joint = entropy_bits(transitions.values().copied());
+        (joint - entropy_bits(current.iter().copied())).max(0.0)
+    });
+
+    CommandEntropy {
+        entropy,
+        conditional_entropy,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransitionMatrix {
+    pub num_commands: u8,
+    /// Row-major `num_commands x num_commands` counts: `counts[prev * num_commands + next]`.
+    pub counts: Vec<u64>,
+}
+
+/// Number of times each command follows each other command in consecutive entries. Pairs with an
+/// id outside the dictionary are skipped; fewer than two entries yield an all-zero matrix.
+pub fn transition_matrix(entries: &[Entry], num_commands: u8) -> TransitionMatrix {
+    let n = num_commands as usize;
+    let mut counts = vec![0u64; n * n];
+
+    for pair in entries.windows(2) {
+        let (prev, next) = (pair[0].cmd_id as usize, pair[1].cmd_id as usize);
+        if prev < n && next < n {
+            counts[prev * n + next] += 1;
+        }
+    }
+
+    TransitionMatrix {
+        num_commands,
+        counts,
+    }
+}
+
+/// Number of `accesses` entries per column bucket, keyed by the first column of the bucket.
+/// Buckets are `bucket_width` columns wide (at least 1). Invalid (negative) columns are skipped,
+/// so a trace without valid columns yields an empty map.
+pub fn column_counts(
+    entries: &[Entry],
+    bucket_width: u32,
+    accesses: &CommandSet,
+) -> BTreeMap<i32, u64> {
+    let width = bucket_width.clamp(1, i32::MAX as u32) as i32;
+    let mut counts = BTreeMap::new();
+
+    for entry in entries.iter().filter(|e| accesses.contains(e.cmd_id)) {
+        let column = entry.column.get();
+        if column < 0 {
+            continue;
+        }
+        *counts.entry(column / width * width).or_insert(0) += 1;
+    }
+
+    counts
+}
+
+/// Upper bound of distinct groups counted by `group_counts`, so a fine-grained key can't exhaust memory.
+pub const MAX_GROUPS: usize = 1 << 16;
+
+/// A dimension entries can be grouped by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GroupKey {
+    Command,
+    Channel,
+    Rank,
+    BankGroup,
+    Bank,
+    /// Time buckets of the given width in cycles (at least 1), keyed by their first clk.
+    TimeBucket(i64),
+}
+
+impl GroupKey {
+    fn value(&self, entry: &Entry) -> i64 {
+        match self {
+            GroupKey::Command => entry.cmd_id as i64,
+            GroupKey::Channel => entry.channel.get() as i64,
+            GroupKey::Rank => entry.rank.get() as i64,
+            GroupKey::BankGroup => entry.bankgroup.get() as i64,
+            GroupKey::Bank => entry.bank.get() as i64,
+            GroupKey::TimeBucket(width) => {
+                let width = (*width).max(1);
+                entry.clk.get().div_euclid(width) * width
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupCount {
+    /// Values of the grouping keys, in the order they were requested.
+    pub key: Vec<i64>,
+    pub count: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GroupCounts {
+    pub groups: Vec<GroupCount>,
+    /// Set if entries of groups beyond `MAX_GROUPS` were dropped.
+    pub capped: bool,
+}
+
+/// Number of entries per distinct combination of the `keys` values, sorted by key. A pivot-table
+/// primitive: no keys yields a single group holding every entry. Invalid components (-1) form a
+/// group of their own rather than being skipped.
+pub fn group_counts(entries: &[Entry], keys: &[GroupKey]) -> GroupCounts {
+    let mut counts: HashMap<Vec<i64>, u64> = HashMap::new();
+    let mut capped = false;
+
+    for entry in entries {
+        let key: Vec<i64> = keys.iter().map(|k| k.value(entry)).collect();
+        if let Some(count) = counts.get_mut(&key) {
+            *count += 1;
+        } else if counts.len() < MAX_GROUPS {
+            counts.insert(key, 1);
+        } else {
+            capped = true;
+        }
+    }
+
+    let mut groups: Vec<GroupCount> = counts
+        .into_iter()
+        .map(|(key, count)| GroupCount { key, count })
+        .collect();
+    groups.sort_unstable_by(|a, b| a.key.cmp(&b.key));
+
+    GroupCounts { groups, capped }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(cmd_ids: &[u8]) -> Vec<Entry> {
+        cmd_ids
+            .iter()
+            .enumerate()
+            .map(|(clk, &cmd_id)| Entry::new(clk as i64, [0, 0, 0, 0, 1, -1], cmd_id))
+            .collect()
+    }
+
+    #[test]
+    fn transitions_count_consecutive_pairs() {
+        // ACT RD RD PRE ACT WR PRE
+        let matrix = transition_matrix(&stream(&[0, 1, 1, 3, 0, 2, 3]), 4);
+        let cell = |prev: usize, next: usize| matrix.counts[prev * 4 + next];
+
+        assert_eq!(matrix.counts.len(), 16);
+        assert_eq!(cell(0, 1), 1);
+        assert_eq!(cell(1, 1), 1);
+        assert_eq!(cell(1, 3), 1);
+        assert_eq!(cell(3, 0), 1);
+        assert_eq!(cell(0, 2), 1);
+        assert_eq!(cell(2, 3), 1);
+        assert_eq!(cell(1, 0), 0);
+        assert_eq!(matrix.counts.iter().sum::<u64>(), 6);
+    }
+
+    #[test]
+    fn transitions_of_short_traces_and_unknown_ids_are_empty() {
+        let single = transition_matrix(&stream(&[2]), 3);
+        assert_eq!(single.counts, vec![0; 9]);
+
+        // Pairs touching id 5 fall outside the 3-command matrix.
+        let matrix = transition_matrix(&stream(&[0, 5, 1, 2]), 3);
+        assert_eq!(matrix.counts.iter().sum::<u64>(), 1);
+        assert_eq!(matrix.counts[3 + 2], 1, "RD -> WR");
+    }
+
+    #[test]
+    fn columns_bucket_by_width_and_skip_invalid_ones() {
+        let access = |clk, cmd_id, column| Entry::new(clk, [0, 1, 0, 2, 77, column], cmd_id);
+        let entries = [
+            access(0, 1, 0),
+            access(1, 1, 7),
+            access(2, 2, 8),
+            access(3, 1, 63),
+            access(4, 1, -1),
+            // Not an access.
+            access(5, 0, 8),
+        ];
+        let accesses = CommandSet::from_ids([1, 2]);
+
+        assert_eq!(
+            column_counts(&entries, 8, &accesses),
+            BTreeMap::from([(0, 2), (8, 1), (56, 1)])
+        );
+        assert_eq!(
+            column_counts(&entries, 0, &accesses),
+            BTreeMap::from([(0, 1), (7, 1), (8, 1), (63, 1)])
+        );
+        assert!(column_counts(&entries[4..5], 8, &accesses).is_empty());
+    }
+
+    #[test]
+    fn group_counts_pivot_by_composite_keys() {
+        let entries = [
+            Entry::new(-3, [0, 1, 0, 0, 0, 0], 2),
+            Entry::new(4, [0, 1, 0, 0, 0, 0], 2),
+            Entry::new(9, [0, 0, 0, 0, 0, 0], 2),
+            Entry::new(12, [0, 1, 0, 0, 0, 0], 5),
+            Entry::new(13, [0, -1, 0, 0, 0, 0], 5),
+        ];
+        let pivot = |keys: &[GroupKey]| -> Vec<(Vec<i64>, u64)> {
+            group_counts(&entries, keys)
+                .groups
+                .into_iter()
+                .map(|g| (g.key, g.count))
+                .collect()
+        };
+
+        assert_eq!(
+            pivot(&[GroupKey::Rank, GroupKey::Command]),
+            [
+                (vec![-1, 5], 1),
+                (vec![0, 2], 1),
+                (vec![1, 2], 2),
+                (vec![1, 5], 1)
+            ]
+        );
+        // Negative clks round down into their bucket.
+        assert_eq!(
+            pivot(&[GroupKey::TimeBucket(10)]),
+            [(vec![-10], 1), (vec![0], 2), (vec![10], 2)]
+        );
+        assert_eq!(pivot(&[]), [(vec![], 5)]);
+        assert!(!group_counts(&entries, &[GroupKey::Bank]).capped);
+    }
+
+    #[test]
+    fn entropy_of_predictable_and_varied_streams() {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+
+        // Alternating commands: one bit per command, but the next one is always known.
+        let alternating = command_entropy(&stream(&[0, 1, 0, 1, 0, 1]));
+        assert!(close(alternating.entropy, 1.0));
+        assert!(close(alternating.conditional_entropy.unwrap(), 0.0));
+
+        // Transitions (0,0), (0,1), (1,1) once each, after 0 twice and 1 once.
+        let varied = command_entropy(&stream(&[0, 0, 1, 1]));
+        let expected = 3f64.log2() - (1.5f64.log2() * 2.0 / 3.0 + 3f64.log2() / 3.0);
+        assert!(close(varied.conditional_entropy.unwrap(), expected));
+
+        let single = command_entropy(&stream(&[4]));
+        assert_eq!((single.entropy, single.conditional_entropy), (0.0, None));
+    }
+
+    #[test]
+    fn time_breakdown_weights_counts_by_period() {
+        let mut periods = [0.0; 256];
+        periods[0] = 2.0;
+        periods[2] = 6.0;
+
+        let breakdown: Vec<(u8, u64, f64, f64, f64)> =
+            command_time_breakdown(&stream(&[2, 0, 0, 0, 7]), &periods)
+                .iter()
+                .map(|c| (c.cmd_id, c.count, c.total_cycles, c.avg_cycles, c.share))
+                .collect();
+        assert_eq!(
+            breakdown,
+            [
+                (0, 3, 6.0, 2.0, 0.5),
+                (2, 1, 6.0, 6.0, 0.5),
+                (7, 1, 0.0, 0.0, 0.0)
+            ]
+        );
+
+        // Without any configured duration nothing is busy, rather than dividing by zero.
+        let idle = command_time_breakdown(&stream(&[7, 7]), &periods);
+        assert_eq!((idle[0].count, idle[0].share), (2, 0.0));
+    }
+}
@@ -0,0 +1,279 @@
+/// Data-quality checks of a trace's address fields.
+use serde::{Deserialize, Serialize};
+
+use crate::trace::entry::Entry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AddressField {
+    Channel,
+    Rank,
+    Bankgroup,
+    Bank,
+    Row,
+    Column,
+}
+
+impl AddressField {
+    const ALL: [AddressField; 6] = [
+        AddressField::Channel,
+        AddressField::Rank,
+        AddressField::Bankgroup,
+        AddressField::Bank,
+        AddressField::Row,
+        AddressField::Column,
+    ];
+
+    fn value(&self, entry: &Entry) -> i64 {
+        match self {
+            AddressField::Channel => entry.channel.get() as i64,
+            AddressField::Rank => entry.rank.get() as i64,
+            AddressField::Bankgroup => entry.bankgroup.get() as i64,
+            AddressField::Bank => entry.bank.get() as i64,
+            AddressField::Row => entry.row.get() as i64,
+            AddressField::Column => entry.column.get() as i64,
+        }
+    }
+
+    fn is_valid(&self, entry: &Entry) -> bool {
+        self.value(entry) >= 0
+    }
+}
+
+/// "If `field` is valid, `requires` must be valid too", e.g. a bank only makes sense within a
+/// bank group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressRule {
+    pub field: AddressField,
+    pub requires: AddressField,
+}
+
+/// The address hierarchy: every component implies the one it lives in.
+pub fn default_rules() -> Vec<AddressRule> {
+    use AddressField::*;
+
+    [
+        (Rank, Channel),
+        (Bankgroup, Rank),
+        (Bank, Bankgroup),
+        (Row, Bank),
+        (Column, Row),
+    ]
+    .into_iter()
+    .map(|(field, requires)| AddressRule { field, requires })
+    .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressAnomaly {
+    pub index: u64,
+    /// The first rule, in the order given, that the entry violates.
+    pub rule: AddressRule,
+}
+
+/// Up to `limit` entries violating any of `rules`, in file order.
+pub fn address_anomalies(
+    entries: &[Entry],
+    rules: &[AddressRule],
+    limit: usize,
+) -> Vec<AddressAnomaly> {
+    entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            let rule = rules
+                .iter()
+                .find(|rule| rule.field.is_valid(entry) && !rule.requires.is_valid(entry))?;
+            Some(AddressAnomaly {
+                index: index as u64,
+                rule: *rule,
+            })
+        })
+        .take(limit)
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldRange {
+    pub min: i64,
+    pub max: i64,
+}
+
+/// Per address field, the smallest and largest valid value. `None` if no entry has a valid one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressRanges {
+    pub channel: Option<FieldRange>,
+    pub rank: Option<FieldRange>,
+    pub bankgroup: Option<FieldRange>,
+    pub bank: Option<FieldRange>,
+    pub row: Option<FieldRange>,
+    pub column: Option<FieldRange>,
+}
+
+/// The value ranges of all address fields in one pass, ignoring invalid (negative) values.
+pub fn address_ranges(entries: &[Entry]) -> AddressRanges {
+    let mut ranges: [Option<FieldRange>; 6] = [None; 6];
+
+    for entry in entries {
+        for (range, field) in ranges.iter_mut().zip(AddressField::ALL) {
+            let value = field.value(entry);
+            if value < 0 {
+                continue;
+            }
+            *range = Some(match *range {
+                Some(r) => FieldRange {
+                    min: r.min.min(value),
+                    max: r.max.max(value),
+                },
+                None => FieldRange {
+                    min: value,
+                    max: value,
+                },
+            });
+        }
+    }
+
+    let [channel, rank, bankgroup, bank, row, column] = ranges;
+    AddressRanges {
+        channel,
+        rank,
+        bankgroup,
+        bank,
+        row,
+        column,
+    }
+}
+
+/// Per address field, the fraction of entries where it is invalid (negative).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressCompleteness {
+    pub channel: f64,
+    pub rank: f64,
+    pub bankgroup: f64,
+    pub bank: f64,
+    pub row: f64,
+    pub column: f64,
+}
+
+/// The invalid fraction of every address field in one pass. All zero for an empty trace.
+pub fn address_completeness(entries: &[Entry]) -> AddressCompleteness {
+    let mut invalid = [0u64; 6];
+    for entry in entries {
+        for (count, field) in invalid.iter_mut().zip(AddressField::ALL) {
+            *count += !field.is_valid(entry) as u64;
+        }
+    }
+
+    let total = entries.len().max(1) as f64;
+    let [channel, rank, bankgroup, bank, row, column] = invalid.map(|count| count as f64 / total);
+    AddressCompleteness {
+        channel,
+        rank,
+        bankgroup,
+        bank,
+        row,
+        column,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn consistent_traces_have_no_anomalies() {
+        // Commands addressing a whole bank or rank leave the inner components invalid.
+        let entries = [
+            Entry::new(0, [0, 0, 1, 2, 40, -1], 0),
+            Entry::new(4, [0, 0, 1, 2, 40, 8], 1),
+            Entry::new(9, [0, 0, 1, 2, -1, -1], 2),
+            Entry::new(20, [0, 1, -1, -1, -1, -1], 3),
+        ];
+
+        assert!(address_anomalies(&entries, &default_rules(), usize::MAX).is_empty());
+    }
+
+    #[test]
+    fn injected_inconsistencies_report_the_first_broken_rule() {
+        use AddressField::*;
+        let entries = [
+            Entry::new(0, [0, 0, 1, 2, 40, 8], 1),
+            Entry::new(1, [0, 0, -1, 2, 40, 8], 1),
+            Entry::new(2, [0, 0, 1, 2, -1, 8], 1),
+            Entry::new(3, [-1, 0, 1, 2, 40, 8], 1),
+        ];
+        let rule = |field, requires| AddressRule { field, requires };
+
+        let anomalies = address_anomalies(&entries, &default_rules(), usize::MAX);
+        assert_eq!(
+            anomalies,
+            [
+                AddressAnomaly {
+                    index: 1,
+                    rule: rule(Bank, Bankgroup)
+                },
+                AddressAnomaly {
+                    index: 2,
+                    rule: rule(Column, Row)
+                },
+                AddressAnomaly {
+                    index: 3,
+                    rule: rule(Rank, Channel)
+                },
+            ]
+        );
+
+        assert_eq!(address_anomalies(&entries, &default_rules(), 1).len(), 1);
+        let custom = address_anomalies(&entries, &[rule(Column, Row)], usize::MAX);
+        assert_eq!(custom.iter().map(|a| a.index).collect::<Vec<_>>(), [2]);
+    }
+
+    #[test]
+    fn ranges_skip_invalid_values() {
+        let entries = [
+            Entry::new(0, [0, 1, 3, -1, 900, 16], 0),
+            Entry::new(1, [0, 0, -1, 5, 12, -1], 2),
+            Entry::new(2, [0, 1, 0, 2, -1, 8], 2),
+        ];
+        let span = |min, max| Some(FieldRange { min, max });
+
+        let ranges = address_ranges(&entries);
+        assert_eq!(ranges.channel, span(0, 0));
+        assert_eq!(ranges.rank, span(0, 1));
+        assert_eq!(ranges.bankgroup, span(0, 3));
+        assert_eq!(ranges.bank, span(2, 5));
+        assert_eq!(ranges.row, span(12, 900));
+        assert_eq!(ranges.column, span(8, 16));
+
+        // A field that is never valid has no range rather than a negative one.
+        let refreshes = [Entry::new(0, [0, 0, -1, -1, -1, -1], 4)];
+        assert_eq!(address_ranges(&refreshes).bank, None);
+        assert_eq!(address_ranges(&[]).channel, None);
+    }
+
+    #[test]
+    fn completeness_is_the_invalid_fraction_per_field() {
+        // A refresh per rank and a precharge-all next to four fully addressed commands.
+        let mut entries = vec![
+            Entry::new(0, [0, 0, -1, -1, -1, -1], 4),
+            Entry::new(1, [0, 1, -1, -1, -1, -1], 4),
+            Entry::new(2, [0, 0, 1, -1, -1, -1], 1),
+        ];
+        entries.extend((0..4).map(|i| Entry::new(3 + i, [0, 0, 0, i, 7, 8], 2)));
+
+        let completeness = address_completeness(&entries);
+        assert_eq!(completeness.channel, 0.0);
+        assert_eq!(completeness.rank, 0.0);
+        assert_eq!(completeness.bankgroup, 2.0 / 7.0);
+        assert_eq!(completeness.bank, 3.0 / 7.0);
+        assert_eq!(completeness.row, 3.0 / 7.0);
+        assert_eq!(completeness.column, 3.0 / 7.0);
+
+        assert_eq!(address_completeness(&[]).column, 0.0);
+    }
+}
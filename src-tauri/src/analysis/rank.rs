@@ -0,0 +1,232 @@
+/// Rank-level rollups of a trace.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::classifier::{CommandCategory, CommandClassifier};
+use crate::analysis::TimeBuckets;
+use crate::trace::entry::Entry;
+
+/// Fraction of all entries that target each rank, computed in a single scan.
+/// Entries with an invalid rank (-1) end up in their own -1 bucket.
+/// The fractions of all buckets sum to 1.0; an empty trace yields an empty map.
+pub fn utilization(entries: &[Entry]) -> HashMap<i16, f64> {
+    let mut counts: HashMap<i16, u64> = HashMap::new();
+    for entry in entries {
+        *counts.entry(entry.rank.get()).or_insert(0) += 1;
+    }
+
+    let total = entries.len() as f64;
+    counts
+        .into_iter()
+        .map(|(rank, count)| (rank, count as f64 / total))
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankBucket {
+    pub clk_start: i64,
+    /// Entries per rank, in the order of `RankTimeline::ranks`.
+    pub counts: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankTimeline {
+    /// Every rank that occurs, sorted. An invalid rank (-1) is listed like any other.
+    pub ranks: Vec<i16>,
+    pub buckets: Vec<RankBucket>,
+}
+
+/// Entries per rank and time bucket, for stacking rank activity over time.
+pub fn timeline(entries: &[Entry], num_buckets: usize) -> RankTimeline {
+    if num_buckets == 0 {
+        return RankTimeline::default();
+    }
+
+    let ranks: Vec<i16> = entries
+        .iter()
+        .map(|e| e.rank.get())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let column: HashMap<i16, usize> = ranks.iter().enumerate().map(|(i, r)| (*r, i)).collect();
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+    let mut counts = vec![vec![0u64; ranks.len()]; num_buckets];
+    for entry in entries {
+        counts[buckets.index(entry.clk.get())][column[&entry.rank.get()]] += 1;
+    }
+
+    RankTimeline {
+        ranks,
+        buckets: counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, counts)| RankBucket {
+                clk_start: buckets.start(i),
+                counts,
+            })
+            .collect(),
+    }
+}
+
+const CATEGORIES: [CommandCategory; 6] = [
+    CommandCategory::Read,
+    CommandCategory::Write,
+    CommandCategory::Activate,
+    CommandCategory::Precharge,
+    CommandCategory::Refresh,
+    CommandCategory::Other,
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RankCommandMix {
+    pub rank: i16,
+    pub total: u64,
+    /// Share of the rank's entries per category in percent, summing to 100. Every category is
+    /// listed, unused ones with 0.
+    pub percentages: BTreeMap<CommandCategory, f64>,
+}
+
+/// Per rank, how its entries split over the command categories, sorted by rank. Entries with an
+/// invalid rank (-1) form their own rank as in `utilization`.
+pub fn command_mix(entries: &[Entry], classifier: &CommandClassifier) -> Vec<RankCommandMix> {
+    let mut counts: BTreeMap<i16, HashMap<CommandCategory, u64>> = BTreeMap::new();
+    for entry in entries {
+        *counts
+            .entry(entry.rank.get())
+            .or_default()
+            .entry(classifier.category(entry.cmd_id))
+            .or_insert(0) += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|(rank, counts)| {
+            let total: u64 = counts.values().sum();
+            let percentages = CATEGORIES
+                .iter()
+                .map(|category| {
+                    let count = counts.get(category).copied().unwrap_or(0);
+                    (*category, count as f64 / total as f64 * 100.0)
+                })
+                .collect();
+            RankCommandMix {
+                rank,
+                total,
+                percentages,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn on_rank(clk: i64, rank: i64) -> Entry {
+        Entry::new(clk, [0, rank, 0, 0, 0, 0], 0)
+    }
+
+    #[test]
+    fn utilization_splits_entries_by_rank() {
+        let entries = [
+            on_rank(0, 0),
+            on_rank(1, 0),
+            on_rank(2, 0),
+            on_rank(3, 1),
+            on_rank(4, 1),
+            on_rank(5, 1),
+            on_rank(6, 1),
+            on_rank(7, -1),
+        ];
+
+        let fractions = utilization(&entries);
+        assert_eq!(
+            fractions,
+            HashMap::from([(0, 0.375), (1, 0.5), (-1, 0.125)])
+        );
+        assert_eq!(fractions.values().sum::<f64>(), 1.0);
+        assert!(utilization(&[]).is_empty());
+    }
+
+    #[test]
+    fn timeline_stacks_ranks_per_bucket() {
+        let entries = [
+            on_rank(0, 2),
+            on_rank(1, 2),
+            on_rank(3, 0),
+            on_rank(4, -1),
+            on_rank(7, 2),
+        ];
+
+        let stacked = timeline(&entries, 2);
+        assert_eq!(stacked.ranks, [-1, 0, 2]);
+        let buckets: Vec<(i64, Vec<u64>)> = stacked
+            .buckets
+            .into_iter()
+            .map(|b| (b.clk_start, b.counts))
+            .collect();
+        assert_eq!(buckets, [(0, vec![0, 1, 2]), (4, vec![1, 0, 1])]);
+
+        assert_eq!(timeline(&entries, 0), RankTimeline::default());
+    }
+
+    #[test]
+    fn command_mix_is_a_percentage_split_per_rank() {
+        use crate::analysis::classifier::CategoryPatterns;
+        use crate::trace::dictionary::Dictionary;
+        use CommandCategory::*;
+
+        let dictionary = Dictionary::from_names(&["ACT", "RD", "WR", "PREA", "REFab", "MRS"]);
+        let classifier = CommandClassifier::new(&dictionary, &CategoryPatterns::default());
+        let issue = |clk, rank, cmd_id| Entry::new(clk, [0, rank, 0, 0, 0, 0], cmd_id);
+        // Rank 0 is read heavy, rank 1 mostly writes; the refresh addresses no rank.
+        let entries = [
+            issue(0, 0, 0),
+            issue(1, 0, 1),
+            issue(2, 1, 0),
+            issue(3, 0, 1),
+            issue(4, 1, 2),
+            issue(5, 0, 1),
+            issue(6, 1, 2),
+            issue(7, 1, 3),
+            issue(8, -1, 4),
+            issue(9, 1, 5),
+        ];
+
+        let mix = command_mix(&entries, &classifier);
+        let shares =
+            |rank: usize| CATEGORIES.map(|category| (category, mix[rank].percentages[&category]));
+        assert_eq!(
+            mix.iter().map(|m| (m.rank, m.total)).collect::<Vec<_>>(),
+            [(-1, 1), (0, 4), (1, 5)]
+        );
+        assert_eq!(
+            shares(1),
+            [
+                (Read, 75.0),
+                (Write, 0.0),
+                (Activate, 25.0),
+                (Precharge, 0.0),
+                (Refresh, 0.0),
+                (Other, 0.0),
+            ]
+        );
+        assert_eq!(
+            shares(2),
+            [
+                (Read, 0.0),
+                (Write, 40.0),
+                (Activate, 20.0),
+                (Precharge, 20.0),
+                (Refresh, 0.0),
+                (Other, 20.0),
+            ]
+        );
+        assert_eq!(mix[0].percentages[&Refresh], 100.0);
+    }
+}
@@ -0,0 +1,536 @@
+/// Row-level statistics of a trace.
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{BankKey, CommandSet, TimeBuckets};
+use crate::trace::entry::Entry;
+
+/// Upper bound of distinct rows remembered per bank, so pathological traces can't exhaust memory.
+pub const MAX_TRACKED_ROWS: usize = 1 << 16;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowActivationStats {
+    pub bank: BankKey,
+    /// Number of distinct activated rows. A lower bound if `capped` is set.
+    pub distinct_rows: u64,
+    pub total_acts: u64,
+    pub capped: bool,
+}
+
+/// Per bank, the number of activations and of distinct activated rows, sorted by bank.
+/// Activations with an invalid row (-1) are counted but don't contribute a distinct row.
+pub fn activation_stats(entries: &[Entry], activates: &CommandSet) -> Vec<RowActivationStats> {
+    let mut banks: BTreeMap<BankKey, (HashSet<i32>, u64, bool)> = BTreeMap::new();
+
+    for entry in entries.iter().filter(|e| activates.contains(e.cmd_id)) {
+        let (rows, total, capped) = banks.entry(BankKey::of(entry)).or_default();
+        *total += 1;
+
+        let row = entry.row.get();
+        if row == -1 || rows.contains(&row) {
+            continue;
+        }
+        if rows.len() < MAX_TRACKED_ROWS {
+            rows.insert(row);
+        } else {
+            *capped = true;
+        }
+    }
+
+    banks
+        .into_iter()
+        .map(|(bank, (rows, total_acts, capped))| RowActivationStats {
+            bank,
+            distinct_rows: rows.len() as u64,
+            total_acts,
+            capped,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotRow {
+    pub bank: BankKey,
+    pub row: i32,
+    pub count: u64,
+}
+
+/// The `n` (bank, row) pairs hit by the most `accesses`, hottest first; ties in bank/row order.
+/// Entries with an invalid row (-1) are skipped. Every distinct row is counted before the top `n`
+/// are selected, so memory grows with the number of distinct rows touched, not with `n`.
+pub fn hot_rows(entries: &[Entry], n: usize, accesses: &CommandSet) -> Vec<HotRow> {
+    if n == 0 {
+        return Vec::new();
+    }
+
+    let mut counts: HashMap<(BankKey, i32), u64> = HashMap::new();
+
+    for entry in entries.iter().filter(|e| accesses.contains(e.cmd_id)) {
+        let row = entry.row.get();
+        if row != -1 {
+            *counts.entry((BankKey::of(entry), row)).or_insert(0) += 1;
+        }
+    }
+
+    let mut rows: Vec<HotRow> = counts
+        .into_iter()
+        .map(|((bank, row), count)| HotRow { bank, row, count })
+        .collect();
+
+    let hotter = |a: &HotRow, b: &HotRow| {
+        b.count
+            .cmp(&a.count)
+            .then(a.bank.cmp(&b.bank))
+            .then(a.row.cmp(&b.row))
+    };
+    if rows.len() > n {
+        rows.select_nth_unstable_by(n - 1, hotter);
+        rows.truncate(n);
+    }
+    rows.sort_unstable_by(hotter);
+
+    rows
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressAlias {
+    pub row: i32,
+    pub column: i32,
+    pub distinct_banks: u64,
+    pub accesses: u64,
+}
+
+/// The `limit` (row, column) pairs accessed in the most distinct banks, most widespread first;
+/// ties in row/column order. Addresses recurring across many banks can point at an address
+/// mapping that ignores some bits. Pairs seen in a single bank are not aliases and are omitted.
+/// Entries with any invalid address component (-1) are skipped.
+pub fn address_aliases(
+    entries: &[Entry],
+    limit: usize,
+    accesses: &CommandSet,
+) -> Vec<AddressAlias> {
+    let mut addresses: HashMap<(i32, i32), (HashSet<BankKey>, u64)> = HashMap::new();
+
+    for entry in entries.iter().filter(|e| accesses.contains(e.cmd_id)) {
+        let bank = BankKey::of(entry);
+        let (row, column) = (entry.row.get(), entry.column.get());
+        if bank.is_wildcard() || row == -1 || column == -1 {
+            continue;
+        }
+
+        let (banks, count) = addresses.entry((row, column)).or_default();
+        banks.insert(bank);
+        *count += 1;
+    }
+
+    let mut aliases: Vec<AddressAlias> = addresses
+        .into_iter()
+        .filter(|(_, (banks, _))| banks.len() > 1)
+        .map(|((row, column), (banks, accesses))| AddressAlias {
+            row,
+            column,
+            distinct_banks: banks.len() as u64,
+            accesses,
+        })
+        .collect();
+
+    aliases.sort_unstable_by(|a, b| {
+        b.distinct_banks
+            .cmp(&a.distinct_banks)
+            .then(a.row.cmp(&b.row))
+            .then(a.column.cmp(&b.column))
+    });
+    aliases.truncate(limit);
+
+    aliases
+}
+
+/// Tracks the open row of every bank to classify accesses as row-buffer hits or misses.
+/// The first access after an activation is a miss, since it needed the activation; further
+/// accesses to the same row are hits until the bank is precharged or another row is opened.
+/// An access to a closed bank or another row opens its row implicitly, for traces that don't
+/// record every activation.
+#[derive(Debug, Default)]
+pub struct RowBufferTracker {
+    /// Open row per bank and whether it has been accessed since it was opened.
+    open: HashMap<BankKey, (i32, bool)>,
+}
+
+impl RowBufferTracker {
+    pub fn activate(&mut self, entry: &Entry) {
+        self.open
+            .insert(BankKey::of(entry), (entry.row.get(), false));
+    }
+
+    /// Closes the bank of `entry`, or every bank it covers for multi-bank precharges such as a
+    /// PREA with bank -1, see `BankKey::covers`.
+    pub fn precharge(&mut self, entry: &Entry) {
+        let key = BankKey::of(entry);
+        self.open.retain(|bank, _| !key.covers(bank));
+    }
+
+    /// Records an access and returns whether it hit the open row.
+    pub fn access(&mut self, entry: &Entry) -> bool {
+        let row = entry.row.get();
+        let open = self.open.entry(BankKey::of(entry)).or_insert((row, false));
+        let hit = *open == (row, true);
+        *open = (row, true);
+        hit
+    }
+
+    /// Feeds an entry of any command to the tracker and returns whether it hit if it is one of the
+    /// `accesses`. Precharges with an invalid bank address component (-1) close every bank they
+    /// cover; other such entries are skipped, as are activations and accesses with an invalid row.
+    pub fn observe(
+        &mut self,
+        entry: &Entry,
+        activates: &CommandSet,
+        precharges: &CommandSet,
+        accesses: &CommandSet,
+    ) -> Option<bool> {
+        // Precharges close their banks whatever their row; the others need a valid one.
+        if precharges.contains(entry.cmd_id) {
+            self.precharge(entry);
+        } else if BankKey::of(entry).is_wildcard() || entry.row.get() == -1 {
+            return None;
+        } else if activates.contains(entry.cmd_id) {
+            self.activate(entry);
+        } else if accesses.contains(entry.cmd_id) {
+            return Some(self.access(entry));
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BankGroupLocality {
+    pub channel: i16,
+    pub rank: i16,
+    pub bankgroup: i32,
+    pub hits: u64,
+    pub accesses: u64,
+    pub hit_rate: f64,
+}
+
+/// Row-buffer hit rate of the `accesses` per bank group, sorted by bank group. Rows are tracked
+/// per bank with `RowBufferTracker`, only the counts are rolled up by group. Invalid addresses are
+/// handled as in `RowBufferTracker::observe`.
+pub fn bankgroup_locality(
+    entries: &[Entry],
+    activates: &CommandSet,
+    precharges: &CommandSet,
+    accesses: &CommandSet,
+) -> Vec<BankGroupLocality> {
+    let mut tracker = RowBufferTracker::default();
+    let mut groups: BTreeMap<(i16, i16, i32), (u64, u64)> = BTreeMap::new();
+
+    for entry in entries {
+        if let Some(hit) = tracker.observe(entry, activates, precharges, accesses) {
+            let (hits, total) = groups
+                .entry((entry.channel.get(), entry.rank.get(), entry.bankgroup.get()))
+                .or_default();
+            *hits += hit as u64;
+            *total += 1;
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(
+            |((channel, rank, bankgroup), (hits, accesses))| BankGroupLocality {
+                channel,
+                rank,
+                bankgroup,
+                hits,
+                accesses,
+                hit_rate: hits as f64 / accesses as f64,
+            },
+        )
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalityBucket {
+    pub clk_start: i64,
+    pub hits: u64,
+    pub accesses: u64,
+    /// `None` for buckets without accesses.
+    pub hit_rate: Option<f64>,
+}
+
+/// Row-buffer hit rate of the `accesses` per time bucket. With `reset_per_bucket`, every bucket
+/// starts with all banks closed, so its rate only reflects locality within the window; otherwise
+/// the open rows carry over and the counts sum to those of `bankgroup_locality`.
+pub fn locality_timeline(
+    entries: &[Entry],
+    num_buckets: usize,
+    activates: &CommandSet,
+    precharges: &CommandSet,
+    accesses: &CommandSet,
+    reset_per_bucket: bool,
+) -> Vec<LocalityBucket> {
+    if num_buckets == 0 {
+        return Vec::new();
+    }
+
+    let buckets = TimeBuckets::new(entries, num_buckets);
+    let mut counts = vec![(0u64, 0u64); num_buckets];
+    let mut tracker = RowBufferTracker::default();
+    let mut current_bucket = 0;
+
+    for entry in entries {
+        let bucket = buckets.index(entry.clk.get());
+        if reset_per_bucket && bucket != current_bucket {
+            tracker = RowBufferTracker::default();
+            current_bucket = bucket;
+        }
+
+        if let Some(hit) = tracker.observe(entry, activates, precharges, accesses) {
+            counts[bucket].0 += hit as u64;
+            counts[bucket].1 += 1;
+        }
+    }
+
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, &(hits, accesses))| LocalityBucket {
+            clk_start: buckets.start(i),
+            hits,
+            accesses,
+            hit_rate: (accesses > 0).then(|| hits as f64 / accesses as f64),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACT: u8 = 0;
+    const PRE: u8 = 1;
+    const RD: u8 = 2;
+
+    fn cmd(clk: i64, cmd_id: u8, bankgroup: i64, bank: i64, row: i64) -> Entry {
+        Entry::new(clk, [0, 0, bankgroup, bank, row, 0], cmd_id)
+    }
+
+    fn locality(entries: &[Entry]) -> Vec<(i32, u64, u64)> {
+        bankgroup_locality(
+            entries,
+            &CommandSet::from_ids([ACT]),
+            &CommandSet::from_ids([PRE]),
+            &CommandSet::from_ids([RD]),
+        )
+        .into_iter()
+        .map(|g| (g.bankgroup, g.hits, g.accesses))
+        .collect()
+    }
+
+    #[test]
+    fn bankgroup_locality_rolls_banks_up_by_group() {
+        let entries = [
+            // Group 0: bank 0 hits twice, bank 1 misses twice on a row conflict.
+            cmd(0, ACT, 0, 0, 5),
+            cmd(1, RD, 0, 0, 5),
+            cmd(2, RD, 0, 0, 5),
+            cmd(3, RD, 0, 0, 5),
+            cmd(4, ACT, 0, 1, 7),
+            cmd(5, RD, 0, 1, 7),
+            cmd(6, RD, 0, 1, 8),
+            // Group 1: a precharge closes the row between two reads.
+            cmd(7, RD, 1, 0, 2),
+            cmd(8, RD, 1, 0, 2),
+            cmd(9, PRE, 1, 0, -1),
+            cmd(10, RD, 1, 0, 2),
+            // Invalid bank components are skipped.
+            cmd(11, RD, 1, -1, 2),
+        ];
+
+        assert_eq!(locality(&entries), [(0, 2, 5), (1, 1, 3)]);
+    }
+
+    #[test]
+    fn multi_bank_precharges_close_every_covered_bank() {
+        let entries = [
+            cmd(0, RD, 0, 0, 5),
+            cmd(1, RD, 0, 1, 5),
+            cmd(2, RD, 1, 0, 5),
+            // A PREA of bank group 0 leaves group 1 open.
+            cmd(3, PRE, 0, -1, -1),
+            cmd(4, RD, 0, 0, 5),
+            cmd(5, RD, 0, 1, 5),
+            cmd(6, RD, 1, 0, 5),
+        ];
+        assert_eq!(locality(&entries), [(0, 0, 4), (1, 1, 2)]);
+
+        // One covering the whole rank closes everything.
+        let mut all = entries;
+        all[3] = cmd(3, PRE, -1, -1, -1);
+        assert_eq!(locality(&all), [(0, 0, 4), (1, 0, 2)]);
+    }
+
+    fn timeline(entries: &[Entry], num_buckets: usize, reset: bool) -> Vec<(i64, u64, u64)> {
+        locality_timeline(
+            entries,
+            num_buckets,
+            &CommandSet::from_ids([ACT]),
+            &CommandSet::from_ids([PRE]),
+            &CommandSet::from_ids([RD]),
+            reset,
+        )
+        .into_iter()
+        .map(|b| (b.clk_start, b.hits, b.accesses))
+        .collect()
+    }
+
+    #[test]
+    fn locality_timeline_carries_or_resets_open_rows() {
+        let entries = [
+            cmd(0, ACT, 0, 0, 5),
+            cmd(1, RD, 0, 0, 5),
+            cmd(2, RD, 0, 0, 5),
+            cmd(3, RD, 0, 0, 5),
+            cmd(10, RD, 0, 0, 5),
+            cmd(11, RD, 0, 0, 5),
+            cmd(19, RD, 0, 0, 5),
+        ];
+
+        assert_eq!(timeline(&entries, 2, false), [(0, 2, 3), (10, 3, 3)]);
+        // Reset, the first read of the second window finds the bank closed.
+        assert_eq!(timeline(&entries, 2, true), [(0, 2, 3), (10, 2, 3)]);
+    }
+
+    #[test]
+    fn locality_timeline_leaves_idle_buckets_without_a_rate() {
+        let entries = [
+            cmd(0, RD, 0, 0, 5),
+            cmd(1, RD, 0, 0, 5),
+            cmd(19, RD, 0, 0, 5),
+        ];
+        let buckets = locality_timeline(
+            &entries,
+            4,
+            &CommandSet::from_ids([ACT]),
+            &CommandSet::from_ids([PRE]),
+            &CommandSet::from_ids([RD]),
+            false,
+        );
+
+        let rates: Vec<Option<f64>> = buckets.iter().map(|b| b.hit_rate).collect();
+        assert_eq!(rates, [Some(0.5), None, None, Some(1.0)]);
+        assert!(timeline(&entries, 0, false).is_empty());
+    }
+
+    #[test]
+    fn activation_stats_count_distinct_rows_per_bank() {
+        let entries = [
+            cmd(0, ACT, 0, 0, 10),
+            cmd(1, ACT, 0, 1, 10),
+            cmd(2, RD, 0, 0, 99),
+            cmd(3, ACT, 0, 0, 11),
+            cmd(4, ACT, 0, 0, 10),
+            cmd(5, ACT, 1, 0, -1),
+        ];
+
+        let stats: Vec<((i32, i32), u64, u64, bool)> =
+            activation_stats(&entries, &CommandSet::from_ids([ACT]))
+                .into_iter()
+                .map(|s| {
+                    let bank = (s.bank.bankgroup, s.bank.bank);
+                    (bank, s.distinct_rows, s.total_acts, s.capped)
+                })
+                .collect();
+        // The ACT without a valid row still counts towards the total.
+        assert_eq!(
+            stats,
+            [
+                ((0, 0), 2, 3, false),
+                ((0, 1), 1, 1, false),
+                ((1, 0), 0, 1, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn activation_stats_cap_distinct_rows() {
+        let entries: Vec<Entry> = (0..=MAX_TRACKED_ROWS as i64)
+            .map(|row| cmd(row, ACT, 2, 3, row))
+            .collect();
+
+        let stats = activation_stats(&entries, &CommandSet::from_ids([ACT]));
+        assert_eq!(stats.len(), 1);
+        assert!(stats[0].capped);
+        assert_eq!(stats[0].distinct_rows, MAX_TRACKED_ROWS as u64);
+        assert_eq!(stats[0].total_acts, MAX_TRACKED_ROWS as u64 + 1);
+    }
+
+    #[test]
+    fn hot_rows_rank_by_count_then_address() {
+        let mut entries = Vec::new();
+        for (clk, (bank, row)) in [
+            (0, 5),
+            (0, 5),
+            (0, 5),
+            (1, 5),
+            (1, 5),
+            (0, 6),
+            (0, 6),
+            (1, 2),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            entries.push(cmd(clk as i64, RD, 0, bank, row));
+        }
+        entries.push(cmd(8, RD, 0, 0, -1));
+        entries.push(cmd(9, ACT, 0, 1, 2));
+
+        let top = |n| -> Vec<(i32, i32, u64)> {
+            hot_rows(&entries, n, &CommandSet::from_ids([RD]))
+                .iter()
+                .map(|h| (h.bank.bank, h.row, h.count))
+                .collect()
+        };
+        // (0, 6) and (1, 5) tie on 2 hits; bank 0 wins.
+        assert_eq!(top(3), [(0, 5, 3), (0, 6, 2), (1, 5, 2)]);
+        assert_eq!(top(10).len(), 4);
+        assert!(top(0).is_empty());
+    }
+
+    #[test]
+    fn address_aliases_span_several_banks() {
+        let at = |clk, channel, bank, row, column| {
+            Entry::new(clk, [channel, 0, 0, bank, row, column], RD)
+        };
+        let entries = [
+            at(0, 0, 0, 100, 8),
+            at(1, 0, 1, 100, 8),
+            at(2, 1, 0, 100, 8),
+            at(3, 0, 0, 100, 8),
+            at(4, 0, 2, 7, 0),
+            at(5, 0, 3, 7, 0),
+            // One bank only, and an entry with an invalid column.
+            at(6, 0, 0, 55, 1),
+            at(7, 0, 0, 55, 1),
+            at(8, 0, 1, 55, -1),
+        ];
+
+        let aliases: Vec<(i32, i32, u64, u64)> =
+            address_aliases(&entries, 10, &CommandSet::from_ids([RD]))
+                .iter()
+                .map(|a| (a.row, a.column, a.distinct_banks, a.accesses))
+                .collect();
+        assert_eq!(aliases, [(100, 8, 3, 4), (7, 0, 2, 2)]);
+        assert_eq!(
+            address_aliases(&entries, 1, &CommandSet::from_ids([RD])).len(),
+            1
+        );
+    }
+}
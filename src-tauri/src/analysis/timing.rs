@@ -0,0 +1,564 @@
+/// Per-bank timing between pairs of commands (e.g. WR followed by RD).
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::analysis::{BankKey, CommandSet};
+use crate::trace::entry::Entry;
+use crate::trace::filter::EntryFilter;
+
+/// Distribution of latencies in cycles, keyed by exact delta.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub histogram: BTreeMap<i64, u64>,
+    pub count: u64,
+    pub min: Option<i64>,
+    pub avg: Option<f64>,
+    pub max: Option<i64>,
+}
+
+impl LatencyStats {
+    fn from_deltas(deltas: &[i64]) -> Self {
+        let mut histogram = BTreeMap::new();
+        for delta in deltas {
+            *histogram.entry(*delta).or_insert(0) += 1;
+        }
+
+        let count = deltas.len() as u64;
+        let avg = (count > 0).then(|| deltas.iter().sum::<i64>() as f64 / count as f64);
+
+        Self {
+            histogram,
+            count,
+            min: deltas.iter().copied().min(),
+            avg,
+            max: deltas.iter().copied().max(),
+        }
+    }
+}
+
+/// A `from` command addressing several banks, see `BankKey::is_wildcard`.
+struct WildcardFrom {
+    key: BankKey,
+    clk: i64,
+    /// Covered banks that have paired with it or seen a newer `from` since.
+    done: HashSet<BankKey>,
+}
+
+/// Cycles of every `from` -> next `to` pair per bank, and the number of `from`s left unpaired.
+/// Commands with an invalid address component address every bank they cover: a PREA as `to`
+/// closes all pending banks of its rank or bank group, and a PREA as `from` pairs with the next
+/// `to` of each of them.
+fn pair_deltas(entries: &[Entry], from: &CommandSet, to: &CommandSet) -> (Vec<i64>, u64) {
+    let mut pending: HashMap<BankKey, i64> = HashMap::new();
+    let mut wildcards: Vec<WildcardFrom> = Vec::new();
+    let mut deltas = Vec::new();
+
+    for entry in entries {
+        let key = BankKey::of(entry);
+        let clk = entry.clk.get();
+
+        if to.contains(entry.cmd_id) {
+            if key.is_wildcard() {
+                pending.retain(|bank, from_clk| {
+                    let paired = key.covers(bank);
+                    if paired {
+                        deltas.push(clk - *from_clk);
+                    }
+                    !paired
+                });
+                wildcards.retain(|wildcard| {
+                    let paired = key.covers(&wildcard.key);
+                    if paired {
+                        deltas.push(clk - wildcard.clk);
+                    }
+                    !paired
+                });
+            } else if let Some(from_clk) = pending.remove(&key) {
+                deltas.push(clk - from_clk);
+            } else if let Some(wildcard) = wildcards
+                .iter_mut()
+                .rev()
+                .find(|w| w.key.covers(&key) && !w.done.contains(&key))
+            {
+                deltas.push(clk - wildcard.clk);
+                wildcard.done.insert(key);
+            }
+        }
+
+        if from.contains(entry.cmd_id) {
+            if key.is_wildcard() {
+                // Replaces every older `from` it covers.
+                pending.retain(|bank, _| !key.covers(bank));
+                wildcards.retain(|wildcard| !key.covers(&wildcard.key));
+                wildcards.push(WildcardFrom {
+                    key,
+                    clk,
+                    done: HashSet::new(),
+                });
+            } else {
+                for wildcard in &mut wildcards {
+                    if wildcard.key.covers(&key) {
+                        wildcard.done.insert(key);
+                    }
+                }
+                pending.insert(key, clk);
+            }
+        }
+    }
+
+    let unpaired_wildcards = wildcards.iter().filter(|w| w.done.is_empty()).count();
+    (deltas, (pending.len() + unpaired_wildcards) as u64)
+}
+
+/// Measures, per bank, the cycles from a `from` command to the next `to` command on that bank.
+/// Each `from` is paired with at most one `to` per bank; a later `from` before the `to` replaces
+/// it. Banks that never see a `from` followed by a `to` contribute nothing.
+pub fn pair_latencies(entries: &[Entry], from: &CommandSet, to: &CommandSet) -> LatencyStats {
+    LatencyStats::from_deltas(&pair_deltas(entries, from, to).0)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowOpenStats {
+    pub durations: LatencyStats,
+    /// Banks whose last activation was never followed by a precharge before the trace ends.
+    pub still_open: u64,
+}
+
+/// How long rows stay open: per bank, the cycles from an ACT to the next PRE of that bank.
+pub fn row_open_durations(
+    entries: &[Entry],
+    activates: &CommandSet,
+    precharges: &CommandSet,
+) -> RowOpenStats {
+    let (deltas, still_open) = pair_deltas(entries, activates, precharges);
+
+    RowOpenStats {
+        durations: LatencyStats::from_deltas(&deltas),
+        still_open,
+    }
+}
+
+/// Clk gaps between consecutive occurrences of `cmd_id` among the entries matching `filter`,
+/// in clk order. Fewer than two occurrences yield an empty vector.
+pub fn inter_arrivals(entries: &[Entry], cmd_id: u8, filter: &EntryFilter) -> Vec<i64> {
+    let clks: Vec<i64> = entries
+        .iter()
+        .filter(|e| e.cmd_id == cmd_id && e.matches(filter))
+        .map(|e| e.clk.get())
+        .collect();
+
+    clks.windows(2).map(|w| w[1] - w[0]).collect()
+}
+
+/// The `quantiles` (each in [0, 1]) of the clk deltas between consecutive entries, using the
+/// nearest rank of the sorted deltas. All deltas are kept and sorted, so this costs 8 bytes per
+/// entry; `None` for every quantile if the trace has fewer than two entries.
+pub fn interval_quantiles(entries: &[Entry], quantiles: &[f64]) -> Vec<Option<i64>> {
+    let mut deltas: Vec<i64> = entries
+        .windows(2)
+        .map(|pair| pair[1].clk.get() - pair[0].clk.get())
+        .collect();
+    deltas.sort_unstable();
+
+    quantiles
+        .iter()
+        .map(|q| {
+            let last = deltas.len().checked_sub(1)?;
+            let rank = (q.clamp(0.0, 1.0) * last as f64).round() as usize;
+            Some(deltas[rank])
+        })
+        .collect()
+}
+
+/// Value of `cycles_since_anchor` for entries before the first anchor.
+pub const NO_ANCHOR: i64 = -1;
+
+/// For every entry, the cycles since the most recent `anchor` command (e.g. REF), exposing the
+/// phase of commands relative to it. Anchors themselves get 0, entries before the first anchor
+/// get `NO_ANCHOR`. `last_anchor` is the clk of the latest anchor in front of `entries`, so a
+/// window of a trace continues where the entries before it left off, see `last_anchor_clk`.
+pub fn cycles_since_anchor(entries: &[Entry], anchor: u8, last_anchor: Option<i64>) -> Vec<i64> {
+    let mut last_anchor = last_anchor;
+
+    entries
+        .iter()
+        .map(|entry| {
+            let clk = entry.clk.get();
+            if entry.cmd_id == anchor {
+                last_anchor = Some(clk);
+            }
+            last_anchor.map_or(NO_ANCHOR, |anchor_clk| clk - anchor_clk)
+        })
+        .collect()
+}
+
+/// Clk of the last `anchor` command in `entries`, scanning backwards from the end.
+pub fn last_anchor_clk(entries: &[Entry], anchor: u8) -> Option<i64> {
+    entries
+        .iter()
+        .rev()
+        .find(|entry| entry.cmd_id == anchor)
+        .map(|entry| entry.clk.get())
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// The effective tick size of the trace: the GCD of all non-zero clk deltas between consecutive
+/// entries. `None` if the trace has fewer than two distinct clks.
+pub fn clk_granularity(entries: &[Entry]) -> Option<u64> {
+    let granularity = entries
+        .windows(2)
+        .map(|pair| pair[1].clk.get().abs_diff(pair[0].clk.get()))
+        .filter(|delta| *delta > 0)
+        .fold(0, gcd);
+
+    (granularity > 0).then_some(granularity)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GapTotals {
+    pub count: u64,
+    pub cycles: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GapAttribution {
+    /// Gaps that start or end at a refresh, i.e. stalls the refresh makes necessary.
+    pub refresh: GapTotals,
+    /// All other gaps, i.e. scheduling bubbles.
+    pub other: GapTotals,
+}
+
+/// Idle gaps are clk deltas of at least `min_gap` cycles between consecutive entries. A gap
+/// counts as refresh-adjacent if the entry before or after it is one of `refreshes`.
+pub fn gap_attribution(entries: &[Entry], min_gap: u64, refreshes: &CommandSet) -> GapAttribution {
+    let mut attribution = GapAttribution::default();
+
+    for pair in entries.windows(2) {
+        let gap = pair[1].clk.get().abs_diff(pair[0].clk.get());
+        if gap == 0 || gap < min_gap {
+            continue;
+        }
+
+        let totals = if refreshes.contains(pair[0].cmd_id) || refreshes.contains(pair[1].cmd_id) {
+            &mut attribution.refresh
+        } else {
+            &mut attribution.other
+        };
+        totals.count += 1;
+        totals.cycles += gap;
+    }
+
+    attribution
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACT: u8 = 0;
+    const PRE: u8 = 1;
+    const RD: u8 = 2;
+    const WR: u8 = 3;
+
+    fn cmd(clk: i64, cmd_id: u8, bankgroup: i64, bank: i64) -> Entry {
+        Entry::new(clk, [0, 0, bankgroup, bank, 12, -1], cmd_id)
+    }
+
+    #[test]
+    fn pre_to_act_pairs_per_bank() {
+        let entries = [
+            cmd(0, PRE, 0, 0),
+            cmd(2, PRE, 0, 1),
+            cmd(10, ACT, 0, 1),
+            cmd(15, ACT, 0, 0),
+            // A second ACT without a PRE in between pairs with nothing.
+            cmd(20, ACT, 0, 0),
+            cmd(30, PRE, 0, 1),
+            cmd(31, PRE, 0, 1),
+            cmd(45, ACT, 0, 1),
+        ];
+
+        let stats = pair_latencies(
+            &entries,
+            &CommandSet::from_ids([PRE]),
+            &CommandSet::from_ids([ACT]),
+        );
+        assert_eq!(stats.count, 3);
+        assert_eq!(stats.histogram, BTreeMap::from([(8, 1), (14, 1), (15, 1)]));
+        assert_eq!((stats.min, stats.max), (Some(8), Some(15)));
+        assert_eq!(stats.avg, Some(37.0 / 3.0));
+    }
+
+    #[test]
+    fn precharge_all_closes_every_covered_bank() {
+        let entries = [
+            cmd(0, ACT, 0, 0),
+            cmd(4, ACT, 1, 2),
+            cmd(6, ACT, 0, 3),
+            // PREA of bank group 0 closes banks 0 and 3 there, but not bank group 1.
+            cmd(10, PRE, 0, -1),
+            cmd(20, ACT, 0, 0),
+            cmd(25, PRE, -1, -1),
+        ];
+
+        let stats = row_open_durations(
+            &entries,
+            &CommandSet::from_ids([ACT]),
+            &CommandSet::from_ids([PRE]),
+        );
+        assert_eq!(
+            stats.durations.histogram,
+            BTreeMap::from([(4, 1), (5, 1), (10, 1), (21, 1)])
+        );
+        assert_eq!(stats.still_open, 0);
+    }
+
+    #[test]
+    fn precharge_all_pairs_with_the_next_act_of_each_bank() {
+        let entries = [
+            cmd(0, PRE, 0, 0),
+            cmd(5, PRE, -1, -1),
+            cmd(9, ACT, 1, 1),
+            cmd(11, ACT, 0, 0),
+            cmd(12, ACT, 0, 0),
+            cmd(13, PRE, 1, 3),
+            cmd(16, ACT, 1, 3),
+            cmd(18, RD, 1, 2),
+        ];
+
+        let stats = pair_latencies(
+            &entries,
+            &CommandSet::from_ids([PRE]),
+            &CommandSet::from_ids([ACT]),
+        );
+        // Bank 1/3 pairs with its own PRE at 13, not with the older PREA.
+        assert_eq!(stats.histogram, BTreeMap::from([(3, 1), (4, 1), (6, 1)]));
+    }
+
+    #[test]
+    fn anchor_deltas_reset_at_every_anchor() {
+        const REF: u8 = 3;
+        let entries = [
+            cmd(0, RD, 0, 0),
+            cmd(5, REF, 0, 0),
+            cmd(9, ACT, 0, 0),
+            cmd(20, RD, 0, 0),
+            cmd(22, REF, 0, 0),
+            cmd(30, PRE, 0, 0),
+        ];
+
+        assert_eq!(
+            cycles_since_anchor(&entries, REF, None),
+            [NO_ANCHOR, 0, 4, 15, 0, 8]
+        );
+        assert_eq!(
+            cycles_since_anchor(&entries, 7, None),
+            [NO_ANCHOR; 6].to_vec()
+        );
+    }
+
+    #[test]
+    fn anchor_windows_continue_from_earlier_anchors() {
+        const REF: u8 = 3;
+        let entries = [
+            cmd(5, REF, 0, 0),
+            cmd(9, ACT, 0, 0),
+            cmd(20, RD, 0, 0),
+            cmd(22, REF, 0, 0),
+            cmd(30, PRE, 0, 0),
+        ];
+
+        let whole = cycles_since_anchor(&entries, REF, None);
+        for start in 0..entries.len() {
+            let last = last_anchor_clk(&entries[..start], REF);
+            assert_eq!(
+                cycles_since_anchor(&entries[start..], REF, last),
+                whole[start..],
+                "window from {start}"
+            );
+        }
+        assert_eq!(last_anchor_clk(&entries[..3], REF), Some(5));
+        assert_eq!(last_anchor_clk(&entries[..0], REF), None);
+    }
+
+    #[test]
+    fn granularity_is_the_gcd_of_nonzero_deltas() {
+        let clks =
+            |clks: &[i64]| -> Vec<Entry> { clks.iter().map(|&c| cmd(c, RD, 0, 0)).collect() };
+
+        assert_eq!(
+            clk_granularity(&clks(&[0, 12, 12, 30, 54, 54, 96])),
+            Some(6)
+        );
+        assert_eq!(clk_granularity(&clks(&[-8, 0, 4])), Some(4));
+        assert_eq!(clk_granularity(&clks(&[3, 10])), Some(7));
+    }
+
+    #[test]
+    fn granularity_needs_two_distinct_clks() {
+        assert_eq!(clk_granularity(&[]), None);
+        assert_eq!(clk_granularity(&[cmd(5, RD, 0, 0)]), None);
+        assert_eq!(
+            clk_granularity(&[cmd(5, RD, 0, 0), cmd(5, ACT, 0, 1)]),
+            None
+        );
+    }
+
+    #[test]
+    fn write_to_read_turnaround_per_bank() {
+        let entries = [
+            cmd(0, WR, 1, 2),
+            cmd(4, WR, 1, 2),
+            cmd(9, WR, 0, 3),
+            // Pairs with the later write to its bank.
+            cmd(21, RD, 1, 2),
+            cmd(22, RD, 1, 2),
+            cmd(25, RD, 0, 3),
+            // A bank that only reads has no turnaround.
+            cmd(30, RD, 2, 0),
+        ];
+
+        let stats = pair_latencies(
+            &entries,
+            &CommandSet::from_ids([WR]),
+            &CommandSet::from_ids([RD]),
+        );
+        assert_eq!(stats.histogram, BTreeMap::from([(16, 1), (17, 1)]));
+        assert_eq!((stats.count, stats.min, stats.max), (2, Some(16), Some(17)));
+        assert_eq!(stats.avg, Some(16.5));
+
+        let no_writes = pair_latencies(
+            &entries[3..],
+            &CommandSet::from_ids([WR]),
+            &CommandSet::from_ids([RD]),
+        );
+        assert_eq!(no_writes, LatencyStats::default());
+    }
+
+    #[test]
+    fn inter_arrivals_of_a_command_scoped_by_filter() {
+        const REF: u8 = 4;
+        let entries = [
+            cmd(0, REF, 0, 0),
+            cmd(3, RD, 0, 0),
+            cmd(10, REF, 1, 2),
+            cmd(30, REF, 0, 0),
+            cmd(31, RD, 1, 2),
+            cmd(70, REF, 0, 0),
+        ];
+
+        let all = inter_arrivals(&entries, REF, &EntryFilter::default());
+        assert_eq!(all, [10, 20, 40]);
+
+        let bankgroup_0 = EntryFilter {
+            bankgroup: Some(0),
+            ..EntryFilter::default()
+        };
+        assert_eq!(inter_arrivals(&entries, REF, &bankgroup_0), [30, 40]);
+        assert!(inter_arrivals(&entries, RD, &bankgroup_0).is_empty());
+    }
+
+    #[test]
+    fn rows_left_open_at_the_end_are_counted() {
+        let entries = [
+            cmd(0, ACT, 0, 0),
+            cmd(3, ACT, 1, 0),
+            cmd(20, PRE, 0, 0),
+            cmd(25, ACT, 0, 0),
+            cmd(26, ACT, 0, 2),
+            cmd(40, PRE, 1, 0),
+        ];
+
+        let stats = row_open_durations(
+            &entries,
+            &CommandSet::from_ids([ACT]),
+            &CommandSet::from_ids([PRE]),
+        );
+        assert_eq!(
+            stats.durations.histogram,
+            BTreeMap::from([(20, 1), (37, 1)])
+        );
+        // Bank (0, 0) was reopened at 25 and bank (0, 2) at 26, neither closed again.
+        assert_eq!(stats.still_open, 2);
+    }
+
+    #[test]
+    fn interval_quantiles_use_the_nearest_rank() {
+        // Deltas 1, 2, 2, 5, 10.
+        let entries: Vec<Entry> = [0, 1, 3, 5, 10, 20]
+            .iter()
+            .map(|&clk| cmd(clk, RD, 1, 1))
+            .collect();
+
+        assert_eq!(
+            interval_quantiles(&entries, &[0.0, 0.5, 0.9, 1.0]),
+            [Some(1), Some(2), Some(10), Some(10)]
+        );
+        // Quantiles outside [0, 1] clamp to the extremes.
+        assert_eq!(
+            interval_quantiles(&entries, &[-1.0, 3.0]),
+            [Some(1), Some(10)]
+        );
+        assert_eq!(interval_quantiles(&entries[..1], &[0.5]), [None]);
+    }
+
+    #[test]
+    fn write_recovery_through_the_classifier() {
+        use crate::analysis::classifier::{CategoryPatterns, CommandCategory, CommandClassifier};
+        use crate::trace::dictionary::Dictionary;
+
+        let dictionary = Dictionary::from_names(&["ACT", "WRA", "PRE", "WR", "RD"]);
+        let classifier = CommandClassifier::new(&dictionary, &CategoryPatterns::default());
+        let entries = [
+            cmd(0, 3, 0, 0),
+            cmd(1, 4, 0, 1),
+            cmd(6, 1, 0, 0),
+            cmd(18, 2, 0, 0),
+            // Bank 1 was only read, so its precharge has no write to recover from.
+            cmd(19, 2, 0, 1),
+        ];
+
+        let stats = pair_latencies(
+            &entries,
+            &classifier.set(CommandCategory::Write),
+            &classifier.set(CommandCategory::Precharge),
+        );
+        assert_eq!(stats.histogram, BTreeMap::from([(12, 1)]));
+        assert_eq!(
+            (stats.min, stats.avg, stats.max),
+            (Some(12), Some(12.0), Some(12))
+        );
+    }
+
+    #[test]
+    fn gaps_next_to_refreshes_are_attributed_to_them() {
+        const REF: u8 = 5;
+        let entries = [
+            cmd(0, RD, 0, 0),
+            cmd(100, REF, 0, 0),
+            cmd(400, ACT, 0, 0),
+            cmd(402, RD, 0, 0),
+            cmd(402, RD, 0, 1),
+            cmd(450, RD, 0, 1),
+            cmd(451, RD, 0, 1),
+        ];
+
+        let gaps = gap_attribution(&entries, 10, &CommandSet::from_ids([REF]));
+        assert_eq!((gaps.refresh.count, gaps.refresh.cycles), (2, 400));
+        // The 2- and 1-cycle gaps are below the minimum, the same-clk pair is no gap at all.
+        assert_eq!((gaps.other.count, gaps.other.cycles), (1, 48));
+
+        let every_gap = gap_attribution(&entries, 0, &CommandSet::from_ids([REF]));
+        assert_eq!((every_gap.other.count, every_gap.other.cycles), (3, 51));
+    }
+}
@@ -0,0 +1,183 @@
+/// This file implements user annotations of traces: labelled markers at a clk or over an entry
+/// range that the frontend overlays on the timeline.
+///
+/// Annotations live in a JSON sidecar next to the trace (`<trace>.annotations.json`), grouped by
+/// trace identity. A trace that is rewritten in place gets a new identity and so starts without
+/// annotations, while copies of the same trace share theirs. Writes go through
+/// `trace::writer::write_atomic`, so a crash never leaves a half-written sidecar.
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::trace::writer::write_atomic;
+
+/// What an annotation is attached to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Anchor {
+    Clk {
+        clk: i64,
+    },
+    /// The entries `start..end` by index.
+    Range {
+        start: u64,
+        end: u64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    /// Unique among the annotations of one trace.
+    pub id: u64,
+    pub anchor: Anchor,
+    pub label: String,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// On-disk shape of the sidecar: annotations per trace identity (16 hex digits).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Sidecar {
+    traces: BTreeMap<String, Vec<Annotation>>,
+}
+
+pub fn sidecar_path(trace_path: &Path) -> PathBuf {
+    let mut name = trace_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".annotations.json");
+    trace_path.with_file_name(name)
+}
+
+fn identity_key(identity: u64) -> String {
+    format!("{:016x}", identity)
+}
+
+fn read_sidecar(path: &Path) -> Result<Sidecar, String> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents).map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Sidecar::default()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn write_sidecar(path: &Path, sidecar: &Sidecar) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(sidecar).map_err(|e| e.to_string())?;
+    write_atomic(path, |writer| writer.write_all(&json)).map_err(|e| e.to_string())
+}
+
+pub fn list(trace_path: &Path, identity: u64) -> Result<Vec<Annotation>, String> {
+    let mut sidecar = read_sidecar(&sidecar_path(trace_path))?;
+    Ok(sidecar
+        .traces
+        .remove(&identity_key(identity))
+        .unwrap_or_default())
+}
+
+/// Adds an annotation with the next free id and returns it.
+pub fn add(
+    trace_path: &Path,
+    identity: u64,
+    anchor: Anchor,
+    label: String,
+    color: Option<String>,
+) -> Result<Annotation, String> {
+    let path = sidecar_path(trace_path);
+    let mut sidecar = read_sidecar(&path)?;
+    let annotations = sidecar.traces.entry(identity_key(identity)).or_default();
+
+    let annotation = Annotation {
+        id: annotations.iter().map(|a| a.id + 1).max().unwrap_or(0),
+        anchor,
+        label,
+        color,
+    };
+    annotations.push(annotation.clone());
+
+    write_sidecar(&path, &sidecar)?;
+    Ok(annotation)
+}
+
+/// Removes the annotation with `id`. Returns whether it existed.
+pub fn remove(trace_path: &Path, identity: u64, id: u64) -> Result<bool, String> {
+    let path = sidecar_path(trace_path);
+    let mut sidecar = read_sidecar(&path)?;
+    let key = identity_key(identity);
+
+    let Some(annotations) = sidecar.traces.get_mut(&key) else {
+        return Ok(false);
+    };
+    let before = annotations.len();
+    annotations.retain(|a| a.id != id);
+    if annotations.len() == before {
+        return Ok(false);
+    }
+    if annotations.is_empty() {
+        sidecar.traces.remove(&key);
+    }
+
+    write_sidecar(&path, &sidecar)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::writer::tests::scratch_path;
+
+    #[test]
+    fn annotations_round_trip_through_the_sidecar() {
+        let trace = scratch_path("annotated.bin");
+        let marker = add(
+            &trace,
+            7,
+            Anchor::Clk { clk: 120 },
+            "refresh storm".into(),
+            None,
+        )
+        .unwrap();
+        let range = add(
+            &trace,
+            7,
+            Anchor::Range { start: 10, end: 40 },
+            "warm-up".into(),
+            Some("#ffcc00".into()),
+        )
+        .unwrap();
+        assert_eq!((marker.id, range.id), (0, 1));
+        assert_eq!(list(&trace, 7).unwrap(), [marker.clone(), range.clone()]);
+
+        assert!(remove(&trace, 7, 0).unwrap());
+        assert!(!remove(&trace, 7, 0).unwrap());
+        assert_eq!(list(&trace, 7).unwrap(), [range]);
+
+        // Ids keep counting from the highest one left.
+        let next = add(&trace, 7, Anchor::Clk { clk: 5 }, "again".into(), None).unwrap();
+        assert_eq!(next.id, 2);
+
+        std::fs::remove_file(sidecar_path(&trace)).unwrap();
+    }
+
+    #[test]
+    fn traces_sharing_a_sidecar_keep_their_own_annotations() {
+        let trace = scratch_path("rewritten.bin");
+        add(&trace, 1, Anchor::Clk { clk: 0 }, "old".into(), None).unwrap();
+        add(&trace, 2, Anchor::Clk { clk: 0 }, "new".into(), None).unwrap();
+
+        assert_eq!(list(&trace, 1).unwrap()[0].label, "old");
+        assert_eq!(list(&trace, 2).unwrap()[0].label, "new");
+        assert!(list(&trace, 3).unwrap().is_empty());
+        assert!(!remove(&trace, 3, 0).unwrap());
+
+        // Removing the last annotation of a trace drops its key from the sidecar.
+        assert!(remove(&trace, 1, 0).unwrap());
+        let sidecar = read_sidecar(&sidecar_path(&trace)).unwrap();
+        assert_eq!(
+            sidecar.traces.keys().collect::<Vec<_>>(),
+            [&identity_key(2)]
+        );
+
+        std::fs::remove_file(sidecar_path(&trace)).unwrap();
+    }
+}
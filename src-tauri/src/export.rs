@@ -0,0 +1,20 @@
+/// This file groups the exporters that write (parts of) a loaded trace or its analyses to disk
+/// in formats meant for external tools (spreadsheets, scripts, ...).
+#[cfg(feature = "arrow")]
+pub mod arrow;
+pub mod density;
+pub mod folded;
+pub mod histogram;
+pub mod jsonl;
+pub mod progress;
+#[cfg(feature = "thumbnail")]
+pub mod thumbnail;
+
+/// Quotes a CSV field if it contains a separator, a quote or a line break.
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
@@ -0,0 +1,140 @@
+/// Exports entries as an Arrow IPC stream holding a single record batch, for Polars/Pandas.
+///
+/// Columns: clk (Int64), channel and rank (Int16), bankgroup, bank, row and column (Int32) and
+/// command (dictionary-encoded UInt8 -> Utf8, using the trace's dictionary). Invalid (negative)
+/// address components are nulls.
+use std::sync::Arc;
+
+use arrow_array::types::UInt8Type;
+use arrow_array::{
+    ArrayRef, DictionaryArray, Int16Array, Int32Array, Int64Array, RecordBatch, StringArray,
+    UInt8Array,
+};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{ArrowError, DataType, Field, Schema};
+
+use crate::trace::dictionary::Dictionary;
+use crate::trace::entry::{valid, Entry};
+
+pub fn schema() -> Schema {
+    let command = DataType::Dictionary(Box::new(DataType::UInt8), Box::new(DataType::Utf8));
+
+    Schema::new(vec![
+        Field::new("clk", DataType::Int64, false),
+        Field::new("channel", DataType::Int16, true),
+        Field::new("rank", DataType::Int16, true),
+        Field::new("bankgroup", DataType::Int32, true),
+        Field::new("bank", DataType::Int32, true),
+        Field::new("row", DataType::Int32, true),
+        Field::new("column", DataType::Int32, true),
+        Field::new("command", command, false),
+    ])
+}
+
+pub fn record_batch(entries: &[Entry], dictionary: &Dictionary) -> Result<RecordBatch, ArrowError> {
+    // Ids missing from the dictionary are named by their number, so every key has a value.
+    let num_names = entries
+        .iter()
+        .map(|e| e.cmd_id as usize + 1)
+        .max()
+        .unwrap_or(0)
+        .max(dictionary.commands.len());
+    let names = StringArray::from_iter_values((0..num_names).map(|id| {
+        dictionary
+            .commands
+            .get(&(id as u8))
+            .cloned()
+            .unwrap_or_else(|| id.to_string())
+    }));
+    let keys = UInt8Array::from_iter_values(entries.iter().map(|e| e.cmd_id));
+    let command = DictionaryArray::<UInt8Type>::try_new(keys, Arc::new(names))?;
+
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from_iter_values(
+            entries.iter().map(|e| e.clk.get()),
+        )),
+        Arc::new(Int16Array::from_iter(
+            entries.iter().map(|e| valid(e.channel.get())),
+        )),
+        Arc::new(Int16Array::from_iter(
+            entries.iter().map(|e| valid(e.rank.get())),
+        )),
+        Arc::new(Int32Array::from_iter(
+            entries.iter().map(|e| valid(e.bankgroup.get())),
+        )),
+        Arc::new(Int32Array::from_iter(
+            entries.iter().map(|e| valid(e.bank.get())),
+        )),
+        Arc::new(Int32Array::from_iter(
+            entries.iter().map(|e| valid(e.row.get())),
+        )),
+        Arc::new(Int32Array::from_iter(
+            entries.iter().map(|e| valid(e.column.get())),
+        )),
+        Arc::new(command),
+    ];
+
+    RecordBatch::try_new(Arc::new(schema()), columns)
+}
+
+/// Serializes the entries into an in-memory Arrow IPC stream.
+pub fn to_ipc_bytes(entries: &[Entry], dictionary: &Dictionary) -> Result<Vec<u8>, ArrowError> {
+    let batch = record_batch(entries, dictionary)?;
+
+    let mut bytes = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut bytes, &batch.schema())?;
+    writer.write(&batch)?;
+    writer.finish()?;
+    drop(writer);
+
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Int16Type, Int32Type, Int64Type};
+    use arrow_array::Array;
+    use arrow_ipc::reader::StreamReader;
+
+    #[test]
+    fn ipc_stream_reads_back_as_one_batch() {
+        let dictionary = Dictionary::from_names(&["ACT", "RD"]);
+        let entries = [
+            Entry::new(7, [1, 0, 2, 3, 99, -1], 0),
+            Entry::new(9, [1, -1, 2, 3, 99, 16], 1),
+            Entry::new(11, [0, 0, 0, 0, 0, 0], 4),
+        ];
+
+        let bytes = to_ipc_bytes(&entries, &dictionary).unwrap();
+        let batches: Vec<RecordBatch> = StreamReader::try_new(bytes.as_slice(), None)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(batches.len(), 1);
+
+        let batch = &batches[0];
+        assert_eq!(batch.schema().as_ref(), &schema());
+        assert_eq!(
+            batch.column(0).as_primitive::<Int64Type>().values(),
+            &[7, 9, 11]
+        );
+
+        let rank = batch.column(2).as_primitive::<Int16Type>();
+        assert!(rank.is_valid(0) && rank.is_null(1));
+        let column = batch.column(6).as_primitive::<Int32Type>();
+        assert_eq!(column.null_count(), 1);
+        assert_eq!(column.value(1), 16);
+
+        let command = batch.column(7).as_dictionary::<UInt8Type>();
+        let names = command.values().as_string::<i32>();
+        let resolved: Vec<&str> = command
+            .keys()
+            .values()
+            .iter()
+            .map(|key| names.value(*key as usize))
+            .collect();
+        assert_eq!(resolved, ["ACT", "RD", "4"]);
+    }
+}
@@ -0,0 +1,51 @@
+/// Exports the command density over time as a two-column CSV, `clk_start,total_commands`, one row
+/// per time bucket including empty ones, for gnuplot and the like.
+use std::io::Write;
+
+use crate::analysis::density::bucket_counts;
+use crate::trace::entry::Entry;
+
+pub fn write_csv<W: Write>(
+    writer: &mut W,
+    entries: &[Entry],
+    num_buckets: usize,
+) -> std::io::Result<()> {
+    writeln!(writer, "clk_start,total_commands")?;
+
+    for (clk_start, count) in bucket_counts(entries, num_buckets) {
+        writeln!(writer, "{},{}", clk_start, count)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_bucket_gets_a_row_and_no_command_is_lost() {
+        let entries: Vec<Entry> = [0, 1, 2, 25, 29]
+            .into_iter()
+            .map(|clk| Entry::new(clk, [0, 0, 0, 1, 3, 0], 2))
+            .collect();
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &entries, 3).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("clk_start,total_commands"));
+        let rows: Vec<(i64, u64)> = lines
+            .map(|line| {
+                let (clk_start, count) = line.split_once(',').unwrap();
+                (clk_start.parse().unwrap(), count.parse().unwrap())
+            })
+            .collect();
+        assert_eq!(rows, [(0, 3), (10, 0), (20, 2)]);
+        assert_eq!(
+            rows.iter().map(|(_, count)| count).sum::<u64>(),
+            entries.len() as u64
+        );
+    }
+}
@@ -0,0 +1,77 @@
+/// Exports per-bank command counts as folded stacks, the input format of flamegraph tools:
+/// one `channel0;bankgroup1;bank3 1234` line per bank, sorted by address. The first invalid
+/// component of an address ends its stack in an `unknown` leaf, so e.g. all entries without a
+/// bank group of channel 0 are counted under `channel0;unknown`.
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::trace::entry::Entry;
+
+const LEVELS: [&str; 3] = ["channel", "bankgroup", "bank"];
+
+/// The address as a stack, with everything from the first invalid component on set to -1.
+fn stack(entry: &Entry) -> [i64; 3] {
+    let mut stack = [
+        entry.channel.get() as i64,
+        entry.bankgroup.get() as i64,
+        entry.bank.get() as i64,
+    ];
+    if let Some(first_invalid) = stack.iter().position(|component| *component < 0) {
+        stack[first_invalid..].fill(-1);
+    }
+    stack
+}
+
+pub fn write_folded<W: Write>(writer: &mut W, entries: &[Entry]) -> std::io::Result<()> {
+    let mut counts: BTreeMap<[i64; 3], u64> = BTreeMap::new();
+    for entry in entries {
+        *counts.entry(stack(entry)).or_insert(0) += 1;
+    }
+
+    for (stack, count) in counts {
+        let mut frames = Vec::with_capacity(LEVELS.len());
+        for (level, component) in LEVELS.iter().zip(stack) {
+            if component < 0 {
+                frames.push("unknown".to_string());
+                break;
+            }
+            frames.push(format!("{}{}", level, component));
+        }
+
+        writeln!(writer, "{} {}", frames.join(";"), count)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stacks_end_at_the_first_invalid_component() {
+        let entries = [
+            Entry::new(0, [0, 0, 1, 3, 10, 0], 2),
+            Entry::new(1, [0, 0, 1, 3, 11, 0], 2),
+            Entry::new(2, [0, 0, 0, 2, 10, 0], 2),
+            Entry::new(3, [0, 0, -1, -1, -1, -1], 4),
+            // The bank is ignored once the bank group is unknown.
+            Entry::new(4, [0, 0, -1, 5, -1, -1], 4),
+            Entry::new(5, [1, 0, 2, -1, -1, -1], 1),
+        ];
+
+        let mut folded = Vec::new();
+        write_folded(&mut folded, &entries).unwrap();
+        let folded = String::from_utf8(folded).unwrap();
+
+        assert_eq!(
+            folded.lines().collect::<Vec<_>>(),
+            [
+                "channel0;unknown 2",
+                "channel0;bankgroup0;bank2 1",
+                "channel0;bankgroup1;bank3 2",
+                "channel1;bankgroup2;unknown 1",
+            ]
+        );
+    }
+}
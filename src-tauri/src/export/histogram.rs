@@ -0,0 +1,77 @@
+/// Exports the command histogram as CSV with `command,count,percentage` rows,
+/// sorted by count descending (ties by command id). An empty trace produces a header-only file.
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::export::csv_field;
+use crate::trace::dictionary::Dictionary;
+
+pub fn write_csv<W: Write>(
+    writer: &mut W,
+    counts: &BTreeMap<u8, u64>,
+    dictionary: &Dictionary,
+) -> std::io::Result<()> {
+    writeln!(writer, "command,count,percentage")?;
+
+    let total: u64 = counts.values().sum();
+
+    let mut rows: Vec<(u8, u64)> = counts.iter().map(|(id, count)| (*id, *count)).collect();
+    rows.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    for (cmd_id, count) in rows {
+        let name = dictionary
+            .commands
+            .get(&cmd_id)
+            .cloned()
+            .unwrap_or_else(|| cmd_id.to_string());
+        let percentage = count as f64 / total as f64 * 100.0;
+
+        writeln!(writer, "{},{},{:.4}", csv_field(&name), count, percentage)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_sorted_by_count_with_names_and_percentages() {
+        let dictionary = Dictionary::from_names(&["ACT", "RD", "WR,A"]);
+        let counts = BTreeMap::from([(0, 25), (1, 50), (2, 25), (7, 0)]);
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &counts, &dictionary).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                "command,count,percentage",
+                "RD,50,50.0000",
+                "ACT,25,25.0000",
+                "\"WR,A\",25,25.0000",
+                "7,0,0.0000",
+            ]
+        );
+
+        let total: f64 = lines[1..]
+            .iter()
+            .map(|line| line.rsplit(',').next().unwrap().parse::<f64>().unwrap())
+            .sum();
+        assert!((total - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn empty_histogram_is_header_only() {
+        let dictionary = Dictionary {
+            commands: Default::default(),
+        };
+
+        let mut csv = Vec::new();
+        write_csv(&mut csv, &BTreeMap::new(), &dictionary).unwrap();
+        assert_eq!(csv, b"command,count,percentage\n");
+    }
+}
@@ -0,0 +1,96 @@
+/// Exports entries as JSON lines, one object per entry:
+/// `{"clk":120,"command":"RD","channel":0,"rank":0,"bankgroup":1,"bank":2,"row":42,"column":8}`.
+/// Invalid (negative) address components are written as `null`. Entries are serialized straight
+/// into the writer, so memory stays bounded regardless of the trace size.
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::trace::dictionary::Dictionary;
+use crate::trace::entry::{valid, Entry};
+
+#[derive(Serialize)]
+struct Line<'a> {
+    clk: i64,
+    command: &'a str,
+    channel: Option<i16>,
+    rank: Option<i16>,
+    bankgroup: Option<i32>,
+    bank: Option<i32>,
+    row: Option<i32>,
+    column: Option<i32>,
+}
+
+pub fn write_jsonl<W: Write>(
+    writer: &mut W,
+    entries: &[Entry],
+    dictionary: &Dictionary,
+) -> std::io::Result<()> {
+    // Ids missing from the dictionary are written as their number.
+    let fallback_names: Vec<String> = (0..=u8::MAX).map(|id| id.to_string()).collect();
+
+    for entry in entries {
+        let command = dictionary
+            .commands
+            .get(&entry.cmd_id)
+            .unwrap_or(&fallback_names[entry.cmd_id as usize]);
+
+        let line = Line {
+            clk: entry.clk.get(),
+            command,
+            channel: valid(entry.channel.get()),
+            rank: valid(entry.rank.get()),
+            bankgroup: valid(entry.bankgroup.get()),
+            bank: valid(entry.bank.get()),
+            row: valid(entry.row.get()),
+            column: valid(entry.column.get()),
+        };
+
+        serde_json::to_writer(&mut *writer, &line)?;
+        writer.write_all(b"\n")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_line_is_an_object_matching_its_entry() {
+        let dictionary = Dictionary::from_names(&["ACT", "RD"]);
+        let entries = [
+            Entry::new(120, [0, 0, 1, 2, 42, -1], 0),
+            Entry::new(124, [0, 0, 1, 2, 42, 8], 1),
+            Entry::new(-3, [-1, -1, -1, -1, -1, -1], 9),
+        ];
+
+        let mut out = Vec::new();
+        write_jsonl(&mut out, &entries, &dictionary).unwrap();
+        let out = String::from_utf8(out).unwrap();
+
+        let lines: Vec<serde_json::Value> = out
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), entries.len());
+
+        assert_eq!(
+            lines[1],
+            serde_json::json!({
+                "clk": 124, "command": "RD", "channel": 0, "rank": 0,
+                "bankgroup": 1, "bank": 2, "row": 42, "column": 8,
+            })
+        );
+        assert_eq!(lines[0]["column"], serde_json::Value::Null);
+        assert_eq!(lines[0]["command"], "ACT");
+
+        // Unknown ids fall back to their number, invalid components to null.
+        assert_eq!(lines[2]["command"], "9");
+        assert_eq!(lines[2]["clk"], -3);
+        for field in ["channel", "rank", "bankgroup", "bank", "row", "column"] {
+            assert!(lines[2][field].is_null(), "{} should be null", field);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+/// Progress reporting and cancellation for long-running exports.
+///
+/// Exporters write in chunks of `PROGRESS_INTERVAL` entries and call `ExportProgress::report`
+/// between them, which emits `export-progress` (`{ fraction }`, in [0, 1]) and fails with
+/// `ErrorKind::Interrupted` once `cancel` was called. Exports go through `write_atomic`, so
+/// failing midway removes the partial file and leaves an existing one at the path untouched.
+/// `write_chunked` loads the chunks under a short loader lock each, like `search`, so other
+/// commands keep working during an export.
+///
+/// Only exports that write every entry of a range report progress (JSON lines, compressed).
+/// The aggregate exports (histogram, density, folded stacks) take one pass over the trace and
+/// write a few rows, and the Arrow IPC bytes are built in memory for a view-capped range, so they
+/// have no progress to report and run to completion.
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::session::SessionState;
+use crate::trace::entry::Entry;
+
+/// Entries written between two progress events.
+pub const PROGRESS_INTERVAL: usize = 1 << 16;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressEvent {
+    fraction: f64,
+}
+
+pub struct ExportProgress {
+    emit: Box<dyn Fn(f64) + Send + Sync>,
+    cancel: Arc<AtomicBool>,
+    total: u64,
+}
+
+impl ExportProgress {
+    /// Registers a new export of `total` entries, cancelling the running one, if any.
+    pub fn begin<R: Runtime>(
+        app: AppHandle<R>,
+        session: &SessionState,
+        total: u64,
+    ) -> Result<Self, String> {
+        Self::with_emitter(session, total, move |fraction| {
+            if let Err(e) = app.emit("export-progress", ProgressEvent { fraction }) {
+                log::warn!("Failed to emit export-progress: {}", e);
+            }
+        })
+    }
+
+    /// Like `begin`, reporting fractions to `emit` instead of as events.
+    fn with_emitter(
+        session: &SessionState,
+        total: u64,
+        emit: impl Fn(f64) + Send + Sync + 'static,
+    ) -> Result<Self, String> {
+        let cancel = Arc::new(AtomicBool::new(false));
+        {
+            let mut guard = session.export_cancel.lock().map_err(|e| e.to_string())?;
+            if let Some(previous) = guard.replace(cancel.clone()) {
+                previous.store(true, Ordering::Relaxed);
+            }
+        }
+
+        Ok(Self {
+            emit: Box::new(emit),
+            cancel,
+            total,
+        })
+    }
+
+    /// Reports that `done` entries have been written.
+    pub fn report(&self, done: u64) -> std::io::Result<()> {
+        if self.cancel.load(Ordering::Relaxed) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Interrupted,
+                "export cancelled",
+            ));
+        }
+
+        let fraction = if self.total == 0 {
+            1.0
+        } else {
+            (done as f64 / self.total as f64).min(1.0)
+        };
+        (self.emit)(fraction);
+
+        Ok(())
+    }
+}
+
+/// Passes the `count` entries from `start` to `write` in chunks of `PROGRESS_INTERVAL`, taking the
+/// loader lock once per chunk, and reports progress after each. `write` runs with the lock held.
+/// Fails if the loaded trace is no longer the one with `identity`, i.e. it was closed or replaced
+/// during the export.
+pub fn write_chunked(
+    session: &SessionState,
+    identity: u64,
+    start: u64,
+    count: u64,
+    progress: &ExportProgress,
+    mut write: impl FnMut(&[Entry]) -> io::Result<()>,
+) -> io::Result<()> {
+    let mut done = 0;
+    while done < count {
+        let chunk = (count - done).min(PROGRESS_INTERVAL as u64);
+        {
+            let guard = session
+                .loader
+                .lock()
+                .map_err(|e| io::Error::other(e.to_string()))?;
+            let loader = guard
+                .as_ref()
+                .filter(|loader| loader.identity() == identity)
+                .ok_or_else(|| io::Error::other("The trace changed during the export"))?;
+
+            write(loader.load_entry_slice(start + done, chunk as usize)?)?;
+        }
+        done += chunk;
+        progress.report(done)?;
+    }
+    Ok(())
+}
+
+/// Cancels the running export, if any.
+pub fn cancel(session: &SessionState) -> Result<(), String> {
+    let guard = session.export_cancel.lock().map_err(|e| e.to_string())?;
+    if let Some(cancel) = guard.as_ref() {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::trace::dictionary::Dictionary;
+    use crate::trace::writer::{self, tests::scratch_path};
+    use crate::trace::TraceLoader;
+
+    const NUM_ENTRIES: u64 = PROGRESS_INTERVAL as u64 * 2 + 10;
+
+    /// A session with a loaded trace of `NUM_ENTRIES` entries written to `name`.
+    fn session_with_trace(name: &str) -> (SessionState, std::path::PathBuf) {
+        let path = scratch_path(name);
+        let entries: Vec<Entry> = (0..NUM_ENTRIES as i64)
+            .map(|clk| Entry::new(clk, [0, 0, 0, clk % 4, -1, -1], 0))
+            .collect();
+        writer::write_trace(&path, 1, &entries, &Dictionary::from_names(&["RD"])).unwrap();
+
+        let session = SessionState::new();
+        *session.loader.lock().unwrap() = Some(TraceLoader::new(path.clone()).unwrap());
+        (session, path)
+    }
+
+    fn identity(session: &SessionState) -> u64 {
+        session.loader.lock().unwrap().as_ref().unwrap().identity()
+    }
+
+    #[test]
+    fn chunks_cover_the_range_and_report_progress() {
+        let (session, path) = session_with_trace("progress-chunks.bin");
+        let fractions = Arc::new(Mutex::new(Vec::new()));
+        let reported = fractions.clone();
+        let progress = ExportProgress::with_emitter(&session, NUM_ENTRIES - 5, move |f| {
+            reported.lock().unwrap().push(f)
+        })
+        .unwrap();
+
+        let mut clks = Vec::new();
+        write_chunked(
+            &session,
+            identity(&session),
+            5,
+            NUM_ENTRIES - 5,
+            &progress,
+            |chunk| {
+                clks.push((chunk[0].clk.get(), chunk.len()));
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        let interval = PROGRESS_INTERVAL as i64;
+        assert_eq!(
+            clks,
+            [
+                (5, PROGRESS_INTERVAL),
+                (5 + interval, PROGRESS_INTERVAL),
+                (5 + 2 * interval, 5)
+            ]
+        );
+        let fractions = fractions.lock().unwrap();
+        assert_eq!(fractions.len(), 3);
+        assert!(fractions.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(fractions.last(), Some(&1.0));
+
+        drop(session);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn cancelling_removes_the_partial_output() {
+        let (session, path) = session_with_trace("progress-cancel.bin");
+        let out = scratch_path("progress-cancel.jsonl");
+        std::fs::write(&out, "previous export\n").unwrap();
+
+        let progress = ExportProgress::with_emitter(&session, NUM_ENTRIES, |_| {}).unwrap();
+        let mut chunks = 0;
+        let result = writer::write_atomic(&out, |file| {
+            write_chunked(
+                &session,
+                identity(&session),
+                0,
+                NUM_ENTRIES,
+                &progress,
+                |chunk| {
+                    chunks += 1;
+                    writeln!(file, "{}", chunk.len())?;
+                    cancel(&session).map_err(io::Error::other)
+                },
+            )
+        });
+
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::Interrupted);
+        assert_eq!(chunks, 1);
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "previous export\n");
+        let leftovers = std::fs::read_dir(out.parent().unwrap())
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                name.starts_with(&*out.file_name().unwrap().to_string_lossy())
+                    && name.ends_with(".tmp")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        drop(session);
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(out).unwrap();
+    }
+
+    #[test]
+    fn a_closed_trace_ends_the_export() {
+        let (session, path) = session_with_trace("progress-closed.bin");
+        let session = Arc::new(session);
+        let identity = identity(&session);
+
+        // Progress is reported between chunks, outside the loader lock.
+        let closing = session.clone();
+        let progress = ExportProgress::with_emitter(&session, NUM_ENTRIES, move |_| {
+            *closing.loader.lock().unwrap() = None;
+        })
+        .unwrap();
+
+        let mut chunks = 0;
+        let result = write_chunked(&session, identity, 0, NUM_ENTRIES, &progress, |_| {
+            chunks += 1;
+            Ok(())
+        });
+        assert!(result.is_err());
+        assert_eq!(chunks, 1);
+
+        std::fs::remove_file(path).unwrap();
+    }
+}
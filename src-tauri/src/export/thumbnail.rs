@@ -0,0 +1,114 @@
+/// Renders a trace range into a PNG timeline for reports and other headless uses.
+///
+/// Mirrors the frontend's swimlanes in their simplest form: one row per bank (flat bank index of
+/// the memory layout, channel-major), time on the x axis scaled to the range's clk span, and
+/// one rectangle per entry, as wide as its clock period but at least one pixel. Colors come from
+/// the command config; unconfigured commands are drawn grey like in the frontend.
+/// Entries whose bank is invalid or outside the layout are not drawn.
+use tiny_skia::{Color, Paint, Pixmap, Rect, Transform};
+
+use crate::analysis::clk_bounds;
+use crate::session::MemoryLayout;
+use crate::trace::entry::Entry;
+
+const BACKGROUND: Color = Color::WHITE;
+
+/// Largest thumbnail rendered, in pixels (4096 x 4096). The pixmap alone takes 4 bytes per pixel.
+pub const MAX_THUMBNAIL_PIXELS: u64 = 4096 * 4096;
+
+pub fn render_png(
+    entries: &[Entry],
+    layout: &MemoryLayout,
+    colors: &[[u8; 3]; 256],
+    periods: &[f32; 256],
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, String> {
+    if width as u64 * height as u64 > MAX_THUMBNAIL_PIXELS {
+        return Err(format!(
+            "Thumbnail of {}x{} exceeds the limit of {} pixels",
+            width, height, MAX_THUMBNAIL_PIXELS
+        ));
+    }
+
+    let mut pixmap =
+        Pixmap::new(width, height).ok_or_else(|| "Thumbnail size must be non-zero".to_string())?;
+    pixmap.fill(BACKGROUND);
+
+    let num_rows = layout.flat_bank_count();
+    let Some((min_clk, max_clk)) = clk_bounds(entries) else {
+        return pixmap.encode_png().map_err(|e| e.to_string());
+    };
+    if num_rows == 0 {
+        return pixmap.encode_png().map_err(|e| e.to_string());
+    }
+
+    let span = (max_clk as f64 - min_clk as f64 + 1.0).max(1.0);
+    let x_scale = width as f64 / span;
+    let row_height = height as f32 / num_rows as f32;
+
+    let mut paint = Paint::default();
+    for entry in entries {
+        let Some(row) = layout.flat_bank_index(entry) else {
+            continue;
+        };
+
+        let x = ((entry.clk.get() as f64 - min_clk as f64) * x_scale) as f32;
+        let w = (periods[entry.cmd_id as usize] as f64 * x_scale).max(1.0) as f32;
+        let y = row as f32 * row_height;
+
+        let [r, g, b] = colors[entry.cmd_id as usize];
+        paint.set_color_rgba8(r, g, b, 255);
+
+        if let Some(rect) = Rect::from_xywh(x, y, w, row_height.max(1.0)) {
+            pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+        }
+    }
+
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUT: MemoryLayout = MemoryLayout {
+        num_channels: 1,
+        num_bankgroups: 1,
+        num_banks: 2,
+    };
+
+    #[test]
+    fn entries_are_drawn_in_their_bank_row() {
+        let mut colors = [[128; 3]; 256];
+        colors[1] = [255, 0, 0];
+        let periods = [1.0; 256];
+        let entries = [
+            Entry::new(0, [0, 0, 0, 1, 0, 0], 1),
+            Entry::new(5, [0, 0, 0, 7, 0, 0], 1),
+            Entry::new(9, [0, 0, 0, 1, 0, 0], 1),
+        ];
+
+        let png = render_png(&entries, &LAYOUT, &colors, &periods, 10, 4).unwrap();
+        let pixmap = Pixmap::decode_png(&png).unwrap();
+        let red = |x: u32, y: u32| {
+            let pixel = pixmap.pixel(x, y).unwrap();
+            (pixel.red(), pixel.green(), pixel.blue()) == (255, 0, 0)
+        };
+
+        // Bank 0 is the top half, bank 1 the bottom one; bank 7 is outside the layout.
+        assert!(red(0, 2) && red(0, 3) && red(9, 3));
+        assert!(!red(0, 1) && !red(5, 1) && !red(5, 3));
+    }
+
+    #[test]
+    fn oversized_thumbnails_are_rejected() {
+        let colors = [[0; 3]; 256];
+        let periods = [1.0; 256];
+
+        assert!(render_png(&[], &LAYOUT, &colors, &periods, 8192, 8192).is_err());
+        assert!(render_png(&[], &LAYOUT, &colors, &periods, u32::MAX, 2).is_err());
+        assert!(render_png(&[], &LAYOUT, &colors, &periods, 4096, 16).is_ok());
+        assert!(render_png(&[], &LAYOUT, &colors, &periods, 0, 16).is_err());
+    }
+}
@@ -1,49 +1,113 @@
+mod analysis;
+mod annotations;
+mod export;
+mod search;
 mod session;
 mod trace;
+mod view_stream;
 
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use tauri::ipc::Response;
 use tauri::{AppHandle, State};
 
+use crate::analysis::classifier::{CategoryPatterns, CommandCategory, CommandClassifier};
 use crate::session::{CommandConfig, MemoryLayout, SessionState};
 
+/// Classifier for the loaded trace's dictionary using the configured category patterns.
+fn command_classifier(
+    app: &AppHandle,
+    session: &SessionState,
+    loader: &trace::TraceLoader,
+) -> Result<CommandClassifier, String> {
+    let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+    let patterns = session::command_categories(app, session)?;
+    Ok(CommandClassifier::new(&dictionary, &patterns))
+}
+
+/// The memory layout in effect, for commands that can't do without one.
+fn memory_layout(app: &AppHandle, session: &SessionState) -> Result<MemoryLayout, String> {
+    session::current_memory_layout(app, session)?.ok_or_else(|| "No memory layout set".to_string())
+}
+
+/// Cheap check whether a file looks like a trace, e.g. for drag and drop. Never fails; unreadable
+/// files are reported as `false`. See `trace::is_trace_file`.
+#[tauri::command]
+fn is_ramwiz_trace(path: String) -> bool {
+    trace::is_trace_file(Path::new(&path))
+}
+
+/// Loads a trace. If both `start_clk` and `end_clk` are given, only that clk slice is loaded,
+/// see `TraceLoader::new_ranged`.
 #[tauri::command]
 fn load_trace(
     app: AppHandle,
     path: String,
+    start_clk: Option<i64>,
+    end_clk: Option<i64>,
     session: State<'_, SessionState>,
 ) -> Result<trace::header::Header, String> {
-    let loader = trace::TraceLoader::new(PathBuf::from(path)).map_err(|e| e.to_string())?;
+    let path = PathBuf::from(path);
+    let loader = match (start_clk, end_clk) {
+        (Some(start_clk), Some(end_clk)) => {
+            trace::TraceLoader::new_ranged(path, start_clk, end_clk)
+        }
+        _ => trace::TraceLoader::new(path),
+    }
+    .map_err(|e| e.to_string())?;
 
-    let header = *loader.header();
+    let header = loader.header();
     {
         let mut guard = session.loader.lock().map_err(|e| e.to_string())?;
         *guard = Some(loader);
     }
+    session::clear_address_ranges(&session)?;
 
     let config = session::load_command_config(&app)?;
+    let sentinels = config
+        .as_ref()
+        .map(|c| c.invalid_sentinels.clone())
+        .unwrap_or_default();
     {
         let mut guard = session.config.lock().map_err(|e| e.to_string())?;
         *guard = config;
     }
+    session::apply_sentinels(&session, &sentinels)?;
 
     Ok(header)
 }
 
+#[derive(serde::Serialize)]
+struct ReloadResult {
+    status: trace::ReloadStatus,
+    header: trace::header::Header,
+}
+
 #[tauri::command]
-fn get_command_config(
-    app: AppHandle,
-    session: State<'_, SessionState>,
-) -> Result<Option<CommandConfig>, String> {
-    let guard = session.config.lock().map_err(|e| e.to_string())?;
+fn reload_trace(session: State<'_, SessionState>) -> Result<ReloadResult, String> {
+    let mut guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = guard
+        .as_mut()
+        .ok_or_else(|| "No trace loaded".to_string())?;
 
-    if let Some(config) = guard.as_ref() {
-        return Ok(Some(config.clone()));
+    let status = loader.reload().map_err(|e| e.to_string())?;
+    if status != trace::ReloadStatus::Unchanged {
+        session::clear_address_ranges(&session)?;
     }
 
-    drop(guard);
+    Ok(ReloadResult {
+        status,
+        header: loader.header(),
+    })
+}
 
-    session::load_command_config(&app)
+#[tauri::command]
+fn get_command_config(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<Option<CommandConfig>, String> {
+    session::current_command_config(&app, &session)
 }
 
 #[tauri::command]
@@ -55,17 +119,116 @@ fn set_command_config(
     session::set_command_config(&app, &session, config).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn set_command_color(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+    cmd_id: u8,
+    hex: String,
+) -> Result<(), String> {
+    session::patch_command_config(&app, &session, |config| {
+        config.colors.insert(cmd_id, hex);
+    })
+}
+
+#[tauri::command]
+fn set_command_clock_period(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+    cmd_id: u8,
+    period: f32,
+) -> Result<(), String> {
+    session::patch_command_config(&app, &session, |config| {
+        config.clock_periods.insert(cmd_id, period);
+    })
+}
+
+#[tauri::command]
+fn get_command_aliases(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<HashMap<u8, String>, String> {
+    Ok(session::current_command_config(&app, &session)?
+        .map(|c| c.command_aliases)
+        .unwrap_or_default())
+}
+
+/// Sets the display name of a command; `None` or an empty alias removes it.
+#[tauri::command]
+fn set_command_alias(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+    cmd_id: u8,
+    alias: Option<String>,
+) -> Result<(), String> {
+    session::patch_command_config(&app, &session, |config| match alias {
+        Some(alias) if !alias.is_empty() => {
+            config.command_aliases.insert(cmd_id, alias);
+        }
+        _ => {
+            config.command_aliases.remove(&cmd_id);
+        }
+    })
+}
+
+/// Name, display label, color and clock period of every command of the loaded trace.
+#[tauri::command]
+fn get_command_legend(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<Vec<session::LegendEntry>, String> {
+    let config = session::current_command_config(&app, &session)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+    Ok(session::command_legend(config.as_ref(), &dictionary))
+}
+
+/// Id, name and color of every command of the loaded trace, for a fast startup.
+#[tauri::command]
+fn get_command_palette(
+    session: State<'_, SessionState>,
+) -> Result<Vec<session::PaletteEntry>, String> {
+    let config = {
+        let guard = session.config.lock().map_err(|e| e.to_string())?;
+        guard.clone()
+    };
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+    Ok(session::command_palette(config.as_ref(), &dictionary))
+}
+
+/// The command config as arrays indexed by cmd_id, sized to the loaded trace's dictionary.
+#[tauri::command]
+fn get_render_config(session: State<'_, SessionState>) -> Result<session::RenderConfig, String> {
+    let num_commands = {
+        let guard = session.loader.lock().map_err(|e| e.to_string())?;
+        guard
+            .as_ref()
+            .ok_or_else(|| "No trace loaded".to_string())?
+            .header()
+            .num_commands()
+    };
+
+    let config = session.config.lock().map_err(|e| e.to_string())?;
+    Ok(session::render_config(config.as_ref(), num_commands))
+}
+
 #[tauri::command]
 fn get_memory_layout(
     app: AppHandle,
     session: State<'_, SessionState>,
 ) -> Result<Option<MemoryLayout>, String> {
-    let guard = session.memory.lock().map_err(|e| e.to_string())?;
-    if let Some(layout) = guard.as_ref() {
-        return Ok(Some(layout.clone()));
-    }
-    drop(guard);
-    session::load_memory_layout(&app)
+    session::current_memory_layout(&app, &session)
 }
 
 #[tauri::command]
@@ -77,8 +240,65 @@ fn set_memory_layout(
     session::set_memory_layout(&app, &session, layout).map_err(|e| e.to_string())
 }
 
+/// The stored memory layout compared to the one detected from the loaded trace.
+#[tauri::command]
+fn get_layout_diff(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<session::LayoutDiff, String> {
+    let stored = session::current_memory_layout(&app, &session)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(session::layout_diff(stored, MemoryLayout::detect(entries)))
+}
+
+#[tauri::command]
+fn list_filter_presets(app: AppHandle) -> Result<Vec<session::FilterPreset>, String> {
+    session::list_filter_presets(&app)
+}
+
+/// Saves a named filter, replacing any preset of the same name.
+#[tauri::command]
+fn save_filter_preset(
+    app: AppHandle,
+    name: String,
+    filter: trace::EntryFilter,
+) -> Result<(), String> {
+    session::save_filter_preset(&app, name, filter)
+}
+
+#[tauri::command]
+fn delete_filter_preset(app: AppHandle, name: String) -> Result<bool, String> {
+    session::delete_filter_preset(&app, &name)
+}
+
+#[tauri::command]
+fn get_command_categories(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<CategoryPatterns, String> {
+    session::command_categories(&app, &session)
+}
+
+#[tauri::command]
+fn set_command_categories(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+    patterns: CategoryPatterns,
+) -> Result<(), String> {
+    session::set_command_categories(&app, &session, patterns)
+}
+
+/// Loads the dictionary of the current trace. With `lossy`, command names that aren't valid UTF-8
+/// are recovered with replacement characters instead of failing the whole load.
 #[tauri::command]
 fn load_dictionary(
+    lossy: Option<bool>,
     session: State<'_, SessionState>,
 ) -> Result<trace::dictionary::Dictionary, String> {
     let guard = session.loader.lock().map_err(|e| e.to_string())?;
@@ -87,7 +307,51 @@ fn load_dictionary(
         .as_ref()
         .ok_or_else(|| "No trace loaded. Call load_trace first.".to_string())?;
 
-    loader.load_dictionary().map_err(|e| e.to_string())
+    if !lossy.unwrap_or(false) {
+        return loader.load_dictionary().map_err(|e| e.to_string());
+    }
+
+    let (dictionary, recovered) = loader.load_dictionary_lossy().map_err(|e| e.to_string())?;
+    if !recovered.is_empty() {
+        log::warn!("Recovered non-UTF-8 names of commands {:?}", recovered);
+    }
+
+    Ok(dictionary)
+}
+
+#[tauri::command]
+fn search_commands(
+    query: String,
+    session: State<'_, SessionState>,
+) -> Result<Vec<(u8, String)>, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+
+    let loader = guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded. Call load_trace first.".to_string())?;
+
+    let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+    Ok(dictionary.search(&query))
+}
+
+#[tauri::command]
+fn get_used_commands(session: State<'_, SessionState>) -> Result<Vec<(u8, String)>, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+
+    let loader = guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded. Call load_trace first.".to_string())?;
+
+    let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+    let used = loader.used_command_ids().map_err(|e| e.to_string())?;
+
+    Ok(used
+        .into_iter()
+        .map(|id| {
+            let name = dictionary.commands.get(&id).cloned().unwrap_or_default();
+            (id, name)
+        })
+        .collect())
 }
 
 #[tauri::command]
@@ -99,12 +363,58 @@ fn get_entry_index_by_time(time: i64, session: State<'_, SessionState>) -> Resul
     loader.find_index_for_time(time).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn jump_to_clk(
+    time: i64,
+    session: State<'_, SessionState>,
+) -> Result<Option<trace::ClkJump>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+    loader.jump_to_clk(time).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_entry_offset(index: u64, session: State<'_, SessionState>) -> Result<Option<u64>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+    Ok(loader.entry_offset(index))
+}
+
+#[tauri::command]
+fn get_max_view_entries(session: State<'_, SessionState>) -> Result<u64, String> {
+    let guard = session.max_view_entries.lock().map_err(|e| e.to_string())?;
+    Ok(*guard)
+}
+
+#[tauri::command]
+fn set_max_view_entries(max: u64, session: State<'_, SessionState>) -> Result<(), String> {
+    if max == 0 {
+        return Err("The view limit must be at least 1".to_string());
+    }
+    let mut guard = session.max_view_entries.lock().map_err(|e| e.to_string())?;
+    *guard = max;
+    Ok(())
+}
+
+/// Returns the WebGL buffer for a window of entries. Optional lanes are appended after the regular
+/// 8 bytes per entry, in this order:
+/// - if `min_duration` or `max_duration` is given, a clamped duration lane (+4 bytes per entry),
+/// - if `with_index` is set, the two-part absolute index lane used for picking (+8 bytes per entry).
 #[tauri::command]
 fn get_trace_view(
     start: u64,
     count: u64,
+    min_duration: Option<f32>,
+    max_duration: Option<f32>,
+    with_index: Option<bool>,
     session: State<'_, SessionState>,
 ) -> Result<Response, String> {
+    session::check_view_count(&session, count)?;
+
     let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
     let loader = loader_guard
         .as_ref()
@@ -113,48 +423,1901 @@ fn get_trace_view(
     let entries = loader
         .load_entry_slice(start, count as usize)
         .map_err(|e| e.to_string())?;
-    let bytes = trace::entry::get_entry_range_bytes(entries);
+    let mut bytes = trace::entry::get_entry_range_bytes(entries);
+
+    let clamp = trace::entry::DurationClamp {
+        min: min_duration,
+        max: max_duration,
+    };
+    if clamp.is_active() {
+        let config = session.config.lock().map_err(|e| e.to_string())?;
+        let periods = session::period_table(config.as_ref());
+        trace::entry::append_duration_lane(&mut bytes, entries, &periods, clamp);
+    }
+
+    if with_index.unwrap_or(false) {
+        trace::entry::append_index_lane(&mut bytes, start, entries.len());
+    }
 
     Ok(Response::new(bytes))
 }
 
+/// Like `get_trace_view` without optional lanes, but streams the buffer to `on_chunk` in binary
+/// chunks of `chunk_entries` entries each, see `view_stream` for their layout.
 #[tauri::command]
-fn export_config_yaml(
+fn stream_trace_view(
     app: AppHandle,
+    start: u64,
+    count: u64,
+    chunk_entries: Option<u64>,
+    on_chunk: tauri::ipc::Channel<Response>,
     session: State<'_, SessionState>,
-    path: String,
 ) -> Result<(), String> {
-    session::export_config_yaml(&app, &session, path)
+    session::check_view_count(&session, count)?;
+    view_stream::spawn(
+        app,
+        &session,
+        (start, count),
+        chunk_entries.unwrap_or(view_stream::DEFAULT_CHUNK_ENTRIES),
+        on_chunk,
+    )
 }
 
 #[tauri::command]
-fn import_config_yaml(
+fn cancel_trace_view(session: State<'_, SessionState>) -> Result<(), String> {
+    view_stream::cancel(&session)
+}
+
+/// The `count` entries from `start` decimated to at most `max_points`, keeping the first and
+/// last entry of every occupied time bucket so bursts stay visible (see
+/// `analysis::density::minmax_decimate`). The view buffer is followed by the index lane of the
+/// picked entries, as in `sample_stratified`.
+#[tauri::command]
+fn get_trace_view_minmax(
+    start: u64,
+    count: u64,
+    max_points: u64,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    session::check_view_count(&session, max_points)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let window = loader
+        .load_entry_slice(start, count as usize)
+        .map_err(|e| e.to_string())?;
+    let positions = analysis::density::minmax_decimate(window, max_points as usize);
+
+    let entries: Vec<_> = positions.iter().map(|p| window[*p]).collect();
+    let indices: Vec<u64> = positions.iter().map(|p| start + *p as u64).collect();
+
+    let mut bytes = trace::entry::get_entry_range_bytes(&entries);
+    trace::entry::append_sparse_index_lane(&mut bytes, &indices);
+
+    Ok(Response::new(bytes))
+}
+
+/// Only the start and duration lanes of `count` entries from `start`:
+/// [Start CLKs (N * 4 bytes)][Durations (N * 4 bytes)], both f32. Durations are the configured
+/// clock periods, unclamped, for computations that don't render.
+#[tauri::command]
+fn get_durations(
+    start: u64,
+    count: u64,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    session::check_view_count(&session, count)?;
+
+    let periods = {
+        let config = session.config.lock().map_err(|e| e.to_string())?;
+        session::period_table(config.as_ref())
+    };
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader
+        .load_entry_slice(start, count as usize)
+        .map_err(|e| e.to_string())?;
+
+    let mut bytes = trace::entry::start_lane(entries);
+    trace::entry::append_duration_lane(
+        &mut bytes,
+        entries,
+        &periods,
+        trace::entry::DurationClamp::default(),
+    );
+
+    Ok(Response::new(bytes))
+}
+
+/// Up to `trace::entry::MAX_DECODED_ENTRIES` entries from `start` as structured records, the
+/// counterpart of the `get_trace_view` byte buffer for the details table.
+#[tauri::command]
+fn get_entries_decoded(
     app: AppHandle,
+    start: u64,
+    count: u64,
     session: State<'_, SessionState>,
-    path: String,
-) -> Result<(), String> {
-    session::import_config_yaml(&app, &session, path)
+) -> Result<Vec<trace::entry::DecodedEntry>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let aliases = session::current_command_config(&app, &session)?
+        .map(|c| c.command_aliases)
+        .unwrap_or_default();
+
+    let count = count.min(trace::entry::MAX_DECODED_ENTRIES as u64) as usize;
+    let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+    let entries = loader
+        .load_entry_slice(start, count)
+        .map_err(|e| e.to_string())?;
+
+    Ok(trace::entry::decode_entries(
+        entries,
+        start,
+        &dictionary,
+        &aliases,
+    ))
 }
 
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EntryContext {
+    /// Position of the requested entry within `entries`.
+    center: usize,
+    entries: Vec<trace::entry::DecodedEntry>,
+}
+
+/// The decoded entries within `radius` of `index`, clamped to the trace, for a context strip
+/// around a selection, see `trace::entry::context_range`.
 #[tauri::command]
-fn close_session(session: State<'_, SessionState>) -> Result<(), String> {
-    {
-        let mut guard = session.loader.lock().map_err(|e| e.to_string())?;
-        *guard = None;
-    }
-    {
-        let mut guard = session.config.lock().map_err(|e| e.to_string())?;
-        *guard = None;
+fn get_entry_context(
+    app: AppHandle,
+    index: u64,
+    radius: u64,
+    session: State<'_, SessionState>,
+) -> Result<EntryContext, String> {
+    let aliases = session::current_command_config(&app, &session)?
+        .map(|c| c.command_aliases)
+        .unwrap_or_default();
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    if index >= loader.num_entries() {
+        return Err("Out of bounds".to_string());
     }
-    Ok(())
+
+    let range = trace::entry::context_range(index, radius, loader.num_entries());
+    let (start, end) = (*range.start(), *range.end());
+
+    let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+    let entries = loader
+        .load_entry_slice(start, (end - start + 1) as usize)
+        .map_err(|e| e.to_string())?;
+
+    Ok(EntryContext {
+        center: (index - start) as usize,
+        entries: trace::entry::decode_entries(entries, start, &dictionary, &aliases),
+    })
 }
 
+/// Like `get_entries_decoded`, sorted by `sort_key`. Only the requested window is sorted.
 #[tauri::command]
-fn get_session_info(
+fn get_entries_sorted(
+    app: AppHandle,
+    start: u64,
+    count: u64,
+    sort_key: trace::entry::SortKey,
+    descending: Option<bool>,
     session: State<'_, SessionState>,
-) -> Result<Option<trace::header::Header>, String> {
+) -> Result<Vec<trace::entry::DecodedEntry>, String> {
+    let mut entries = get_entries_decoded(app, start, count, session)?;
+    trace::entry::sort_decoded(&mut entries, sort_key, descending.unwrap_or(false));
+    Ok(entries)
+}
+
+/// Clk of the first and last command of `categories` (by default reads and writes), i.e. the
+/// trace without its leading and trailing idle time. `None` if no such command exists.
+#[tauri::command]
+fn get_active_span(
+    app: AppHandle,
+    categories: Option<Vec<CommandCategory>>,
+    session: State<'_, SessionState>,
+) -> Result<Option<(i64, i64)>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let categories =
+        categories.unwrap_or_else(|| vec![CommandCategory::Read, CommandCategory::Write]);
+    let commands = categories
+        .iter()
+        .fold(analysis::CommandSet::from_ids([]), |set, category| {
+            set.union(&classifier.set(*category))
+        });
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::active_span(entries, &commands))
+}
+
+/// Whether the trace's clks exceed f32 precision in the view buffer, and the origin to offset
+/// them by. `None` for an empty trace.
+#[tauri::command]
+fn check_render_precision(
+    session: State<'_, SessionState>,
+) -> Result<Option<trace::entry::RenderPrecision>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::clk_bounds(entries).map(|(min, max)| trace::entry::render_precision(min, max)))
+}
+
+/// The lanes of `get_trace_view` stored one after another as raw bytes, with their offsets in a
+/// header, see `trace::entry::get_entry_lanes`.
+#[tauri::command]
+fn get_trace_view_soa(
+    start: u64,
+    count: u64,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    session::check_view_count(&session, count)?;
+
+    let (periods, colors) = {
+        let config = session.config.lock().map_err(|e| e.to_string())?;
+        (
+            session::period_table(config.as_ref()),
+            session::color_table(config.as_ref()),
+        )
+    };
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader
+        .load_entry_slice(start, count as usize)
+        .map_err(|e| e.to_string())?;
+    Ok(Response::new(trace::entry::get_entry_lanes(
+        entries, &periods, &colors,
+    )))
+}
+
+/// The view as interleaved instances for instanced rendering, see
+/// `trace::entry::get_entry_instances`. Rows are the flat bank index of the memory layout.
+#[tauri::command]
+fn get_trace_view_interleaved(
+    app: AppHandle,
+    start: u64,
+    count: u64,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    session::check_view_count(&session, count)?;
+
+    let layout = memory_layout(&app, &session)?;
+
+    let (periods, colors) = {
+        let config = session.config.lock().map_err(|e| e.to_string())?;
+        (
+            session::period_table(config.as_ref()),
+            session::color_table(config.as_ref()),
+        )
+    };
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader
+        .load_entry_slice(start, count as usize)
+        .map_err(|e| e.to_string())?;
+    Ok(Response::new(trace::entry::get_entry_instances(
+        entries,
+        &periods,
+        &colors,
+        |entry| layout.flat_bank_index(entry),
+    )))
+}
+
+/// Returns the WebGL buffer for all entries with `start_clk <= clk <= end_clk` in one call.
+#[tauri::command]
+fn get_trace_view_by_time(
+    start_clk: i64,
+    end_clk: i64,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let range = loader
+        .index_range_for_time(start_clk, end_clk)
+        .map_err(|e| e.to_string())?;
+    session::check_view_count(&session, range.end - range.start)?;
+
+    let entries = loader
+        .load_entry_slice(range.start, (range.end - range.start) as usize)
+        .map_err(|e| e.to_string())?;
+    let bytes = trace::entry::get_entry_range_bytes(entries);
+
+    Ok(Response::new(bytes))
+}
+
+#[tauri::command]
+fn prefetch_trace_view(
+    start: u64,
+    count: u64,
+    session: State<'_, SessionState>,
+) -> Result<(), String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    loader
+        .prefetch(start, count as usize)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn sample_entries(k: u64, session: State<'_, SessionState>) -> Result<Response, String> {
+    session::check_view_count(&session, k)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.sample_entries(k).map_err(|e| e.to_string())?;
+    let bytes = trace::entry::get_entry_range_bytes(&entries);
+
+    Ok(Response::new(bytes))
+}
+
+/// Up to `per_command` entries of every command in the view format, followed by their index
+/// lane so picking still works on the non-contiguous sample.
+#[tauri::command]
+fn sample_stratified(
+    per_command: u64,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let max_samples = per_command.saturating_mul(loader.header().num_commands() as u64);
+    session::check_view_count(&session, max_samples)?;
+
+    let indices = loader
+        .sample_stratified(per_command)
+        .map_err(|e| e.to_string())?;
+    let all = loader.entries().map_err(|e| e.to_string())?;
+    let entries: Vec<_> = indices.iter().map(|index| all[*index as usize]).collect();
+
+    let mut bytes = trace::entry::get_entry_range_bytes(&entries);
+    trace::entry::append_sparse_index_lane(&mut bytes, &indices);
+
+    Ok(Response::new(bytes))
+}
+
+#[tauri::command]
+fn get_command_runs(
+    start: u64,
+    count: u64,
+    split_by_bank: Option<bool>,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    session::check_view_count(&session, count)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let runs = loader
+        .command_runs(start, count as usize, split_by_bank.unwrap_or(false))
+        .map_err(|e| e.to_string())?;
+    let bytes = trace::runs::get_run_bytes(&runs);
+
+    Ok(Response::new(bytes))
+}
+
+/// The longest run in the whole trace, optionally restricted to one command.
+#[tauri::command]
+fn get_longest_run(
+    cmd_id: Option<u8>,
+    session: State<'_, SessionState>,
+) -> Result<Option<trace::Run>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(trace::runs::longest(entries, cmd_id))
+}
+
+#[tauri::command]
+fn find_duplicate_entries(session: State<'_, SessionState>) -> Result<Vec<u64>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    loader.find_duplicate_entries().map_err(|e| e.to_string())
+}
+
+/// Number of entries matching `filter`, for counters that don't need the indices.
+#[tauri::command]
+fn count_entries(
+    filter: trace::EntryFilter,
+    session: State<'_, SessionState>,
+) -> Result<u64, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    loader.count_matching(&filter).map_err(|e| e.to_string())
+}
+
+/// Smallest and largest valid value of every address field, e.g. for filter slider bounds.
+/// Computed once per loaded trace and cached in the session.
+#[tauri::command]
+fn get_address_ranges(
+    session: State<'_, SessionState>,
+) -> Result<analysis::quality::AddressRanges, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let mut cache = session.address_ranges.lock().map_err(|e| e.to_string())?;
+    if let Some(ranges) = *cache {
+        return Ok(ranges);
+    }
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    let ranges = analysis::quality::address_ranges(entries);
+    *cache = Some(ranges);
+
+    Ok(ranges)
+}
+
+/// Fraction of entries missing each address component, see
+/// `analysis::quality::address_completeness`.
+#[tauri::command]
+fn get_address_completeness(
+    session: State<'_, SessionState>,
+) -> Result<analysis::quality::AddressCompleteness, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::quality::address_completeness(entries))
+}
+
+/// Entries whose valid and invalid address components contradict `rules`, by default the
+/// address hierarchy (see `analysis::quality::default_rules`).
+#[tauri::command]
+fn get_address_anomalies(
+    rules: Option<Vec<analysis::quality::AddressRule>>,
+    limit: Option<usize>,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::quality::AddressAnomaly>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let rules = rules.unwrap_or_else(analysis::quality::default_rules);
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::quality::address_anomalies(
+        entries,
+        &rules,
+        limit.unwrap_or(usize::MAX),
+    ))
+}
+
+#[tauri::command]
+fn search_entries(
+    filter: trace::EntryFilter,
+    limit: Option<usize>,
+    session: State<'_, SessionState>,
+) -> Result<Vec<u64>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    loader
+        .search(&filter, limit.unwrap_or(usize::MAX))
+        .map_err(|e| e.to_string())
+}
+
+/// Index of the `n`th (1-based) `cmd_id`, optionally counting only entries matching `filter`.
+#[tauri::command]
+fn find_nth_command(
+    cmd_id: u8,
+    n: u64,
+    filter: Option<trace::EntryFilter>,
+    session: State<'_, SessionState>,
+) -> Result<Option<u64>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    loader
+        .nth_command(cmd_id, n, &filter.unwrap_or_default())
+        .map_err(|e| e.to_string())
+}
+
+/// Index of the entry on the bank(s) selected by `bank_filter` closest to `clk`, for selecting
+/// events by clicking on a bank's track.
+#[tauri::command]
+fn nearest_entry_in_bank(
+    clk: i64,
+    bank_filter: trace::EntryFilter,
+    session: State<'_, SessionState>,
+) -> Result<Option<u64>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    loader
+        .nearest_matching(clk, &bank_filter)
+        .map_err(|e| e.to_string())
+}
+
+/// Like `search_entries`, but streams the matches as `search-result` events, see `search`.
+/// Returns the id the search's events carry.
+#[tauri::command]
+fn stream_search(
+    app: AppHandle,
+    filter: trace::EntryFilter,
+    batch_size: Option<usize>,
+    session: State<'_, SessionState>,
+) -> Result<u64, String> {
+    search::spawn(
+        app,
+        &session,
+        filter,
+        batch_size.unwrap_or(search::DEFAULT_BATCH_SIZE),
+    )
+}
+
+#[tauri::command]
+fn cancel_search(session: State<'_, SessionState>) -> Result<(), String> {
+    search::cancel(&session)
+}
+
+#[tauri::command]
+fn get_command_histogram(session: State<'_, SessionState>) -> Result<BTreeMap<u8, u64>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::histogram::command_counts(entries))
+}
+
+#[tauri::command]
+fn get_command_time_breakdown(
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::histogram::CommandTime>, String> {
+    let periods = {
+        let config = session.config.lock().map_err(|e| e.to_string())?;
+        session::period_table(config.as_ref())
+    };
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::histogram::command_time_breakdown(
+        entries, &periods,
+    ))
+}
+
+#[tauri::command]
+fn get_command_entropy(
+    session: State<'_, SessionState>,
+) -> Result<analysis::histogram::CommandEntropy, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::histogram::command_entropy(entries))
+}
+
+#[tauri::command]
+fn get_transition_matrix(
+    session: State<'_, SessionState>,
+) -> Result<analysis::histogram::TransitionMatrix, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::histogram::transition_matrix(
+        entries,
+        loader.header().num_commands(),
+    ))
+}
+
+/// Entry counts grouped by the combination of `keys`, see `analysis::histogram::group_counts`.
+#[tauri::command]
+fn group_count(
+    keys: Vec<analysis::histogram::GroupKey>,
+    session: State<'_, SessionState>,
+) -> Result<analysis::histogram::GroupCounts, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::histogram::group_counts(entries, &keys))
+}
+
+/// Histogram of the columns accessed by reads and writes, see `analysis::histogram::column_counts`.
+#[tauri::command]
+fn get_column_histogram(
+    app: AppHandle,
+    bucket_width: u32,
+    session: State<'_, SessionState>,
+) -> Result<BTreeMap<i32, u64>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let accesses = classifier
+        .set(CommandCategory::Read)
+        .union(&classifier.set(CommandCategory::Write));
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::histogram::column_counts(
+        entries,
+        bucket_width,
+        &accesses,
+    ))
+}
+
+/// Per-command count and bandwidth deltas between two inclusive clk windows, which may overlap.
+#[tauri::command]
+fn compare_ranges(
+    app: AppHandle,
+    range_a: (i64, i64),
+    range_b: (i64, i64),
+    session: State<'_, SessionState>,
+) -> Result<analysis::compare::RangeComparison, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let accesses = classifier
+        .set(CommandCategory::Read)
+        .union(&classifier.set(CommandCategory::Write));
+
+    let stats = |(start_clk, end_clk): (i64, i64)| {
+        let range = loader
+            .index_range_for_time(start_clk, end_clk)
+            .map_err(|e| e.to_string())?;
+        let entries = loader
+            .load_entry_slice(range.start, (range.end - range.start) as usize)
+            .map_err(|e| e.to_string())?;
+        Ok::<_, String>(analysis::compare::window_stats(
+            entries, start_clk, end_clk, &accesses,
+        ))
+    };
+
+    Ok(analysis::compare::compare(stats(range_a)?, stats(range_b)?))
+}
+
+#[tauri::command]
+fn get_wr_to_rd_latency(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<analysis::timing::LatencyStats, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let writes = classifier.set(CommandCategory::Write);
+    let reads = classifier.set(CommandCategory::Read);
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::timing::pair_latencies(entries, &writes, &reads))
+}
+
+/// Per-bank PRE to next ACT cycles (tRP-related).
+#[tauri::command]
+fn get_pre_to_act_timing(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<analysis::timing::LatencyStats, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let precharges = classifier.set(CommandCategory::Precharge);
+    let activates = classifier.set(CommandCategory::Activate);
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::timing::pair_latencies(
+        entries,
+        &precharges,
+        &activates,
+    ))
+}
+
+/// Write recovery (tWR): per-bank cycles from the last WR to the next PRE of that bank.
+#[tauri::command]
+fn get_write_recovery_timing(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<analysis::timing::LatencyStats, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let writes = classifier.set(CommandCategory::Write);
+    let precharges = classifier.set(CommandCategory::Precharge);
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::timing::pair_latencies(
+        entries,
+        &writes,
+        &precharges,
+    ))
+}
+
+/// Cycles since the last `anchor_cmd_id` for the `count` entries from `start`, as one f32 per
+/// entry for plotting. Anchors before `start` count, so windows line up with each other; entries
+/// before the first anchor are `analysis::timing::NO_ANCHOR`.
+#[tauri::command]
+fn get_relative_to_anchor(
+    anchor_cmd_id: u8,
+    start: u64,
+    count: u64,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    session::check_view_count(&session, count)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let window = loader
+        .load_entry_slice(start, count as usize)
+        .map_err(|e| e.to_string())?;
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    let last_anchor = analysis::timing::last_anchor_clk(&entries[..start as usize], anchor_cmd_id);
+
+    let bytes: Vec<u8> = analysis::timing::cycles_since_anchor(window, anchor_cmd_id, last_anchor)
+        .into_iter()
+        .flat_map(|delta| (delta as f32).to_le_bytes())
+        .collect();
+
+    Ok(Response::new(bytes))
+}
+
+/// Smallest clk step the trace resolves, so the UI doesn't zoom finer than one tick.
+#[tauri::command]
+fn detect_clk_granularity(session: State<'_, SessionState>) -> Result<Option<u64>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::timing::clk_granularity(entries))
+}
+
+/// Idle gaps of at least `min_gap` cycles split into refresh-adjacent and other gaps.
+#[tauri::command]
+fn get_gap_attribution(
+    app: AppHandle,
+    min_gap: u64,
+    session: State<'_, SessionState>,
+) -> Result<analysis::timing::GapAttribution, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let refreshes = classifier.set(CommandCategory::Refresh);
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::timing::gap_attribution(
+        entries, min_gap, &refreshes,
+    ))
+}
+
+/// Quantiles of the clk deltas between consecutive entries, e.g. `[0.5, 0.9, 0.99]`.
+#[tauri::command]
+fn get_interval_quantiles(
+    quantiles: Vec<f64>,
+    session: State<'_, SessionState>,
+) -> Result<Vec<Option<i64>>, String> {
+    if let Some(q) = quantiles.iter().find(|q| !(0.0..=1.0).contains(*q)) {
+        return Err(format!("Quantile {} is outside [0, 1]", q));
+    }
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::timing::interval_quantiles(entries, &quantiles))
+}
+
+/// Per-bank ACT to next PRE cycles, plus the rows left open at the end of the trace.
+#[tauri::command]
+fn get_row_open_durations(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<analysis::timing::RowOpenStats, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let activates = classifier.set(CommandCategory::Activate);
+    let precharges = classifier.set(CommandCategory::Precharge);
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::timing::row_open_durations(
+        entries,
+        &activates,
+        &precharges,
+    ))
+}
+
+#[tauri::command]
+fn get_dominant_command_timeline(
+    num_buckets: usize,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::density::DominantBucket>, String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::dominant_commands(entries, num_buckets))
+}
+
+#[tauri::command]
+fn get_occupancy(
+    num_buckets: usize,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::density::OccupancyBucket>, String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let periods = {
+        let config = session.config.lock().map_err(|e| e.to_string())?;
+        session::period_table(config.as_ref())
+    };
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::occupancy(entries, num_buckets, &periods))
+}
+
+/// Fraction of the non-refresh cycles occupied by reads and writes, see
+/// `analysis::density::efficiency` for the exact formula.
+#[tauri::command]
+fn get_efficiency(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<analysis::density::Efficiency, String> {
+    let periods = {
+        let config = session.config.lock().map_err(|e| e.to_string())?;
+        session::period_table(config.as_ref())
+    };
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let accesses = classifier
+        .set(CommandCategory::Read)
+        .union(&classifier.set(CommandCategory::Write));
+    let refreshes = classifier.set(CommandCategory::Refresh);
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::efficiency(
+        entries, &accesses, &refreshes, &periods,
+    ))
+}
+
+#[tauri::command]
+fn get_concurrency(
+    num_buckets: usize,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::density::ConcurrencyBucket>, String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let periods = {
+        let config = session.config.lock().map_err(|e| e.to_string())?;
+        session::period_table(config.as_ref())
+    };
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::concurrency(
+        entries,
+        num_buckets,
+        &periods,
+    ))
+}
+
+#[tauri::command]
+fn get_dominant_period(
+    session: State<'_, SessionState>,
+) -> Result<Option<analysis::density::DominantPeriod>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::dominant_period(entries))
+}
+
+#[tauri::command]
+fn get_issue_rate(
+    window_cycles: i64,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::density::RateWindow>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    if let Some((min, max)) = analysis::clk_bounds(entries) {
+        let windows = analysis::density::issue_window_count(min, max, window_cycles);
+        if windows > analysis::density::MAX_ISSUE_WINDOWS {
+            return Err(format!(
+                "A window of {} cycles yields {} windows, more than the maximum of {}",
+                window_cycles,
+                windows,
+                analysis::density::MAX_ISSUE_WINDOWS
+            ));
+        }
+    }
+
+    Ok(analysis::density::issue_rate(entries, window_cycles))
+}
+
+/// The busiest `window_cycles` long stretch of the trace, see
+/// `analysis::density::densest_window`.
+#[tauri::command]
+fn find_densest_window(
+    window_cycles: i64,
+    session: State<'_, SessionState>,
+) -> Result<Option<analysis::density::DensestWindow>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::densest_window(entries, window_cycles))
+}
+
+#[tauri::command]
+fn get_rolling_issue_rate(
+    window_cycles: i64,
+    step_cycles: i64,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::density::RateWindow>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    if let Some((min, max)) = analysis::clk_bounds(entries) {
+        let windows = analysis::density::rolling_window_count(min, max, step_cycles);
+        if windows > analysis::density::MAX_ROLLING_WINDOWS {
+            return Err(format!(
+                "A step of {} cycles yields {} windows, more than the maximum of {}",
+                step_cycles,
+                windows,
+                analysis::density::MAX_ROLLING_WINDOWS
+            ));
+        }
+    }
+
+    Ok(analysis::density::rolling_issue_rate(
+        entries,
+        window_cycles,
+        step_cycles,
+    ))
+}
+
+/// Average number of distinct banks among `window_size` consecutive commands, see
+/// `analysis::density::interleaving_score`.
+#[tauri::command]
+fn get_interleaving_score(
+    window_size: usize,
+    session: State<'_, SessionState>,
+) -> Result<analysis::density::InterleavingScore, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::interleaving_score(entries, window_size))
+}
+
+#[tauri::command]
+fn get_bankgroup_parallelism(
+    num_buckets: usize,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::density::ParallelismBucket>, String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::bankgroup_parallelism(
+        entries,
+        num_buckets,
+    ))
+}
+
+/// The most contended bank per time bucket. Requires a memory layout for the bank index.
+#[tauri::command]
+fn get_bank_contention(
+    app: AppHandle,
+    num_buckets: usize,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::density::ContentionBucket>, String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let layout = memory_layout(&app, &session)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::bank_contention(
+        entries,
+        num_buckets,
+        &layout,
+    ))
+}
+
+/// Coefficient of variation of the per-bank command counts, see
+/// `analysis::density::bank_load_imbalance`. Requires a memory layout, which defines the banks.
+#[tauri::command]
+fn get_bank_load_imbalance(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<analysis::density::BankLoadImbalance, String> {
+    let layout = memory_layout(&app, &session)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::bank_load_imbalance(entries, &layout))
+}
+
+/// First and last clk of every bank with commands, see `analysis::density::bank_lifetimes`.
+/// Requires a memory layout for the bank indices.
+#[tauri::command]
+fn get_bank_lifetimes(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::density::BankLifetime>, String> {
+    let layout = memory_layout(&app, &session)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::bank_lifetimes(entries, &layout))
+}
+
+/// Fraction of the layout's banks the trace touches, see `analysis::density::address_coverage`.
+#[tauri::command]
+fn get_address_coverage(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<analysis::density::AddressCoverage, String> {
+    let layout = memory_layout(&app, &session)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::address_coverage(entries, &layout))
+}
+
+/// Bank activity per time bucket as packed bitsets, see `analysis::density::bank_activity_bitset`.
+/// Requires a memory layout, which defines the bitset width.
+#[tauri::command]
+fn get_bank_activity_bitset(
+    app: AppHandle,
+    num_buckets: usize,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let layout = memory_layout(&app, &session)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    let bits = analysis::density::bank_activity_bitset(entries, num_buckets, &layout)
+        .ok_or_else(|| format!("Bank bitsets of {} buckets are too large", num_buckets))?;
+    Ok(Response::new(bits))
+}
+
+/// The `n` rows hit by the most reads and writes, see `analysis::rows::hot_rows`.
+#[tauri::command]
+fn get_hot_rows(
+    app: AppHandle,
+    n: usize,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::rows::HotRow>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let accesses = classifier
+        .set(CommandCategory::Read)
+        .union(&classifier.set(CommandCategory::Write));
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::rows::hot_rows(entries, n, &accesses))
+}
+
+#[tauri::command]
+fn get_address_aliases(
+    app: AppHandle,
+    limit: usize,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::rows::AddressAlias>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let accesses = classifier
+        .set(CommandCategory::Read)
+        .union(&classifier.set(CommandCategory::Write));
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::rows::address_aliases(entries, limit, &accesses))
+}
+
+#[tauri::command]
+fn get_bankgroup_locality(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::rows::BankGroupLocality>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let accesses = classifier
+        .set(CommandCategory::Read)
+        .union(&classifier.set(CommandCategory::Write));
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::rows::bankgroup_locality(
+        entries,
+        &classifier.set(CommandCategory::Activate),
+        &classifier.set(CommandCategory::Precharge),
+        &accesses,
+    ))
+}
+
+/// Row-buffer hit rate per time bucket, see `analysis::rows::locality_timeline`. By default open
+/// rows carry over from one bucket to the next; `reset_per_bucket` starts every bucket closed.
+#[tauri::command]
+fn get_locality_timeline(
+    app: AppHandle,
+    num_buckets: usize,
+    reset_per_bucket: Option<bool>,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::rows::LocalityBucket>, String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let accesses = classifier
+        .set(CommandCategory::Read)
+        .union(&classifier.set(CommandCategory::Write));
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::rows::locality_timeline(
+        entries,
+        num_buckets,
+        &classifier.set(CommandCategory::Activate),
+        &classifier.set(CommandCategory::Precharge),
+        &accesses,
+        reset_per_bucket.unwrap_or(false),
+    ))
+}
+
+#[tauri::command]
+fn get_row_activation_stats(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::rows::RowActivationStats>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let activates = classifier.set(CommandCategory::Activate);
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::rows::activation_stats(entries, &activates))
+}
+
+#[tauri::command]
+fn get_rw_ratio(
+    app: AppHandle,
+    num_buckets: usize,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::density::RwBucket>, String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let reads = classifier.set(CommandCategory::Read);
+    let writes = classifier.set(CommandCategory::Write);
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::rw_ratio(
+        entries,
+        num_buckets,
+        &reads,
+        &writes,
+    ))
+}
+
+/// Activations per read or write, per time bucket and overall, see
+/// `analysis::density::activate_ratio`.
+#[tauri::command]
+fn get_activate_ratio(
+    app: AppHandle,
+    num_buckets: usize,
+    session: State<'_, SessionState>,
+) -> Result<analysis::density::ActivateRatio, String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let activates = classifier.set(CommandCategory::Activate);
+    let accesses = classifier
+        .set(CommandCategory::Read)
+        .union(&classifier.set(CommandCategory::Write));
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::density::activate_ratio(
+        entries,
+        num_buckets,
+        &activates,
+        &accesses,
+    ))
+}
+
+#[tauri::command]
+fn get_raw_hazards(
+    app: AppHandle,
+    window_cycles: i64,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::hazards::RawHazard>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let writes = classifier.set(CommandCategory::Write);
+    let reads = classifier.set(CommandCategory::Read);
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::hazards::raw_hazards(
+        entries,
+        window_cycles,
+        &writes,
+        &reads,
+    ))
+}
+
+#[tauri::command]
+fn get_command_inter_arrivals(
+    cmd_id: u8,
+    filter: Option<trace::EntryFilter>,
+    session: State<'_, SessionState>,
+) -> Result<Vec<i64>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::timing::inter_arrivals(
+        entries,
+        cmd_id,
+        &filter.unwrap_or_default(),
+    ))
+}
+
+#[tauri::command]
+fn get_rank_utilization(session: State<'_, SessionState>) -> Result<HashMap<i16, f64>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::rank::utilization(entries))
+}
+
+/// Per rank, the percentage of its entries in every command category, see
+/// `analysis::rank::command_mix`.
+#[tauri::command]
+fn get_rank_command_mix(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<Vec<analysis::rank::RankCommandMix>, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let classifier = command_classifier(&app, &session, loader)?;
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::rank::command_mix(entries, &classifier))
+}
+
+#[tauri::command]
+fn get_rank_timeline(
+    num_buckets: usize,
+    session: State<'_, SessionState>,
+) -> Result<analysis::rank::RankTimeline, String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    Ok(analysis::rank::timeline(entries, num_buckets))
+}
+
+#[tauri::command]
+fn export_config_yaml(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+    path: String,
+) -> Result<(), String> {
+    session::export_config_yaml(&app, &session, path)
+}
+
+/// Strict by default: a config with invalid values is reported and not applied.
+#[tauri::command]
+fn import_config_yaml(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+    path: String,
+    strict: Option<bool>,
+) -> Result<session::ConfigImportReport, String> {
+    session::import_config_yaml(&app, &session, path, strict.unwrap_or(true))
+}
+
+#[tauri::command]
+fn export_histogram_csv(session: State<'_, SessionState>, path: String) -> Result<(), String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    let counts = analysis::histogram::command_counts(entries);
+
+    trace::writer::write_atomic(&PathBuf::from(path), |writer| {
+        export::histogram::write_csv(writer, &counts, &dictionary)
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Merges two trace files into one chronological v1 trace at `out_path`, see
+/// `trace::writer::write_merged`. Neither input has to be the loaded trace.
+#[tauri::command(async)]
+fn merge_traces(path_a: String, path_b: String, out_path: String) -> Result<(), String> {
+    let a = trace::TraceLoader::new(PathBuf::from(path_a)).map_err(|e| e.to_string())?;
+    let b = trace::TraceLoader::new(PathBuf::from(path_b)).map_err(|e| e.to_string())?;
+
+    let a_dictionary = a.load_dictionary().map_err(|e| e.to_string())?;
+    let b_dictionary = b.load_dictionary().map_err(|e| e.to_string())?;
+
+    trace::writer::write_merged(
+        &PathBuf::from(out_path),
+        1,
+        (a.entries().map_err(|e| e.to_string())?, &a_dictionary),
+        (b.entries().map_err(|e| e.to_string())?, &b_dictionary),
+    )
+    .map_err(|e| e.to_string())
+}
+
+/// Appends `entries` to the v1 trace at `path` in place, see `trace::writer::TraceAppender`.
+/// Returns the new number of entries. A loaded trace keeps showing the old file until reloaded.
+#[tauri::command(async)]
+fn append_entries(path: String, entries: Vec<trace::entry::Entry>) -> Result<u64, String> {
+    let mut appender =
+        trace::writer::TraceAppender::open(PathBuf::from(path)).map_err(|e| e.to_string())?;
+    appender.append(&entries).map_err(|e| e.to_string())?;
+    Ok(appender.num_entries())
+}
+
+/// Writes a deterministic synthetic trace of `num_entries` entries, see `trace::demo`.
+#[tauri::command(async)]
+fn generate_demo_trace(path: String, num_entries: u64, seed: u64) -> Result<(), String> {
+    trace::demo::write(&PathBuf::from(path), num_entries, seed).map_err(|e| e.to_string())
+}
+
+/// Writes the number of commands per time bucket as CSV, see `export::density`.
+#[tauri::command]
+fn export_density_timeseries(
+    path: String,
+    num_buckets: usize,
+    session: State<'_, SessionState>,
+) -> Result<(), String> {
+    analysis::check_bucket_count(num_buckets)?;
+
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    trace::writer::write_atomic(&PathBuf::from(path), |writer| {
+        export::density::write_csv(writer, entries, num_buckets)
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Writes the per-bank command counts as folded stacks for flamegraph tools, see
+/// `export::folded`.
+#[tauri::command]
+fn export_folded_stacks(session: State<'_, SessionState>, path: String) -> Result<(), String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let entries = loader.entries().map_err(|e| e.to_string())?;
+    trace::writer::write_atomic(&PathBuf::from(path), |writer| {
+        export::folded::write_folded(writer, entries)
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Writes `count` entries from `start` as JSON lines, see `export::jsonl`.
+/// Runs off the main thread and reports `export-progress` events so it can be cancelled with
+/// `cancel_export`. The loader is only locked per chunk, see `export::progress`.
+#[tauri::command(async)]
+fn export_jsonl(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+    path: String,
+    start: u64,
+    count: u64,
+) -> Result<(), String> {
+    let (identity, dictionary) = {
+        let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+        let loader = loader_guard
+            .as_ref()
+            .ok_or_else(|| "No trace loaded".to_string())?;
+
+        if start
+            .checked_add(count)
+            .map_or(true, |end| end > loader.num_entries())
+        {
+            return Err("Out of bounds".to_string());
+        }
+        let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+        (loader.identity(), dictionary)
+    };
+
+    let progress = export::progress::ExportProgress::begin(app, &session, count)?;
+    trace::writer::write_atomic(&PathBuf::from(path), |writer| {
+        export::progress::write_chunked(&session, identity, start, count, &progress, |chunk| {
+            export::jsonl::write_jsonl(writer, chunk, &dictionary)
+        })
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// Writes the visible entries in the compressed format, see `trace::compressed`. Reports
+/// progress and is cancellable like `export_jsonl`.
+#[tauri::command(async)]
+fn export_compressed(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+    path: String,
+) -> Result<(), String> {
+    let (header, dictionary_bytes, identity) = {
+        let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+        let loader = loader_guard
+            .as_ref()
+            .ok_or_else(|| "No trace loaded".to_string())?;
+
+        let dictionary_bytes = loader
+            .load_dictionary()
+            .and_then(|dictionary| dictionary.to_bytes().map_err(Into::into))
+            .map_err(|e| e.to_string())?;
+        (loader.header(), dictionary_bytes, loader.identity())
+    };
+    let count = header.num_entries();
+
+    let progress = export::progress::ExportProgress::begin(app, &session, count)?;
+    trace::writer::write_atomic(&PathBuf::from(path), |writer| {
+        let mut encoder = trace::compressed::Encoder::new(writer, &header, count)?;
+        export::progress::write_chunked(&session, identity, 0, count, &progress, |chunk| {
+            encoder.entries(chunk)
+        })?;
+        encoder.finish(&dictionary_bytes)
+    })
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn cancel_export(session: State<'_, SessionState>) -> Result<(), String> {
+    export::progress::cancel(&session)
+}
+
+/// Writes the entries with `start_clk <= clk <= end_clk` as a standalone trace, optionally with
+/// clks rebased so the window starts at 0. See `trace::writer::write_clip`.
+#[tauri::command]
+fn export_clip(
+    session: State<'_, SessionState>,
+    start_clk: i64,
+    end_clk: i64,
+    path: String,
+    rebase: Option<bool>,
+) -> Result<(), String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let range = loader
+        .index_range_for_time(start_clk, end_clk)
+        .map_err(|e| e.to_string())?;
+    if range.is_empty() {
+        return Err("No entries in the clip window".to_string());
+    }
+    let entries = loader
+        .load_entry_slice(range.start, (range.end - range.start) as usize)
+        .map_err(|e| e.to_string())?;
+    let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+
+    trace::writer::write_clip(
+        &PathBuf::from(path),
+        loader.header().version,
+        entries,
+        &dictionary,
+        rebase.unwrap_or(false).then_some(start_clk),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn get_dictionary_json(session: State<'_, SessionState>) -> Result<String, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = loader_guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+    dictionary.to_json().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn export_dictionary_json(session: State<'_, SessionState>, path: String) -> Result<(), String> {
+    let json = get_dictionary_json(session)?;
+    trace::writer::write_atomic(&PathBuf::from(path), |writer| {
+        writer.write_all(json.as_bytes())
+    })
+    .map_err(|e| e.to_string())
+}
+
+/// `count` entries from `start` as an Arrow IPC stream, see `export::arrow`. `count` is capped
+/// like a view, see `session::check_view_count`. Only available in builds with the `arrow` feature.
+#[tauri::command]
+fn get_arrow_ipc(
+    start: u64,
+    count: u64,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    #[cfg(feature = "arrow")]
+    {
+        session::check_view_count(&session, count)?;
+
+        let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+        let loader = loader_guard
+            .as_ref()
+            .ok_or_else(|| "No trace loaded".to_string())?;
+
+        let dictionary = loader.load_dictionary().map_err(|e| e.to_string())?;
+        let entries = loader
+            .load_entry_slice(start, count as usize)
+            .map_err(|e| e.to_string())?;
+        let bytes = export::arrow::to_ipc_bytes(entries, &dictionary).map_err(|e| e.to_string())?;
+
+        Ok(Response::new(bytes))
+    }
+
+    #[cfg(not(feature = "arrow"))]
+    {
+        let _ = (start, count, session);
+        Err("This build does not include Arrow export".to_string())
+    }
+}
+
+#[tauri::command]
+fn get_config_store_info(app: AppHandle) -> Result<session::StoreInfo, String> {
+    session::store_info(&app)
+}
+
+/// PNG timeline of `count` entries from `start`, see `export::thumbnail`.
+/// Only available in builds with the `thumbnail` feature. Requires a memory layout for the rows.
+/// At most `export::thumbnail::MAX_THUMBNAIL_PIXELS` pixels.
+#[tauri::command]
+fn render_thumbnail(
+    app: AppHandle,
+    start: u64,
+    count: u64,
+    width: u32,
+    height: u32,
+    session: State<'_, SessionState>,
+) -> Result<Response, String> {
+    #[cfg(feature = "thumbnail")]
+    {
+        session::check_view_count(&session, count)?;
+
+        let layout = memory_layout(&app, &session)?;
+
+        let (colors, periods) = {
+            let config = session.config.lock().map_err(|e| e.to_string())?;
+            (
+                session::color_table(config.as_ref()),
+                session::period_table(config.as_ref()),
+            )
+        };
+
+        let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+        let loader = loader_guard
+            .as_ref()
+            .ok_or_else(|| "No trace loaded".to_string())?;
+
+        let entries = loader
+            .load_entry_slice(start, count as usize)
+            .map_err(|e| e.to_string())?;
+        let png =
+            export::thumbnail::render_png(entries, &layout, &colors, &periods, width, height)?;
+
+        Ok(Response::new(png))
+    }
+
+    #[cfg(not(feature = "thumbnail"))]
+    {
+        let _ = (app, start, count, width, height, session);
+        Err("This build does not include thumbnail rendering".to_string())
+    }
+}
+
+#[tauri::command]
+fn list_annotations(
+    session: State<'_, SessionState>,
+) -> Result<Vec<annotations::Annotation>, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+    annotations::list(loader.path(), loader.identity())
+}
+
+#[tauri::command]
+fn add_annotation(
+    anchor: annotations::Anchor,
+    label: String,
+    color: Option<String>,
+    session: State<'_, SessionState>,
+) -> Result<annotations::Annotation, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+    annotations::add(loader.path(), loader.identity(), anchor, label, color)
+}
+
+#[tauri::command]
+fn remove_annotation(id: u64, session: State<'_, SessionState>) -> Result<bool, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+    annotations::remove(loader.path(), loader.identity(), id)
+}
+
+#[tauri::command]
+fn close_session(session: State<'_, SessionState>) -> Result<(), String> {
+    {
+        let mut guard = session.loader.lock().map_err(|e| e.to_string())?;
+        *guard = None;
+    }
+    {
+        let mut guard = session.config.lock().map_err(|e| e.to_string())?;
+        *guard = None;
+    }
+    session::clear_address_ranges(&session)?;
+    Ok(())
+}
+
+#[tauri::command]
+fn get_session_info(
+    session: State<'_, SessionState>,
+) -> Result<Option<trace::header::Header>, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+    Ok(guard.as_ref().map(|loader| loader.header()))
+}
+
+/// What the session currently holds, for diagnosing leaks.
+#[tauri::command]
+fn get_session_diagnostics(
+    session: State<'_, SessionState>,
+) -> Result<session::SessionDiagnostics, String> {
+    session::diagnostics(&session)
+}
+
+/// Everything the frontend needs at startup in one call, see `session::bundle`.
+#[tauri::command]
+fn get_session_bundle(
+    app: AppHandle,
+    session: State<'_, SessionState>,
+) -> Result<session::SessionBundle, String> {
+    session::bundle(
+        &session,
+        || session::load_command_config(&app),
+        || session::load_memory_layout(&app),
+    )
+}
+
+/// The 24 header bytes as stored in the file, for a labelled hex view. Field offsets:
+/// magic 0..5, version 5, num_commands 6, reserved 7, num_entries 8..16, dict_offset 16..24
+/// (multi-byte fields in the file's byte order, see `get_trace_endianness`). For compressed
+/// traces these are the bytes of the header the file decodes to, see `raw_header_bytes`.
+#[tauri::command]
+fn get_header_raw(session: State<'_, SessionState>) -> Result<Response, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+
+    let bytes = loader.raw_header_bytes().map_err(|e| e.to_string())?;
+    Ok(Response::new(bytes))
+}
+
+/// The trace identity as 16 hex digits, since a u64 doesn't survive a JS number.
+#[tauri::command]
+fn get_trace_identity(session: State<'_, SessionState>) -> Result<String, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+    Ok(format!("{:016x}", loader.identity()))
+}
+
+#[tauri::command]
+fn get_trace_endianness(
+    session: State<'_, SessionState>,
+) -> Result<Option<trace::Endianness>, String> {
     let guard = session.loader.lock().map_err(|e| e.to_string())?;
-    Ok(guard.as_ref().map(|loader| *loader.header()))
+    Ok(guard.as_ref().map(|loader| loader.endianness()))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -165,18 +2328,131 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .manage(SessionState::new())
         .invoke_handler(tauri::generate_handler![
+            is_ramwiz_trace,
             load_trace,
+            reload_trace,
             load_dictionary,
+            search_commands,
+            get_used_commands,
+            list_annotations,
+            add_annotation,
+            remove_annotation,
             close_session,
+            get_config_store_info,
             get_session_info,
+            get_session_diagnostics,
+            get_session_bundle,
+            get_header_raw,
+            get_trace_endianness,
+            get_trace_identity,
             get_trace_view,
+            get_trace_view_minmax,
+            stream_trace_view,
+            cancel_trace_view,
+            get_max_view_entries,
+            set_max_view_entries,
             get_entry_index_by_time,
+            get_entry_offset,
+            jump_to_clk,
+            get_command_runs,
+            get_longest_run,
+            get_trace_view_by_time,
+            check_render_precision,
+            get_active_span,
+            get_trace_view_soa,
+            get_trace_view_interleaved,
+            get_durations,
+            get_entries_decoded,
+            get_entries_sorted,
+            get_entry_context,
+            prefetch_trace_view,
+            sample_entries,
+            sample_stratified,
+            search_entries,
+            count_entries,
+            find_duplicate_entries,
+            get_address_anomalies,
+            get_address_ranges,
+            get_address_completeness,
+            find_nth_command,
+            nearest_entry_in_bank,
+            stream_search,
+            cancel_search,
+            get_command_histogram,
+            get_column_histogram,
+            get_command_time_breakdown,
+            get_command_entropy,
+            get_transition_matrix,
+            group_count,
+            compare_ranges,
+            get_dominant_command_timeline,
+            get_bank_activity_bitset,
+            get_bank_contention,
+            get_bank_load_imbalance,
+            get_bank_lifetimes,
+            get_address_coverage,
+            get_bankgroup_parallelism,
+            get_interleaving_score,
+            get_issue_rate,
+            get_rolling_issue_rate,
+            find_densest_window,
+            get_dominant_period,
+            get_occupancy,
+            get_concurrency,
+            get_efficiency,
+            get_rw_ratio,
+            get_activate_ratio,
+            get_rank_utilization,
+            get_rank_timeline,
+            get_rank_command_mix,
+            get_wr_to_rd_latency,
+            get_pre_to_act_timing,
+            get_write_recovery_timing,
+            get_row_open_durations,
+            get_relative_to_anchor,
+            get_interval_quantiles,
+            get_gap_attribution,
+            detect_clk_granularity,
+            get_command_inter_arrivals,
+            get_raw_hazards,
+            get_row_activation_stats,
+            get_hot_rows,
+            get_address_aliases,
+            get_bankgroup_locality,
+            get_locality_timeline,
             get_command_config,
             set_command_config,
+            set_command_color,
+            set_command_clock_period,
+            get_command_aliases,
+            set_command_alias,
+            get_command_legend,
+            get_command_palette,
+            get_render_config,
             get_memory_layout,
             set_memory_layout,
+            get_layout_diff,
+            list_filter_presets,
+            save_filter_preset,
+            delete_filter_preset,
+            get_command_categories,
+            set_command_categories,
             export_config_yaml,
             import_config_yaml,
+            export_histogram_csv,
+            export_folded_stacks,
+            export_density_timeseries,
+            generate_demo_trace,
+            merge_traces,
+            append_entries,
+            export_jsonl,
+            export_compressed,
+            export_clip,
+            cancel_export,
+            get_dictionary_json,
+            export_dictionary_json,
+            get_arrow_ipc,
+            render_thumbnail,
         ])
         .setup(|app| {
             if cfg!(debug_assertions) {
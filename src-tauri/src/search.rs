@@ -0,0 +1,294 @@
+/// This file implements streaming entry search: a background scan that reports matches to the
+/// frontend in batches of Tauri events instead of one large result, so the UI can render
+/// incrementally and cancel midway.
+///
+/// Events:
+/// - `search-result`: `{ searchId, indices }`, at most `batch_size` (capped at `MAX_BATCH_SIZE`)
+///   matching indices in file order.
+/// - `search-done`: `{ searchId, total, cancelled, error }`, exactly once at the end of every
+///   search.
+///
+/// Starting a search cancels the previous one, but its last batches and its `search-done` can
+/// still arrive after the new search started. The frontend must therefore drop events whose
+/// `searchId` isn't the one `stream_search` returned for its current search.
+///
+/// The scan locks the loader per chunk rather than for the whole search, so other commands stay
+/// responsive. If the loaded trace changes in between (identity differs), the search ends with an
+/// error.
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::session::SessionState;
+use crate::trace::EntryFilter;
+
+pub const DEFAULT_BATCH_SIZE: usize = 1000;
+
+/// Upper bound of `batch_size`; larger requests are clamped to it.
+pub const MAX_BATCH_SIZE: usize = 100_000;
+
+/// Entries scanned per loader lock.
+const CHUNK_ENTRIES: u64 = 1 << 16;
+
+/// Id of the next search, unique for the lifetime of the process.
+static NEXT_SEARCH_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchResult {
+    search_id: u64,
+    indices: Vec<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchDone {
+    search_id: u64,
+    total: u64,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+/// Matches in `[start, start + CHUNK_ENTRIES)`, or `None` once past the end of the trace.
+fn scan_chunk(
+    session: &SessionState,
+    identity: u64,
+    filter: &EntryFilter,
+    start: u64,
+) -> Result<Option<Vec<u64>>, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+    if loader.identity() != identity {
+        return Err("The trace changed during the search".to_string());
+    }
+
+    let num_entries = loader.num_entries();
+    if start >= num_entries {
+        return Ok(None);
+    }
+
+    let count = CHUNK_ENTRIES.min(num_entries - start);
+    let entries = loader
+        .load_entry_slice(start, count as usize)
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(
+        entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.matches(filter))
+            .map(|(i, _)| start + i as u64)
+            .collect(),
+    ))
+}
+
+/// Drives a search: pulls the matches of consecutive chunks from `next_chunk` (given the first
+/// index of the chunk, `None` past the end) and hands them to `emit` in batches of `batch_size`.
+fn run(
+    search_id: u64,
+    mut next_chunk: impl FnMut(u64) -> Result<Option<Vec<u64>>, String>,
+    mut emit: impl FnMut(Vec<u64>) -> Result<(), String>,
+    batch_size: usize,
+    cancel: &AtomicBool,
+) -> SearchDone {
+    let mut done = SearchDone {
+        search_id,
+        total: 0,
+        cancelled: false,
+        error: None,
+    };
+    // Not preallocated: `batch_size` can be far larger than the number of matches.
+    let mut batch = Vec::new();
+    let mut start = 0;
+
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            done.cancelled = true;
+            return done;
+        }
+
+        let matches = match next_chunk(start) {
+            Ok(Some(matches)) => matches,
+            Ok(None) => break,
+            Err(error) => {
+                done.error = Some(error);
+                return done;
+            }
+        };
+        start += CHUNK_ENTRIES;
+
+        for index in matches {
+            batch.push(index);
+            done.total += 1;
+            if batch.len() == batch_size {
+                if let Err(error) = emit(std::mem::take(&mut batch)) {
+                    done.error = Some(error);
+                    return done;
+                }
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        done.error = emit(batch).err();
+    }
+    done
+}
+
+/// Starts a search in a background thread and returns its id immediately. A search that is still
+/// running is cancelled first, so there is at most one at a time.
+pub fn spawn<R: Runtime>(
+    app: AppHandle<R>,
+    session: &SessionState,
+    filter: EntryFilter,
+    batch_size: usize,
+) -> Result<u64, String> {
+    let identity = {
+        let guard = session.loader.lock().map_err(|e| e.to_string())?;
+        guard
+            .as_ref()
+            .ok_or_else(|| "No trace loaded".to_string())?
+            .identity()
+    };
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut guard = session.search_cancel.lock().map_err(|e| e.to_string())?;
+        if let Some(previous) = guard.replace(cancel.clone()) {
+            previous.store(true, Ordering::Relaxed);
+        }
+    }
+
+    let search_id = NEXT_SEARCH_ID.fetch_add(1, Ordering::Relaxed);
+    std::thread::spawn(move || {
+        let session = app.state::<SessionState>();
+        let done = run(
+            search_id,
+            |start| scan_chunk(&session, identity, &filter, start),
+            |indices| {
+                app.emit("search-result", SearchResult { search_id, indices })
+                    .map_err(|e| e.to_string())
+            },
+            batch_size.clamp(1, MAX_BATCH_SIZE),
+            &cancel,
+        );
+        if let Err(e) = app.emit("search-done", done) {
+            log::warn!("Failed to emit search-done: {}", e);
+        }
+    });
+
+    Ok(search_id)
+}
+
+/// Cancels the running search, if any. It still ends with a `search-done` event.
+pub fn cancel(session: &SessionState) -> Result<(), String> {
+    let guard = session.search_cancel.lock().map_err(|e| e.to_string())?;
+    if let Some(cancel) = guard.as_ref() {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Chunks of a fake trace with `chunks` chunks, matching every 10000th index.
+    fn every_10000th(chunks: u64) -> impl FnMut(u64) -> Result<Option<Vec<u64>>, String> {
+        move |start| {
+            Ok((start < chunks * CHUNK_ENTRIES)
+                .then(|| (start..start + CHUNK_ENTRIES).step_by(10_000).collect()))
+        }
+    }
+
+    #[test]
+    fn matches_are_emitted_in_batches() {
+        let mut batches = Vec::new();
+        let done = run(
+            7,
+            every_10000th(2),
+            |indices| {
+                batches.push(indices);
+                Ok(())
+            },
+            4,
+            &AtomicBool::new(false),
+        );
+
+        // 7 matches per chunk: two full batches and a partial last one.
+        assert_eq!(done.total, 14);
+        assert_eq!(
+            (done.search_id, done.cancelled, done.error),
+            (7, false, None)
+        );
+        let sizes: Vec<usize> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, [4, 4, 4, 2]);
+        assert_eq!(batches[1][3], CHUNK_ENTRIES);
+        assert!(batches.concat().windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn cancelling_stops_before_the_next_chunk() {
+        let cancel = AtomicBool::new(false);
+        let mut scanned = 0;
+        let mut chunks = every_10000th(100);
+
+        let done = run(
+            1,
+            |start| {
+                scanned += 1;
+                chunks(start)
+            },
+            |_| {
+                cancel.store(true, Ordering::Relaxed);
+                Ok(())
+            },
+            3,
+            &cancel,
+        );
+
+        assert!(done.cancelled);
+        assert_eq!((scanned, done.total), (1, 7));
+    }
+
+    #[test]
+    fn scan_errors_end_the_search() {
+        let done = run(
+            2,
+            |start| match start {
+                0 => Ok(Some(vec![1, 2])),
+                _ => Err("The trace changed during the search".to_string()),
+            },
+            |_| Ok(()),
+            10,
+            &AtomicBool::new(false),
+        );
+
+        assert_eq!(done.total, 2);
+        assert!(!done.cancelled);
+        assert!(done.error.is_some());
+    }
+
+    #[test]
+    fn huge_batch_sizes_emit_one_batch_without_preallocating() {
+        let mut batches = Vec::new();
+        let done = run(
+            3,
+            every_10000th(3),
+            |indices| {
+                batches.push(indices);
+                Ok(())
+            },
+            usize::MAX,
+            &AtomicBool::new(false),
+        );
+
+        assert_eq!((done.total, done.error), (21, None));
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].len(), 21);
+    }
+}
@@ -8,19 +8,223 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::sync::Mutex;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Runtime};
-use tauri_plugin_store::StoreExt;
+use tauri_plugin_store::{Store, StoreExt};
 
-use crate::trace::TraceLoader;
+use crate::analysis::classifier::CategoryPatterns;
+use crate::analysis::quality::AddressRanges;
+use crate::trace::dictionary::Dictionary;
+use crate::trace::entry::Entry;
+use crate::trace::writer::write_atomic;
+use crate::trace::{EntryFilter, TraceLoader};
 
 const STORE_PATH: &str = "ramwiz-config.json";
 
+/// Duration used for commands without a configured clock period. Matches the renderer's default.
+pub const DEFAULT_CLOCK_PERIOD: f32 = 10.0;
+
+/// Schema version of the stored `CommandConfig`. Bump it when the shape changes and add a step to
+/// `migrate_command_config`.
+/// - v0: `colors` and `clockPeriods` only, without a version field.
+/// - v1: adds `version` and `invalidSentinels`.
+/// - v2: adds `commandAliases`.
+pub const COMMAND_CONFIG_VERSION: u32 = 2;
+
+fn current_command_config_version() -> u32 {
+    COMMAND_CONFIG_VERSION
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommandConfig {
+    #[serde(default = "current_command_config_version")]
+    pub version: u32,
     pub colors: HashMap<u8, String>,
     #[serde(rename = "clockPeriods")]
     pub clock_periods: HashMap<u8, f32>,
+    /// Address component values treated as "not applicable", see `Entry::normalized`.
+    #[serde(rename = "invalidSentinels", default)]
+    pub invalid_sentinels: Vec<i64>,
+    /// Display names replacing the dictionary's in the legend and decoded entries, e.g. "Activate"
+    /// for "ACT". The dictionary itself and everything matching on names are unaffected.
+    /// Aliases are the only display-name layer today. Should per-view label overrides be added,
+    /// they are meant to take precedence: override, else alias, else dictionary name.
+    #[serde(rename = "commandAliases", default)]
+    pub command_aliases: HashMap<u8, String>,
+}
+
+impl Default for CommandConfig {
+    fn default() -> Self {
+        Self {
+            version: COMMAND_CONFIG_VERSION,
+            colors: HashMap::new(),
+            clock_periods: HashMap::new(),
+            invalid_sentinels: Vec::new(),
+            command_aliases: HashMap::new(),
+        }
+    }
+}
+
+impl CommandConfig {
+    /// Configured clock period of a command, falling back to `DEFAULT_CLOCK_PERIOD`.
+    pub fn clock_period(&self, cmd_id: u8) -> f32 {
+        self.clock_periods
+            .get(&cmd_id)
+            .copied()
+            .filter(|p| p.is_finite() && *p > 0.0)
+            .unwrap_or(DEFAULT_CLOCK_PERIOD)
+    }
+
+    /// The alias of a command, if one is set and not empty.
+    pub fn alias(&self, cmd_id: u8) -> Option<&str> {
+        self.command_aliases
+            .get(&cmd_id)
+            .map(String::as_str)
+            .filter(|alias| !alias.is_empty())
+    }
+}
+
+/// One row of the command legend. `label` is what the UI displays: the alias if one is
+/// configured, else the dictionary name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LegendEntry {
+    pub cmd_id: u8,
+    pub name: String,
+    pub label: String,
+    pub color: [u8; 3],
+    pub clock_period: f32,
+}
+
+/// Legend of every command in `dictionary`, sorted by id, with the configured display settings
+/// or their defaults.
+pub fn command_legend(config: Option<&CommandConfig>, dictionary: &Dictionary) -> Vec<LegendEntry> {
+    let colors = color_table(config);
+    let periods = period_table(config);
+
+    let mut legend: Vec<LegendEntry> = dictionary
+        .commands
+        .iter()
+        .map(|(cmd_id, name)| LegendEntry {
+            cmd_id: *cmd_id,
+            name: name.clone(),
+            label: config
+                .and_then(|c| c.alias(*cmd_id))
+                .unwrap_or(name)
+                .to_string(),
+            color: colors[*cmd_id as usize],
+            clock_period: periods[*cmd_id as usize],
+        })
+        .collect();
+    legend.sort_by_key(|entry| entry.cmd_id);
+
+    legend
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaletteEntry {
+    pub id: u8,
+    pub name: String,
+    /// `#rrggbb`, `DEFAULT_COLOR` for commands without a valid configured color.
+    pub color_hex: String,
+}
+
+/// The dictionary joined with the command colors, sorted by id. Unlike the legend this is only
+/// what startup needs to color the view.
+pub fn command_palette(
+    config: Option<&CommandConfig>,
+    dictionary: &Dictionary,
+) -> Vec<PaletteEntry> {
+    let colors = color_table(config);
+
+    let mut palette: Vec<PaletteEntry> = dictionary
+        .commands
+        .iter()
+        .map(|(id, name)| {
+            let [r, g, b] = colors[*id as usize];
+            PaletteEntry {
+                id: *id,
+                name: name.clone(),
+                color_hex: format!("#{:02x}{:02x}{:02x}", r, g, b),
+            }
+        })
+        .collect();
+    palette.sort_by_key(|entry| entry.id);
+
+    palette
+}
+
+/// Color the renderer uses for commands without a configured color (grey).
+pub const DEFAULT_COLOR: [u8; 3] = [0x80, 0x80, 0x80];
+
+/// Parses a `RRGGBB` color with an optional leading `#`, which is what the renderer accepts.
+pub fn parse_hex_color(color: &str) -> Option<[u8; 3]> {
+    let digits = color.strip_prefix('#').unwrap_or(color);
+    if digits.len() != 6 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+/// RGB color of every possible command id; unset or unparsable colors fall back to `DEFAULT_COLOR`.
+pub fn color_table(config: Option<&CommandConfig>) -> [[u8; 3]; 256] {
+    let mut table = [DEFAULT_COLOR; 256];
+    if let Some(config) = config {
+        for (cmd_id, color) in &config.colors {
+            if let Some(rgb) = parse_hex_color(color) {
+                table[*cmd_id as usize] = rgb;
+            }
+        }
+    }
+    table
+}
+
+/// Grey the frontend shader falls back to for commands without a color, as normalized RGB.
+pub const DEFAULT_RENDER_COLOR: [f32; 3] = [0.5, 0.5, 0.5];
+
+/// The command config flattened into arrays indexed by cmd_id, ready for texture upload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderConfig {
+    /// Normalized RGB per command.
+    pub colors: Vec<[f32; 3]>,
+    pub periods: Vec<f32>,
+}
+
+/// Builds the render config for the first `num_commands` ids. Unset or invalid colors become
+/// `DEFAULT_RENDER_COLOR` and unset periods `DEFAULT_CLOCK_PERIOD`.
+pub fn render_config(config: Option<&CommandConfig>, num_commands: u8) -> RenderConfig {
+    let periods = period_table(config);
+
+    let colors = (0..num_commands)
+        .map(|cmd_id| {
+            config
+                .and_then(|c| c.colors.get(&cmd_id))
+                .and_then(|color| parse_hex_color(color))
+                .map_or(DEFAULT_RENDER_COLOR, |rgb| rgb.map(|c| c as f32 / 255.0))
+        })
+        .collect();
+
+    RenderConfig {
+        colors,
+        periods: periods[..num_commands as usize].to_vec(),
+    }
+}
+
+/// Clock period of every possible command id, so hot loops can index instead of hashing.
+pub fn period_table(config: Option<&CommandConfig>) -> [f32; 256] {
+    let mut table = [DEFAULT_CLOCK_PERIOD; 256];
+    if let Some(config) = config {
+        for (cmd_id, period) in table.iter_mut().enumerate() {
+            *period = config.clock_period(cmd_id as u8);
+        }
+    }
+    table
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,10 +237,114 @@ pub struct MemoryLayout {
     pub num_banks: u8,
 }
 
+impl MemoryLayout {
+    /// Number of banks across all channels, i.e. the width of a flat bank index.
+    pub fn flat_bank_count(&self) -> usize {
+        self.num_channels as usize * self.num_bankgroups as usize * self.num_banks as usize
+    }
+
+    /// Flat index of an entry's bank, channel-major. `None` if a component is invalid (negative)
+    /// or outside the layout. The layout has no ranks, so the rank is not part of the index.
+    pub fn flat_bank_index(&self, entry: &Entry) -> Option<usize> {
+        let channel = usize::try_from(entry.channel.get()).ok()?;
+        let bankgroup = usize::try_from(entry.bankgroup.get()).ok()?;
+        let bank = usize::try_from(entry.bank.get()).ok()?;
+
+        let num_bankgroups = self.num_bankgroups as usize;
+        let num_banks = self.num_banks as usize;
+        if channel >= self.num_channels as usize || bankgroup >= num_bankgroups || bank >= num_banks
+        {
+            return None;
+        }
+
+        Some((channel * num_bankgroups + bankgroup) * num_banks + bank)
+    }
+
+    /// The smallest layout that holds every valid (non-negative) component of `entries`, i.e. the
+    /// largest index per dimension plus one. Dimensions beyond 255 saturate.
+    pub fn detect(entries: &[Entry]) -> Self {
+        let mut max = [-1i64; 3];
+        for entry in entries {
+            let components = [
+                entry.channel.get() as i64,
+                entry.bankgroup.get() as i64,
+                entry.bank.get() as i64,
+            ];
+            for (max, component) in max.iter_mut().zip(components) {
+                *max = (*max).max(component);
+            }
+        }
+
+        let count = |max: i64| u8::try_from(max + 1).unwrap_or(u8::MAX);
+        Self {
+            num_channels: count(max[0]),
+            num_bankgroups: count(max[1]),
+            num_banks: count(max[2]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutDimensionDiff {
+    pub stored: Option<u8>,
+    pub detected: u8,
+    /// The stored count can't address every index the trace uses.
+    pub too_small: bool,
+}
+
+/// Per dimension, the stored memory layout next to the one detected from the trace.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LayoutDiff {
+    pub stored: Option<MemoryLayout>,
+    pub detected: MemoryLayout,
+    pub channels: LayoutDimensionDiff,
+    pub bankgroups: LayoutDimensionDiff,
+    pub banks: LayoutDimensionDiff,
+}
+
+pub fn layout_diff(stored: Option<MemoryLayout>, detected: MemoryLayout) -> LayoutDiff {
+    let dimension = |field: fn(&MemoryLayout) -> u8| {
+        let stored = stored.as_ref().map(field);
+        let detected = field(&detected);
+        LayoutDimensionDiff {
+            stored,
+            detected,
+            too_small: stored.is_some_and(|stored| stored < detected),
+        }
+    };
+
+    LayoutDiff {
+        channels: dimension(|l| l.num_channels),
+        bankgroups: dimension(|l| l.num_bankgroups),
+        banks: dimension(|l| l.num_banks),
+        stored,
+        detected,
+    }
+}
+
+/// Default upper bound of entries one trace-view request may ask for. At 8-12 bytes per entry in
+/// the view buffer this keeps a single response well below 100MB.
+pub const DEFAULT_MAX_VIEW_ENTRIES: u64 = 5_000_000;
+
+/// State shared by all commands. Commands that hold several of these locks at once take them in
+/// one global order, `loader`, then `config`, then `memory`, so they can't deadlock each other.
+/// The remaining fields are only ever locked last, on their own or inside one of those three.
 pub struct SessionState {
     pub loader: Mutex<Option<TraceLoader>>,
     pub config: Mutex<Option<CommandConfig>>,
     pub memory: Mutex<Option<MemoryLayout>>,
+    pub categories: Mutex<Option<CategoryPatterns>>,
+    pub max_view_entries: Mutex<u64>,
+    /// Cancellation flag of the running streaming search, see `search::spawn`.
+    pub search_cancel: Mutex<Option<Arc<AtomicBool>>>,
+    /// Cancellation flag of the running export, see `export::progress`.
+    pub export_cancel: Mutex<Option<Arc<AtomicBool>>>,
+    /// Cancellation flag of the running view stream, see `view_stream`.
+    pub view_cancel: Mutex<Option<Arc<AtomicBool>>>,
+    /// `get_address_ranges` of the loaded trace; cleared whenever its entries change.
+    pub address_ranges: Mutex<Option<AddressRanges>>,
 }
 
 impl SessionState {
@@ -45,10 +353,116 @@ impl SessionState {
             loader: Mutex::new(None),
             config: Mutex::new(None),
             memory: Mutex::new(None),
+            categories: Mutex::new(None),
+            max_view_entries: Mutex::new(DEFAULT_MAX_VIEW_ENTRIES),
+            search_cancel: Mutex::new(None),
+            export_cancel: Mutex::new(None),
+            view_cancel: Mutex::new(None),
+            address_ranges: Mutex::new(None),
         }
     }
 }
 
+/// Rejects view requests over the configured entry cap instead of building a huge buffer.
+pub fn check_view_count(session: &SessionState, count: u64) -> Result<(), String> {
+    let max = *session.max_view_entries.lock().map_err(|e| e.to_string())?;
+    if count > max {
+        return Err(format!(
+            "Requested {} entries, but a view is limited to {}. Narrow the range or use sample_entries/get_command_runs instead.",
+            count, max
+        ));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDiagnostics {
+    pub trace_loaded: bool,
+    /// Bytes of the trace file that are memory mapped.
+    pub mapped_bytes: u64,
+    /// Bytes of trace data copied into memory instead of mapped.
+    pub owned_bytes: u64,
+    pub config_loaded: bool,
+    pub memory_layout_set: bool,
+}
+
+/// What the session currently holds. Every lock is held only for its own field, so this never
+/// blocks behind more than one other command.
+pub fn diagnostics(session: &SessionState) -> Result<SessionDiagnostics, String> {
+    let (trace_loaded, mapped_bytes, owned_bytes) = {
+        let guard = session.loader.lock().map_err(|e| e.to_string())?;
+        match guard.as_ref() {
+            Some(loader) if loader.is_mapped() => (true, loader.data_len(), 0),
+            Some(loader) => (true, 0, loader.data_len()),
+            None => (false, 0, 0),
+        }
+    };
+    let config_loaded = {
+        let guard = session.config.lock().map_err(|e| e.to_string())?;
+        guard.is_some()
+    };
+    let memory_layout_set = {
+        let guard = session.memory.lock().map_err(|e| e.to_string())?;
+        guard.is_some()
+    };
+
+    Ok(SessionDiagnostics {
+        trace_loaded,
+        mapped_bytes,
+        owned_bytes,
+        config_loaded,
+        memory_layout_set,
+    })
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionBundle {
+    pub header: Option<crate::trace::header::Header>,
+    pub dictionary: Option<Dictionary>,
+    pub command_config: Option<CommandConfig>,
+    pub memory_layout: Option<MemoryLayout>,
+}
+
+/// The loaded trace's header and dictionary with the config and layout in effect, where
+/// `stored_config` and `stored_layout` supply the stored ones if the session has none. The
+/// loader, config and layout locks are held together, in the order documented on
+/// `SessionState`, so the parts are a consistent snapshot.
+pub fn bundle(
+    session: &SessionState,
+    stored_config: impl FnOnce() -> Result<Option<CommandConfig>, String>,
+    stored_layout: impl FnOnce() -> Result<Option<MemoryLayout>, String>,
+) -> Result<SessionBundle, String> {
+    let loader_guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let config_guard = session.config.lock().map_err(|e| e.to_string())?;
+    let memory_guard = session.memory.lock().map_err(|e| e.to_string())?;
+
+    let (header, dictionary) = match loader_guard.as_ref() {
+        Some(loader) => (
+            Some(loader.header()),
+            Some(loader.load_dictionary().map_err(|e| e.to_string())?),
+        ),
+        None => (None, None),
+    };
+
+    let command_config = match config_guard.as_ref() {
+        Some(config) => Some(config.clone()),
+        None => stored_config()?,
+    };
+    let memory_layout = match memory_guard.as_ref() {
+        Some(layout) => Some(layout.clone()),
+        None => stored_layout()?,
+    };
+
+    Ok(SessionBundle {
+        header,
+        dictionary,
+        command_config,
+        memory_layout,
+    })
+}
+
 impl Default for SessionState {
     fn default() -> Self {
         Self::new()
@@ -60,14 +474,107 @@ pub fn load_command_config<R: Runtime>(
 ) -> Result<Option<CommandConfig>, String> {
     let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
 
-    if let Some(val) = store.get("commandConfig") {
-        let config: CommandConfig = serde_json::from_value(val).map_err(|e| e.to_string())?;
-        Ok(Some(config))
-    } else {
-        Ok(None)
+    store
+        .get("commandConfig")
+        .map(migrate_command_config)
+        .transpose()
+}
+
+/// The command config in effect: the session's, else the stored one. Everything that shows
+/// configured display settings reads it through here, so the views agree before the first edit.
+pub fn current_command_config<R: Runtime>(
+    app: &AppHandle<R>,
+    session: &SessionState,
+) -> Result<Option<CommandConfig>, String> {
+    let guard = session.config.lock().map_err(|e| e.to_string())?;
+    if let Some(config) = guard.as_ref() {
+        return Ok(Some(config.clone()));
+    }
+    drop(guard);
+
+    load_command_config(app)
+}
+
+/// Upgrades a stored command config of any known version to the current struct.
+/// Older shapes are read field by field, so a single malformed field falls back to its default
+/// instead of dropping the whole config.
+pub fn migrate_command_config(value: serde_json::Value) -> Result<CommandConfig, String> {
+    let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+
+    match version {
+        0 => {
+            let field = |name: &str| value.get(name).cloned().unwrap_or_default();
+
+            Ok(CommandConfig {
+                colors: serde_json::from_value(field("colors")).unwrap_or_default(),
+                clock_periods: serde_json::from_value(field("clockPeriods")).unwrap_or_default(),
+                ..CommandConfig::default()
+            })
+        }
+        // v1 only lacks fields that deserialize to their defaults.
+        1 => {
+            let mut config: CommandConfig =
+                serde_json::from_value(value).map_err(|e| e.to_string())?;
+            config.version = COMMAND_CONFIG_VERSION;
+            Ok(config)
+        }
+        v if v == COMMAND_CONFIG_VERSION as u64 => {
+            serde_json::from_value(value).map_err(|e| e.to_string())
+        }
+        v => Err(format!(
+            "command config version {} is newer than supported version {}",
+            v, COMMAND_CONFIG_VERSION
+        )),
+    }
+}
+
+/// The store operations the persisting commands need, so their logic can run against an
+/// in-memory store in tests.
+trait DurableStore {
+    fn get(&self, key: &str) -> Option<serde_json::Value>;
+    fn set(&self, key: &str, value: serde_json::Value);
+    fn delete(&self, key: &str);
+    fn save(&self) -> Result<(), String>;
+}
+
+impl<R: Runtime> DurableStore for Store<R> {
+    fn get(&self, key: &str) -> Option<serde_json::Value> {
+        Store::get(self, key)
+    }
+
+    fn set(&self, key: &str, value: serde_json::Value) {
+        Store::set(self, key, value)
+    }
+
+    fn delete(&self, key: &str) {
+        Store::delete(self, key);
+    }
+
+    fn save(&self) -> Result<(), String> {
+        Store::save(self).map_err(|e| e.to_string())
     }
 }
 
+/// Sets `key` and saves the store to disk right away instead of relying on the plugin's deferred
+/// save, so a crash right after a settings change can't lose it. If the save fails, `key` is
+/// restored to its previous value so the failed change doesn't stay live in memory either.
+fn set_durably(
+    store: &impl DurableStore,
+    key: &str,
+    value: serde_json::Value,
+) -> Result<(), String> {
+    let previous = store.get(key);
+    store.set(key, value);
+
+    store.save().map_err(|e| {
+        match previous {
+            Some(previous) => store.set(key, previous),
+            None => store.delete(key),
+        }
+        format!("Failed to save {}: {}", key, e)
+    })
+}
+
 pub fn load_memory_layout<R: Runtime>(app: &AppHandle<R>) -> Result<Option<MemoryLayout>, String> {
     let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
 
@@ -79,6 +586,20 @@ pub fn load_memory_layout<R: Runtime>(app: &AppHandle<R>) -> Result<Option<Memor
     }
 }
 
+/// The memory layout in effect: the session's, else the stored one.
+pub fn current_memory_layout<R: Runtime>(
+    app: &AppHandle<R>,
+    session: &SessionState,
+) -> Result<Option<MemoryLayout>, String> {
+    let guard = session.memory.lock().map_err(|e| e.to_string())?;
+    if let Some(layout) = guard.as_ref() {
+        return Ok(Some(layout.clone()));
+    }
+    drop(guard);
+
+    load_memory_layout(app)
+}
+
 pub fn set_command_config<R: Runtime>(
     app: &AppHandle<R>,
     session: &SessionState,
@@ -88,10 +609,65 @@ pub fn set_command_config<R: Runtime>(
     let command_config_value =
         serde_json::to_value(command_config.clone()).map_err(|e| e.to_string())?;
 
-    store.set("commandConfig", command_config_value);
+    set_durably(&*store, "commandConfig", command_config_value)?;
+
+    let sentinels = command_config.invalid_sentinels.clone();
+    {
+        let mut guard = session.config.lock().map_err(|e| e.to_string())?;
+        *guard = Some(command_config);
+    }
+
+    apply_sentinels(session, &sentinels)
+}
+
+/// Re-normalizes the loaded trace (if any) with the configured sentinel values.
+pub fn apply_sentinels(session: &SessionState, sentinels: &[i64]) -> Result<(), String> {
+    let mut guard = session.loader.lock().map_err(|e| e.to_string())?;
+    if let Some(loader) = guard.as_mut() {
+        loader.set_sentinels(sentinels).map_err(|e| e.to_string())?;
+    }
+    clear_address_ranges(session)
+}
+
+/// Drops the cached address ranges, to be called whenever the loaded entries change.
+pub fn clear_address_ranges(session: &SessionState) -> Result<(), String> {
+    let mut guard = session.address_ranges.lock().map_err(|e| e.to_string())?;
+    *guard = None;
+    Ok(())
+}
+
+/// `current` with `patch` applied, starting from an empty config if there is none yet. Everything
+/// `patch` doesn't touch is kept as is.
+pub fn patched_command_config(
+    current: Option<CommandConfig>,
+    patch: impl FnOnce(&mut CommandConfig),
+) -> CommandConfig {
+    let mut config = current.unwrap_or_default();
+    patch(&mut config);
+    config
+}
 
+/// Applies `patch` to the current command config and persists the result.
+/// The config mutex is held for the whole read-modify-write, so concurrent patches of different
+/// fields can't overwrite each other. If no config exists yet, the patch starts from an empty one.
+pub fn patch_command_config<R: Runtime>(
+    app: &AppHandle<R>,
+    session: &SessionState,
+    patch: impl FnOnce(&mut CommandConfig),
+) -> Result<(), String> {
     let mut guard = session.config.lock().map_err(|e| e.to_string())?;
-    *guard = Some(command_config);
+
+    let current = match guard.as_ref() {
+        Some(c) => Some(c.clone()),
+        None => load_command_config(app)?,
+    };
+    let config = patched_command_config(current, patch);
+
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    let config_value = serde_json::to_value(config.clone()).map_err(|e| e.to_string())?;
+    set_durably(&*store, "commandConfig", config_value)?;
+
+    *guard = Some(config);
 
     Ok(())
 }
@@ -105,7 +681,7 @@ pub fn set_memory_layout<R: Runtime>(
     let memory_layout_value =
         serde_json::to_value(memory_layout.clone()).map_err(|e| e.to_string())?;
 
-    store.set("memoryLayout", memory_layout_value);
+    set_durably(&*store, "memoryLayout", memory_layout_value)?;
 
     let mut guard = session.memory.lock().map_err(|e| e.to_string())?;
 
@@ -114,6 +690,150 @@ pub fn set_memory_layout<R: Runtime>(
     Ok(())
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilterPreset {
+    pub name: String,
+    pub filter: EntryFilter,
+}
+
+/// The presets stored under `filterPresets`, by name. A missing key means no presets yet.
+fn stored_filter_presets(
+    store: &impl DurableStore,
+) -> Result<std::collections::BTreeMap<String, EntryFilter>, String> {
+    match store.get("filterPresets") {
+        Some(val) => serde_json::from_value(val).map_err(|e| e.to_string()),
+        None => Ok(Default::default()),
+    }
+}
+
+/// The saved filter presets, sorted by name. Presets are global rather than per trace.
+pub fn list_filter_presets<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<FilterPreset>, String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    list_presets(&*store)
+}
+
+fn list_presets(store: &impl DurableStore) -> Result<Vec<FilterPreset>, String> {
+    Ok(stored_filter_presets(store)?
+        .into_iter()
+        .map(|(name, filter)| FilterPreset { name, filter })
+        .collect())
+}
+
+/// Saves `filter` under `name`. Names are unique: saving under an existing name replaces that
+/// preset, so the UI's "save" doubles as "update".
+pub fn save_filter_preset<R: Runtime>(
+    app: &AppHandle<R>,
+    name: String,
+    filter: EntryFilter,
+) -> Result<(), String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    save_preset(&*store, name, filter)
+}
+
+fn save_preset(store: &impl DurableStore, name: String, filter: EntryFilter) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("A filter preset needs a name".to_string());
+    }
+
+    let mut presets = stored_filter_presets(store)?;
+    presets.insert(name, filter);
+
+    let value = serde_json::to_value(presets).map_err(|e| e.to_string())?;
+    set_durably(store, "filterPresets", value)
+}
+
+/// Removes the preset `name`. Returns whether it existed.
+pub fn delete_filter_preset<R: Runtime>(app: &AppHandle<R>, name: &str) -> Result<bool, String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    delete_preset(&*store, name)
+}
+
+fn delete_preset(store: &impl DurableStore, name: &str) -> Result<bool, String> {
+    let mut presets = stored_filter_presets(store)?;
+    if presets.remove(name).is_none() {
+        return Ok(false);
+    }
+
+    let value = serde_json::to_value(presets).map_err(|e| e.to_string())?;
+    set_durably(store, "filterPresets", value)?;
+    Ok(true)
+}
+
+pub fn load_command_categories<R: Runtime>(
+    app: &AppHandle<R>,
+) -> Result<Option<CategoryPatterns>, String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+
+    if let Some(val) = store.get("commandCategories") {
+        let patterns: CategoryPatterns = serde_json::from_value(val).map_err(|e| e.to_string())?;
+        Ok(Some(patterns))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The category patterns in effect: the session's, else the stored ones, else the defaults.
+pub fn command_categories<R: Runtime>(
+    app: &AppHandle<R>,
+    session: &SessionState,
+) -> Result<CategoryPatterns, String> {
+    let guard = session.categories.lock().map_err(|e| e.to_string())?;
+    if let Some(patterns) = guard.as_ref() {
+        return Ok(patterns.clone());
+    }
+    drop(guard);
+
+    Ok(load_command_categories(app)?.unwrap_or_default())
+}
+
+pub fn set_command_categories<R: Runtime>(
+    app: &AppHandle<R>,
+    session: &SessionState,
+    patterns: CategoryPatterns,
+) -> Result<(), String> {
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+    let patterns_value = serde_json::to_value(patterns.clone()).map_err(|e| e.to_string())?;
+
+    set_durably(&*store, "commandCategories", patterns_value)?;
+
+    let mut guard = session.categories.lock().map_err(|e| e.to_string())?;
+    *guard = Some(patterns);
+
+    Ok(())
+}
+
+/// Where the config store lives and what it holds, for diagnosing configs that don't persist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreInfo {
+    pub path: String,
+    pub exists: bool,
+    /// Keys currently in the store, including values not yet flushed to disk. Sorted.
+    pub keys: Vec<String>,
+}
+
+/// Reports the resolved store path and its keys. Opening the store doesn't write it, so a missing
+/// file stays missing.
+pub fn store_info<R: Runtime>(app: &AppHandle<R>) -> Result<StoreInfo, String> {
+    let path =
+        tauri_plugin_store::resolve_store_path(app, STORE_PATH).map_err(|e| e.to_string())?;
+    let store = app.store(STORE_PATH).map_err(|e| e.to_string())?;
+
+    Ok(describe_store(&path, store.keys()))
+}
+
+/// The store at `path` holding `keys`, without touching the file.
+fn describe_store(path: &Path, mut keys: Vec<String>) -> StoreInfo {
+    keys.sort();
+
+    StoreInfo {
+        path: path.to_string_lossy().into_owned(),
+        exists: path.exists(),
+        keys,
+    }
+}
+
 // --------------------- //
 // YAML Config Export     //
 // --------------------- //
@@ -124,6 +844,62 @@ pub struct FullConfig {
     pub command_config: Option<CommandConfig>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub memory_layout: Option<MemoryLayout>,
+    /// Dictionary of the trace loaded at export, so an import can rekey the command config by name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dictionary: Option<Dictionary>,
+}
+
+/// Dictionary of the loaded trace, if any.
+fn loaded_dictionary(session: &SessionState) -> Result<Option<Dictionary>, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+    match guard.as_ref() {
+        Some(loader) => loader
+            .load_dictionary()
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        None => Ok(None),
+    }
+}
+
+/// Rekeys `colors`, `clock_periods` and `command_aliases` from the ids of `old_dict` to the ids
+/// of the commands with the same name in `new_dict`. Settings of commands missing from `new_dict` are dropped.
+/// The config is keyed by raw cmd_id, so without this a config saved for one trace lands on the
+/// wrong commands of a trace whose dictionary is ordered differently.
+pub fn remap_config_by_name(
+    old_dict: &Dictionary,
+    new_dict: &Dictionary,
+    config: &CommandConfig,
+) -> CommandConfig {
+    let mut new_ids: HashMap<&str, u8> = HashMap::new();
+    for (cmd_id, name) in &new_dict.commands {
+        new_ids
+            .entry(name.as_str())
+            .and_modify(|id| *id = (*id).min(*cmd_id))
+            .or_insert(*cmd_id);
+    }
+    let remap = |old_id: &u8| {
+        let name = old_dict.commands.get(old_id)?;
+        new_ids.get(name.as_str()).copied()
+    };
+
+    CommandConfig {
+        colors: config
+            .colors
+            .iter()
+            .filter_map(|(id, color)| Some((remap(id)?, color.clone())))
+            .collect(),
+        clock_periods: config
+            .clock_periods
+            .iter()
+            .filter_map(|(id, period)| Some((remap(id)?, *period)))
+            .collect(),
+        command_aliases: config
+            .command_aliases
+            .iter()
+            .filter_map(|(id, alias)| Some((remap(id)?, alias.clone())))
+            .collect(),
+        ..config.clone()
+    }
 }
 
 pub fn export_config_yaml<R: Runtime>(
@@ -139,21 +915,17 @@ pub fn export_config_yaml<R: Runtime>(
         }
     };
 
-    let layout = {
-        let guard = session.memory.lock().map_err(|e| e.to_string())?;
-        match guard.as_ref() {
-            Some(l) => Some(l.clone()),
-            None => load_memory_layout(app)?,
-        }
-    };
+    let layout = current_memory_layout(app, session)?;
 
     let full = FullConfig {
         command_config: config,
         memory_layout: layout,
+        dictionary: loaded_dictionary(session)?,
     };
 
     let yaml = serde_yaml::to_string(&full).map_err(|e| e.to_string())?;
-    fs::write(&path, yaml).map_err(|e| e.to_string())?;
+    write_atomic(Path::new(&path), |writer| writer.write_all(yaml.as_bytes()))
+        .map_err(|e| e.to_string())?;
 
     Ok(())
 }
@@ -162,15 +934,86 @@ pub fn export_config_yaml<R: Runtime>(
 // YAML Config Import     //
 // --------------------- //
 
+/// One invalid value found in an imported config. `key` is the path of the offending value,
+/// e.g. `command_config.colors.3`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigProblem {
+    pub key: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigImportReport {
+    /// Whether the (possibly trimmed) config was applied.
+    pub applied: bool,
+    pub problems: Vec<ConfigProblem>,
+}
+
+impl CommandConfig {
+    /// Removes colors that aren't valid hex and clock periods that aren't positive and finite,
+    /// returning one problem per removed value.
+    fn drop_invalid(&mut self) -> Vec<ConfigProblem> {
+        let mut problems = Vec::new();
+
+        self.colors.retain(|cmd_id, color| {
+            let valid = parse_hex_color(color).is_some();
+            if !valid {
+                problems.push(ConfigProblem {
+                    key: format!("command_config.colors.{}", cmd_id),
+                    message: format!("'{}' is not a hex color", color),
+                });
+            }
+            valid
+        });
+
+        self.clock_periods.retain(|cmd_id, period| {
+            let valid = period.is_finite() && *period > 0.0;
+            if !valid {
+                problems.push(ConfigProblem {
+                    key: format!("command_config.clockPeriods.{}", cmd_id),
+                    message: format!("{} is not a positive clock period", period),
+                });
+            }
+            valid
+        });
+
+        problems.sort_by(|a, b| a.key.cmp(&b.key));
+        problems
+    }
+}
+
+/// Imports a YAML config after validating it. In strict mode any problem rejects the whole file;
+/// otherwise the invalid values are dropped and the rest is applied.
+/// If the file carries the dictionary it was exported with and a trace is loaded, the command
+/// config is rekeyed by command name onto the loaded trace's ids, see `remap_config_by_name`.
 pub fn import_config_yaml<R: Runtime>(
     app: &AppHandle<R>,
     session: &SessionState,
     path: String,
-) -> Result<(), String> {
+    strict: bool,
+) -> Result<ConfigImportReport, String> {
     let contents = fs::read_to_string(&path).map_err(|e| e.to_string())?;
-    let full: FullConfig = serde_yaml::from_str(&contents).map_err(|e| e.to_string())?;
+    let mut full: FullConfig = serde_yaml::from_str(&contents).map_err(|e| e.to_string())?;
 
-    if let Some(config) = full.command_config {
+    let problems = full
+        .command_config
+        .as_mut()
+        .map(CommandConfig::drop_invalid)
+        .unwrap_or_default();
+
+    if strict && !problems.is_empty() {
+        return Ok(ConfigImportReport {
+            applied: false,
+            problems,
+        });
+    }
+
+    if let Some(mut config) = full.command_config {
+        if let (Some(old_dict), Some(new_dict)) = (&full.dictionary, loaded_dictionary(session)?) {
+            config = remap_config_by_name(old_dict, &new_dict, &config);
+        }
         set_command_config(app, session, config)?;
     }
 
@@ -178,5 +1021,461 @@ pub fn import_config_yaml<R: Runtime>(
         set_memory_layout(app, session, layout)?;
     }
 
-    Ok(())
+    Ok(ConfigImportReport {
+        applied: true,
+        problems,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMPORTED: &str = "
+command_config:
+  colors:
+    0: '#ff8800'
+    1: 'orange'
+  clockPeriods:
+    0: 1.25
+    1: -2.0
+memory_layout:
+  numChannels: 2
+  numBankgroups: 4
+  numBanks: 4
+";
+
+    #[test]
+    fn drop_invalid_reports_bad_color_and_period() {
+        let full: FullConfig = serde_yaml::from_str(IMPORTED).unwrap();
+        let mut config = full.command_config.unwrap();
+
+        let problems = config.drop_invalid();
+        let keys: Vec<&str> = problems.iter().map(|p| p.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            ["command_config.clockPeriods.1", "command_config.colors.1"]
+        );
+
+        assert_eq!(config.colors, HashMap::from([(0, "#ff8800".to_string())]));
+        assert_eq!(config.clock_periods, HashMap::from([(0, 1.25)]));
+        assert_eq!(full.memory_layout.unwrap().flat_bank_count(), 32);
+    }
+
+    #[test]
+    fn drop_invalid_keeps_a_valid_config() {
+        let mut config = CommandConfig::default();
+        config.colors.insert(3, "00FFaa".to_string());
+        config.clock_periods.insert(3, 0.5);
+
+        assert!(config.drop_invalid().is_empty());
+        assert_eq!(parse_hex_color(&config.colors[&3]), Some([0, 255, 170]));
+    }
+
+    #[test]
+    fn aliases_label_the_legend_but_not_the_dictionary() {
+        let dictionary = Dictionary::from_names(&["ACT", "RD", "PRE"]);
+        let mut config = CommandConfig::default();
+        config.command_aliases.insert(0, "Activate".to_string());
+        config.command_aliases.insert(2, String::new());
+
+        let legend = command_legend(Some(&config), &dictionary);
+        let labels: Vec<(&str, &str)> = legend
+            .iter()
+            .map(|entry| (entry.name.as_str(), entry.label.as_str()))
+            .collect();
+        assert_eq!(labels, [("ACT", "Activate"), ("RD", "RD"), ("PRE", "PRE")]);
+        assert_eq!(dictionary.commands[&0], "ACT");
+    }
+
+    #[test]
+    fn aliases_follow_their_command_across_dictionaries() {
+        let old_dict = Dictionary::from_names(&["ACT", "RD"]);
+        let new_dict = Dictionary::from_names(&["RD", "WR", "ACT"]);
+        let mut config = CommandConfig::default();
+        config.command_aliases.insert(0, "Activate".to_string());
+        config.command_aliases.insert(1, "Read".to_string());
+
+        let remapped = remap_config_by_name(&old_dict, &new_dict, &config);
+        assert_eq!(
+            remapped.command_aliases,
+            HashMap::from([(2, "Activate".to_string()), (0, "Read".to_string())])
+        );
+    }
+
+    #[test]
+    fn v1_configs_migrate_without_aliases() {
+        let v1 = serde_json::json!({
+            "version": 1,
+            "colors": { "0": "#ff0000" },
+            "clockPeriods": {},
+            "invalidSentinels": [-1],
+        });
+
+        let config = migrate_command_config(v1).unwrap();
+        assert_eq!(config.version, COMMAND_CONFIG_VERSION);
+        assert_eq!(config.invalid_sentinels, [-1]);
+        assert!(config.command_aliases.is_empty());
+
+        let newer = serde_json::json!({ "version": COMMAND_CONFIG_VERSION + 1 });
+        assert!(migrate_command_config(newer).is_err());
+    }
+
+    #[test]
+    fn unversioned_configs_keep_what_still_parses() {
+        let v0 = serde_json::json!({
+            "colors": { "2": "#123456" },
+            "clockPeriods": "not a map",
+            "burstBytes": 64,
+        });
+
+        let config = migrate_command_config(v0).unwrap();
+        assert_eq!(config.version, COMMAND_CONFIG_VERSION);
+        assert_eq!(config.colors, HashMap::from([(2, "#123456".to_string())]));
+        assert!(config.clock_periods.is_empty());
+        assert!(config.invalid_sentinels.is_empty());
+    }
+
+    #[test]
+    fn view_counts_are_capped_by_the_session_limit() {
+        let session = SessionState::new();
+        assert!(check_view_count(&session, DEFAULT_MAX_VIEW_ENTRIES).is_ok());
+        assert!(check_view_count(&session, DEFAULT_MAX_VIEW_ENTRIES + 1).is_err());
+
+        *session.max_view_entries.lock().unwrap() = 10;
+        assert!(check_view_count(&session, 10).is_ok());
+        let err = check_view_count(&session, 11).unwrap_err();
+        assert!(err.starts_with("Requested 11 entries, but a view is limited to 10."));
+    }
+
+    #[test]
+    fn render_config_falls_back_per_command() {
+        let mut config = CommandConfig::default();
+        config.colors.insert(0, "#ff0000".to_string());
+        config.colors.insert(1, "0000FF".to_string());
+        config.colors.insert(2, "teal".to_string());
+        config.clock_periods.insert(1, 4.0);
+
+        let render = render_config(Some(&config), 3);
+        assert_eq!(
+            render.colors,
+            [[1.0, 0.0, 0.0], [0.0, 0.0, 1.0], DEFAULT_RENDER_COLOR]
+        );
+        assert_eq!(
+            render.periods,
+            [DEFAULT_CLOCK_PERIOD, 4.0, DEFAULT_CLOCK_PERIOD]
+        );
+
+        let unconfigured = render_config(None, 2);
+        assert_eq!(unconfigured.colors, [DEFAULT_RENDER_COLOR; 2]);
+        assert_eq!(unconfigured.periods, [DEFAULT_CLOCK_PERIOD; 2]);
+        assert!(render_config(None, 0).colors.is_empty());
+    }
+
+    #[test]
+    fn colors_and_periods_follow_names_into_a_reordered_dictionary() {
+        let old_dict = Dictionary::from_names(&["ACT", "PRE", "RD", "WR"]);
+        let new_dict = Dictionary::from_names(&["WR", "RD", "ACT"]);
+        let mut config = CommandConfig::default();
+        config.colors.insert(0, "#00ff00".to_string());
+        config.colors.insert(1, "#ff0000".to_string());
+        config.clock_periods.insert(2, 3.0);
+        config.clock_periods.insert(3, 5.0);
+        // Not in the old dictionary at all.
+        config.clock_periods.insert(9, 1.0);
+
+        let remapped = remap_config_by_name(&old_dict, &new_dict, &config);
+        // PRE has no counterpart and is dropped.
+        assert_eq!(remapped.colors, HashMap::from([(2, "#00ff00".to_string())]));
+        assert_eq!(remapped.clock_periods, HashMap::from([(1, 3.0), (0, 5.0)]));
+    }
+
+    #[test]
+    fn detected_layout_is_compared_per_dimension() {
+        let entries = [
+            Entry::new(0, [1, 0, 3, 0, 9, 0], 0),
+            Entry::new(1, [0, 0, 0, 7, 9, 0], 0),
+            Entry::new(2, [-1, 0, -1, -1, -1, -1], 1),
+        ];
+        let detected = MemoryLayout::detect(&entries);
+        assert_eq!(
+            (
+                detected.num_channels,
+                detected.num_bankgroups,
+                detected.num_banks
+            ),
+            (2, 4, 8)
+        );
+
+        let stored = MemoryLayout {
+            num_channels: 4,
+            num_bankgroups: 2,
+            num_banks: 8,
+        };
+        let diff = layout_diff(Some(stored), detected.clone());
+        let too_small = [diff.channels, diff.bankgroups, diff.banks].map(|d| d.too_small);
+        assert_eq!(too_small, [false, true, false]);
+        assert_eq!(diff.bankgroups.stored, Some(2));
+
+        let unstored = layout_diff(None, detected);
+        assert_eq!(
+            (unstored.banks.stored, unstored.banks.too_small),
+            (None, false)
+        );
+        assert_eq!(MemoryLayout::detect(&[]).num_channels, 0);
+    }
+
+    #[test]
+    fn palette_is_sorted_and_falls_back_to_grey() {
+        let dictionary = Dictionary {
+            commands: HashMap::from([
+                (9, "REF".to_string()),
+                (1, "ACT".to_string()),
+                (4, "RD".to_string()),
+            ]),
+        };
+        let config = CommandConfig {
+            colors: HashMap::from([(1, "#FF0000".to_string()), (4, "teal".to_string())]),
+            ..CommandConfig::default()
+        };
+
+        let palette = command_palette(Some(&config), &dictionary);
+        let shown: Vec<(u8, &str, &str)> = palette
+            .iter()
+            .map(|e| (e.id, e.name.as_str(), e.color_hex.as_str()))
+            .collect();
+        assert_eq!(
+            shown,
+            [
+                (1, "ACT", "#ff0000"),
+                (4, "RD", "#808080"),
+                (9, "REF", "#808080")
+            ]
+        );
+
+        assert!(command_palette(None, &dictionary)
+            .iter()
+            .all(|e| e.color_hex == "#808080"));
+    }
+
+    #[test]
+    fn diagnostics_follow_the_loaded_trace() {
+        let path = crate::trace::writer::tests::scratch_path("session-diagnostics.bin");
+        let entries: Vec<Entry> = (0..8)
+            .map(|clk| Entry::new(clk * 3, [0, 0, 0, clk % 2, 40, -1], 0))
+            .collect();
+        crate::trace::writer::write_trace(&path, 1, &entries, &Dictionary::from_names(&["ACT"]))
+            .unwrap();
+        let session = SessionState::new();
+
+        let closed = diagnostics(&session).unwrap();
+        assert!(!closed.trace_loaded);
+        assert_eq!((closed.mapped_bytes, closed.owned_bytes), (0, 0));
+
+        let mut loader = TraceLoader::new(path.clone()).unwrap();
+        let data_len = loader.data_len();
+        *session.loader.lock().unwrap() = Some(loader);
+        *session.config.lock().unwrap() = Some(CommandConfig::default());
+        let opened = diagnostics(&session).unwrap();
+        assert!(opened.trace_loaded && opened.config_loaded && !opened.memory_layout_set);
+        assert_eq!((opened.mapped_bytes, opened.owned_bytes), (data_len, 0));
+
+        // Normalizing sentinels moves the data from the mapping into memory.
+        loader = session.loader.lock().unwrap().take().unwrap();
+        loader.set_sentinels(&[40]).unwrap();
+        *session.loader.lock().unwrap() = Some(loader);
+        let copied = diagnostics(&session).unwrap();
+        assert_eq!((copied.mapped_bytes, copied.owned_bytes), (0, data_len));
+
+        *session.loader.lock().unwrap() = None;
+        *session.config.lock().unwrap() = None;
+        assert_eq!(diagnostics(&session).unwrap(), closed);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn patching_one_color_keeps_the_rest_of_the_config() {
+        let current = CommandConfig {
+            colors: HashMap::from([(0, "#ff0000".to_string()), (1, "#00ff00".to_string())]),
+            clock_periods: HashMap::from([(0, 1.25), (2, 0.5)]),
+            invalid_sentinels: vec![65535],
+            ..CommandConfig::default()
+        };
+
+        let patched = patched_command_config(Some(current.clone()), |config| {
+            config.colors.insert(1, "#0000ff".to_string());
+        });
+        assert_eq!(patched.colors[&1], "#0000ff");
+        assert_eq!(patched.colors[&0], current.colors[&0]);
+        assert_eq!(patched.clock_periods, current.clock_periods);
+        assert_eq!(patched.invalid_sentinels, current.invalid_sentinels);
+
+        let created = patched_command_config(None, |config| {
+            config.clock_periods.insert(3, 2.0);
+        });
+        assert_eq!(created.version, COMMAND_CONFIG_VERSION);
+        assert_eq!(created.clock_periods, HashMap::from([(3, 2.0)]));
+        assert!(created.colors.is_empty());
+    }
+
+    #[test]
+    fn store_info_reports_the_file_without_creating_it() {
+        let dir = std::env::temp_dir().join(format!("ramwiz-store-info-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(STORE_PATH);
+
+        let before = describe_store(&path, Vec::new());
+        assert_eq!(before.path, path.to_string_lossy());
+        assert!(!before.exists && before.keys.is_empty());
+        assert!(!path.exists());
+
+        // What the store holds once `set_command_config` and a layout change have been saved.
+        let saved = serde_json::json!({
+            "memoryLayout": { "num_channels": 1, "num_bankgroups": 4, "num_banks": 4 },
+            "commandConfig": CommandConfig::default(),
+        });
+        fs::write(&path, saved.to_string()).unwrap();
+        let keys = ["memoryLayout", "commandConfig"].map(String::from).to_vec();
+        let after = describe_store(&path, keys);
+        assert!(after.exists);
+        assert_eq!(after.keys, ["commandConfig", "memoryLayout"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// A store whose saves copy the values to `saved`, or fail if `fail_saves` is set.
+    #[derive(Default)]
+    struct MemoryStore {
+        values: std::cell::RefCell<HashMap<String, serde_json::Value>>,
+        saved: std::cell::RefCell<HashMap<String, serde_json::Value>>,
+        fail_saves: std::cell::Cell<bool>,
+    }
+
+    impl DurableStore for MemoryStore {
+        fn get(&self, key: &str) -> Option<serde_json::Value> {
+            self.values.borrow().get(key).cloned()
+        }
+
+        fn set(&self, key: &str, value: serde_json::Value) {
+            self.values.borrow_mut().insert(key.to_string(), value);
+        }
+
+        fn delete(&self, key: &str) {
+            self.values.borrow_mut().remove(key);
+        }
+
+        fn save(&self) -> Result<(), String> {
+            if self.fail_saves.get() {
+                return Err("disk full".to_string());
+            }
+            *self.saved.borrow_mut() = self.values.borrow().clone();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_saves_surface_and_roll_back_the_value() {
+        let store = MemoryStore::default();
+        set_durably(&store, "memoryLayout", serde_json::json!(1)).unwrap();
+        assert_eq!(store.saved.borrow()["memoryLayout"], 1);
+
+        store.fail_saves.set(true);
+        assert_eq!(
+            set_durably(&store, "memoryLayout", serde_json::json!(2)).unwrap_err(),
+            "Failed to save memoryLayout: disk full"
+        );
+        assert_eq!(store.get("memoryLayout"), Some(serde_json::json!(1)));
+
+        // A key that didn't exist before is removed again.
+        assert!(set_durably(&store, "commandConfig", serde_json::json!({})).is_err());
+        assert_eq!(store.get("commandConfig"), None);
+        assert_eq!(store.saved.borrow().len(), 1);
+    }
+
+    #[test]
+    fn bundle_snapshots_the_session_with_stored_fallbacks() {
+        let layout = MemoryLayout {
+            num_channels: 2,
+            num_bankgroups: 4,
+            num_banks: 4,
+        };
+        let stored = CommandConfig {
+            colors: HashMap::from([(0, "#123456".to_string())]),
+            ..CommandConfig::default()
+        };
+        let session = SessionState::new();
+
+        let empty = bundle(&session, || Ok(Some(stored.clone())), || Ok(None)).unwrap();
+        assert!(empty.header.is_none() && empty.dictionary.is_none());
+        assert_eq!(empty.command_config.map(|c| c.colors), Some(stored.colors));
+        assert!(empty.memory_layout.is_none());
+
+        let path = crate::trace::writer::tests::scratch_path("session-bundle.bin");
+        let entries = [Entry::new(5, [0, 0, 1, 2, 3, 4], 1)];
+        let dictionary = Dictionary::from_names(&["ACT", "RD"]);
+        crate::trace::writer::write_trace(&path, 1, &entries, &dictionary).unwrap();
+        *session.loader.lock().unwrap() = Some(TraceLoader::new(path.clone()).unwrap());
+        *session.config.lock().unwrap() = Some(CommandConfig::default());
+        *session.memory.lock().unwrap() = Some(layout);
+
+        // The session's own config and layout win; the store isn't consulted.
+        let loaded = bundle(&session, || unreachable!(), || unreachable!()).unwrap();
+        assert_eq!(loaded.header.map(|h| h.num_entries()), Some(1));
+        assert_eq!(loaded.dictionary, Some(dictionary));
+        assert!(loaded.command_config.unwrap().colors.is_empty());
+        assert_eq!(loaded.memory_layout.map(|l| l.num_channels), Some(2));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn filter_presets_round_trip_through_the_store() {
+        let store = MemoryStore::default();
+        let rd_to_bank_3 = EntryFilter {
+            cmd_id: Some(1),
+            channel: Some(0),
+            bank: Some(3),
+            ..EntryFilter::default()
+        };
+        let row_7 = EntryFilter {
+            row: Some(7),
+            ..EntryFilter::default()
+        };
+
+        assert!(list_presets(&store).unwrap().is_empty());
+        save_preset(&store, "reads".to_string(), row_7.clone()).unwrap();
+        save_preset(&store, "hot row".to_string(), row_7.clone()).unwrap();
+        // Saving under a taken name replaces the preset.
+        save_preset(&store, "reads".to_string(), rd_to_bank_3.clone()).unwrap();
+        assert!(save_preset(&store, "  ".to_string(), row_7.clone()).is_err());
+
+        let listed = list_presets(&store).unwrap();
+        assert_eq!(
+            listed,
+            [
+                FilterPreset {
+                    name: "hot row".to_string(),
+                    filter: row_7,
+                },
+                FilterPreset {
+                    name: "reads".to_string(),
+                    filter: rd_to_bank_3,
+                },
+            ]
+        );
+        assert_eq!(store.saved.borrow()["filterPresets"]["reads"]["cmdId"], 1);
+
+        assert!(delete_preset(&store, "hot row").unwrap());
+        assert!(!delete_preset(&store, "hot row").unwrap());
+        let names: Vec<String> = list_presets(&store)
+            .unwrap()
+            .into_iter()
+            .map(|p| p.name)
+            .collect();
+        assert_eq!(names, ["reads"]);
+
+        store.set("filterPresets", serde_json::json!(["reads"]));
+        assert!(list_presets(&store).is_err());
+    }
 }
@@ -2,6 +2,7 @@
 /// It defines the API for interacting with the trace file to be used by Tauri commands.
 /// Currently, we only support the custom binary trace file format defined in this repository:
 /// https://github.com/ziadomalik/ramulator2/blob/mtrc/src/dram_controller/impl/plugin/mtrc/mtrc.h
+/// and its compressed variant, see `compressed`.
 ///
 /// Layout of a trace file:
 ///
@@ -22,41 +23,399 @@
 /// Email: zmalik@ethz.ch
 /// ----
 use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 use std::fs::File;
-use std::path::PathBuf;
+use std::io::Read;
+use std::ops::{Deref, Range};
+use std::path::{Path, PathBuf};
 
+pub mod compressed;
+pub mod demo;
 pub mod dictionary;
 pub mod entry;
+pub mod filter;
 pub mod header;
+pub mod runs;
 pub mod serialize;
+pub mod writer;
 
 pub use dictionary::Dictionary;
 pub use entry::Entry;
-pub use header::Header;
+pub use filter::EntryFilter;
+pub use header::{Endianness, Header};
+pub use runs::Run;
 
-use zerocopy::Ref;
+use zerocopy::{FromBytes, IntoBytes, Ref};
+
+/// Whether the file at `path` starts like a trace of a supported version (little- or big-endian,
+/// or compressed). Only the first header's worth of bytes is read, and files that can't be read
+/// or are too short are simply not traces. Passing this doesn't guarantee the rest loads.
+pub fn is_trace_file(path: &Path) -> bool {
+    let mut prefix = Vec::with_capacity(std::mem::size_of::<Header>());
+    let read = File::open(path).and_then(|file| {
+        file.take(std::mem::size_of::<Header>() as u64)
+            .read_to_end(&mut prefix)
+    });
+    if read.is_err() {
+        return false;
+    }
+
+    let has_magic =
+        compressed::is_compressed(&prefix) || header::detect_endianness(&prefix).is_ok();
+    has_magic
+        && prefix
+            .get(5)
+            .is_some_and(|version| header::is_supported_version(*version))
+}
+
+/// Bytes of a loaded trace. Little-endian files are memory mapped; big-endian files are read into
+/// memory once and byte-swapped, so everything downstream only ever sees the little-endian layout.
+enum TraceData {
+    Mapped(Mmap),
+    Owned(Vec<u8>),
+}
+
+impl Deref for TraceData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            TraceData::Mapped(mmap) => mmap,
+            TraceData::Owned(bytes) => bytes,
+        }
+    }
+}
 
 pub struct TraceLoader {
-    mmap: Mmap,
+    path: PathBuf,
+    data: TraceData,
     header: Header,
+    endianness: Endianness,
+    sentinels: Vec<i64>,
+    identity: u64,
+    /// Indices of the file's entries this loader presents, see `new_ranged`. Index 0 of every
+    /// read method is `window.start` in the file.
+    window: Range<u64>,
+    /// The clk range the window was derived from, reapplied on reload.
+    clk_range: Option<(i64, i64)>,
+}
+
+/// Number of evenly spaced entries that go into the identity hash.
+const IDENTITY_SAMPLES: u64 = 1024;
+
+/// Upper bound of dictionary bytes that go into the identity hash.
+const IDENTITY_DICT_BYTES: usize = 64 * 1024;
+
+/// 64-bit FNV-1a. Used instead of `DefaultHasher` because identities are persisted and must not
+/// change between Rust releases.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+/// What changed on disk between two loads of the same trace file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ReloadStatus {
+    Unchanged,
+    /// Entries were appended; everything visible before is still valid.
+    Grew,
+    /// The file shrank or was rewritten; previously fetched data (incl. the dictionary) is stale.
+    Rewritten,
+}
+
+/// Where a jump to a clk landed: the first entry at or after the requested clk,
+/// or the last entry if the clk lies past the end of the trace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClkJump {
+    pub index: u64,
+    pub actual_clk: i64,
+    pub exact: bool,
 }
 
 impl TraceLoader {
+    /// Opens and maps a trace file.
+    ///
+    /// Safety invariant of the mapping: the file must not be truncated or modified in place while it
+    /// is mapped, otherwise reads through the map are undefined behaviour (or SIGBUS on truncation).
+    /// Writers are expected to only append or to replace the file via rename, after which
+    /// `reload` picks up the new contents.
     pub fn new(path: PathBuf) -> Result<Self, std::io::Error> {
-        let file = File::open(path)?;
+        let file = File::open(&path)?;
+
+        // Mapping an empty file fails with an OS-specific error, so reject it with a readable one.
+        if file.metadata()?.len() == 0 {
+            return Err(header::HeaderError::FileTooShort.into());
+        }
+
         let mmap = unsafe { Mmap::map(&file)? };
-        let header = header::parse(&mmap)?;
 
-        Ok(Self { mmap, header })
+        // Compressed traces are expanded into memory in the regular little-endian layout.
+        let (endianness, data) = if compressed::is_compressed(&mmap) {
+            (
+                Endianness::Little,
+                TraceData::Owned(compressed::decode(&mmap)?),
+            )
+        } else {
+            let endianness = header::detect_endianness(&mmap)?;
+            let data = match endianness {
+                Endianness::Little => TraceData::Mapped(mmap),
+                Endianness::Big => TraceData::Owned(Self::swap_to_little_endian(&mmap)?),
+            };
+            (endianness, data)
+        };
+        let header = header::parse(&data)?;
+
+        let mut loader = Self {
+            path,
+            data,
+            header,
+            endianness,
+            sentinels: Vec::new(),
+            identity: 0,
+            window: 0..header.num_entries(),
+            clk_range: None,
+        };
+        loader.identity = loader.compute_identity();
+
+        Ok(loader)
+    }
+
+    /// Opens a trace but only presents the entries with `start_clk <= clk <= end_clk`.
+    /// Every read method, `num_entries` and `header` operate relative to that slice; the dictionary
+    /// is still loaded in full.
+    pub fn new_ranged(path: PathBuf, start_clk: i64, end_clk: i64) -> Result<Self, std::io::Error> {
+        let mut loader = Self::new(path)?;
+        loader.restrict(start_clk, end_clk)?;
+        Ok(loader)
+    }
+
+    fn restrict(&mut self, start_clk: i64, end_clk: i64) -> Result<(), std::io::Error> {
+        let range = self.index_range_for_time(start_clk, end_clk)?;
+        self.window = self.window.start + range.start..self.window.start + range.end;
+        self.clk_range = Some((start_clk, end_clk));
+        self.identity = self.compute_identity();
+        Ok(())
+    }
+
+    /// A fresh loader of the same file and clk range, without sentinels.
+    fn reopen(&self) -> Result<Self, std::io::Error> {
+        let mut loader = Self::new(self.path.clone())?;
+        if let Some((start_clk, end_clk)) = self.clk_range {
+            loader.restrict(start_clk, end_clk)?;
+        }
+        Ok(loader)
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Size of the trace bytes held by this loader, mapped or in memory.
+    pub fn data_len(&self) -> u64 {
+        self.data.len() as u64
+    }
+
+    /// Whether the trace is memory mapped rather than copied into memory (big-endian, compressed
+    /// or sentinel-normalized traces).
+    pub fn is_mapped(&self) -> bool {
+        matches!(self.data, TraceData::Mapped(_))
+    }
+
+    /// The header exactly as stored in the file, i.e. before any byte swapping. Read from disk
+    /// rather than the mapping since big-endian traces are swapped in memory. Compressed traces
+    /// have no such header on disk, so for them this is the header they decode to.
+    pub fn raw_header_bytes(&self) -> Result<Vec<u8>, std::io::Error> {
+        let size = std::mem::size_of::<Header>();
+        let mut bytes = Vec::with_capacity(size);
+        File::open(&self.path)?
+            .take(size as u64)
+            .read_to_end(&mut bytes)?;
+
+        if compressed::is_compressed(&bytes) {
+            return Ok(self.header.as_bytes().to_vec());
+        }
+        if bytes.len() < size {
+            return Err(header::HeaderError::FileTooShort.into());
+        }
+        Ok(bytes)
+    }
+
+    /// Number of entries visible through this loader.
+    pub fn num_entries(&self) -> u64 {
+        self.window.end - self.window.start
+    }
+
+    /// Byte offset of a visible entry in the file, `None` if out of bounds.
+    pub fn entry_offset(&self, index: u64) -> Option<u64> {
+        if index >= self.num_entries() {
+            return None;
+        }
+        self.header.offset_for_entry(self.window.start + index)
+    }
+
+    /// Hashes the header, a sample of entries and the dictionary. Reading the whole file would be
+    /// too slow for huge traces; the header alone changes with any append, and the sample plus
+    /// dictionary catch rewrites that keep the size.
+    fn compute_identity(&self) -> u64 {
+        let mut hasher = Fnv1a::new();
+        hasher.write(self.header.as_bytes());
+
+        let n = self.num_entries();
+        let samples = (0..IDENTITY_SAMPLES.min(n))
+            .map(|i| (i as u128 * n as u128 / IDENTITY_SAMPLES.min(n) as u128) as u64)
+            .chain(n.checked_sub(1));
+        for index in samples {
+            if let Ok(entry) = self.load_entry_slice(index, 1) {
+                hasher.write(entry[0].as_bytes());
+            }
+        }
+
+        let dict_start = usize::try_from(self.header.dict_offset()).unwrap_or(usize::MAX);
+        if let Some(dict) = self.data.get(dict_start..) {
+            hasher.write(&dict[..dict.len().min(IDENTITY_DICT_BYTES)]);
+        }
+
+        hasher.0
+    }
+
+    /// Content-based key of the trace: copies of the same file share it, modified traces don't.
+    /// Computed from the file as loaded, before sentinel normalization.
+    pub fn identity(&self) -> u64 {
+        self.identity
     }
 
-    pub fn header(&self) -> &Header {
-        &self.header
+    /// Normalizes every entry with `Entry::normalized`, so all reads see -1 for "not applicable".
+    /// This copies the trace into memory once, so it is only done when sentinels are configured.
+    /// Normalization is lossy, so this always starts over from a fresh read of the file.
+    pub fn set_sentinels(&mut self, sentinels: &[i64]) -> Result<(), std::io::Error> {
+        if sentinels == self.sentinels.as_slice() {
+            return Ok(());
+        }
+
+        let mut loader = self.reopen()?;
+
+        if !sentinels.is_empty() {
+            let mut bytes = loader.data.to_vec();
+            let num_entries = loader.header.num_entries().try_into().unwrap_or(usize::MAX);
+
+            for chunk in bytes[std::mem::size_of::<Header>()..]
+                .chunks_exact_mut(std::mem::size_of::<Entry>())
+                .take(num_entries)
+            {
+                let entry = Entry::read_from_bytes(chunk).map_err(|_| {
+                    std::io::Error::new(std::io::ErrorKind::InvalidData, "Cast failed")
+                })?;
+                let normalized = entry.normalized(sentinels);
+                chunk.copy_from_slice(normalized.as_bytes());
+            }
+
+            loader.data = TraceData::Owned(bytes);
+        }
+
+        loader.sentinels = sentinels.to_vec();
+        *self = loader;
+        Ok(())
+    }
+
+    /// Copies a big-endian trace and swaps the header and every entry into the little-endian layout.
+    /// The dictionary consists of single bytes and needs no swapping.
+    fn swap_to_little_endian(raw: &[u8]) -> Result<Vec<u8>, std::io::Error> {
+        let mut bytes = raw.to_vec();
+        header::swap_to_little_endian(&mut bytes)?;
+
+        let header_size = std::mem::size_of::<Header>();
+        let entry_size = std::mem::size_of::<Entry>();
+        let num_entries = header::parse(&bytes)?.num_entries();
+
+        for chunk in bytes[header_size..]
+            .chunks_exact_mut(entry_size)
+            .take(num_entries.try_into().unwrap_or(usize::MAX))
+        {
+            entry::swap_to_little_endian(chunk);
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn endianness(&self) -> Endianness {
+        self.endianness
+    }
+
+    /// Re-maps the file and re-reads the header so entries appended since the last load become visible.
+    /// The new mapping is fully parsed before it replaces the current one, so on error the loader is left untouched.
+    pub fn reload(&mut self) -> Result<ReloadStatus, std::io::Error> {
+        let mut reloaded = self.reopen()?;
+        reloaded.set_sentinels(&self.sentinels)?;
+
+        let old_entries = self.num_entries();
+        let new_entries = reloaded.num_entries();
+
+        // Comparing the whole prefix would read the entire trace on every poll, so we only check the
+        // first and last entry that were visible before. That's enough to tell an append from a rewrite.
+        let old_boundary = self.boundary_bytes(old_entries);
+        let same_prefix = new_entries >= old_entries
+            && reloaded.header.num_commands() == self.header.num_commands()
+            && old_boundary.is_some()
+            && old_boundary == reloaded.boundary_bytes(old_entries);
+
+        let status = if !same_prefix {
+            ReloadStatus::Rewritten
+        } else if new_entries > old_entries {
+            ReloadStatus::Grew
+        } else if self.data.len() == reloaded.data.len() {
+            ReloadStatus::Unchanged
+        } else {
+            ReloadStatus::Rewritten
+        };
+
+        *self = reloaded;
+        Ok(status)
+    }
+
+    /// Raw bytes of the first and last of the first `count` entries, if they are in bounds.
+    fn boundary_bytes(&self, count: u64) -> Option<Vec<u8>> {
+        if count == 0 {
+            return Some(Vec::new());
+        }
+        let first = self.load_entry_slice(0, 1).ok()?;
+        let last = self.load_entry_slice(count - 1, 1).ok()?;
+        Some([first[0].as_bytes(), last[0].as_bytes()].concat())
+    }
+
+    /// The file's header, with `num_entries` reduced to the visible entries of a ranged loader.
+    pub fn header(&self) -> Header {
+        let mut header = self.header;
+        header.num_entries = self.num_entries().into();
+        header
     }
 
     pub fn load_dictionary(&self) -> Result<Dictionary, std::io::Error> {
         dictionary::parse(
-            &self.mmap,
+            &self.data,
+            self.header.dict_offset(),
+            self.header.num_commands,
+        )
+        .map_err(Into::into)
+    }
+
+    /// Loads the dictionary, recovering command names that aren't valid UTF-8.
+    /// Returns the ids of the recovered names alongside the dictionary.
+    pub fn load_dictionary_lossy(&self) -> Result<(Dictionary, Vec<u8>), std::io::Error> {
+        dictionary::parse_lossy(
+            &self.data,
             self.header.dict_offset(),
             self.header.num_commands,
         )
@@ -64,22 +423,68 @@ impl TraceLoader {
     }
 
     pub fn load_entry(&self, index: u64) -> Result<Entry, std::io::Error> {
-        entry::parse(&self.mmap, &self.header, index).map_err(Into::into)
+        if index >= self.num_entries() {
+            return Err(entry::EntryError::InvalidIndex.into());
+        }
+        entry::parse(&self.data, &self.header, self.window.start + index).map_err(Into::into)
+    }
+
+    /// Hints the OS to start reading the pages of an upcoming window into the page cache.
+    /// Entry slices are served straight from the mapping, so the page cache is the cache that makes
+    /// the following `load_entry_slice` fast. The hint returns immediately; the kernel reads ahead
+    /// in the background. Windows past the end are clamped; in-memory traces need no prefetching.
+    pub fn prefetch(&self, start: u64, count: usize) -> Result<(), std::io::Error> {
+        let count = (count as u64).min(self.num_entries().saturating_sub(start));
+        if count == 0 {
+            return Ok(());
+        }
+
+        #[cfg(unix)]
+        if let TraceData::Mapped(mmap) = &self.data {
+            let slice = self.load_entry_slice(start, count as usize)?;
+            let offset = slice.as_ptr() as usize - mmap.as_ptr() as usize;
+            mmap.advise_range(
+                memmap2::Advice::WillNeed,
+                offset,
+                std::mem::size_of_val(slice),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// All entries of the trace as one zero-copy slice.
+    pub fn entries(&self) -> Result<&[Entry], std::io::Error> {
+        self.load_entry_slice(0, self.num_entries() as usize)
     }
 
     pub fn load_entry_slice(&self, start: u64, count: usize) -> Result<&[Entry], std::io::Error> {
-        let start_offset =
-            std::mem::size_of::<Header>() + (start as usize * std::mem::size_of::<Entry>());
-        let end_offset = start_offset + (count * std::mem::size_of::<Entry>());
+        if count == 0 {
+            return Ok(&[]);
+        }
 
-        if end_offset > self.mmap.len() {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::UnexpectedEof,
-                "Out of bounds",
-            ));
+        let out_of_bounds =
+            || std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "Out of bounds");
+
+        let to_offset = |index: u64| {
+            self.entry_offset(index)
+                .and_then(|offset| usize::try_from(offset).ok())
+                .ok_or_else(out_of_bounds)
+        };
+
+        let last = start
+            .checked_add(count as u64 - 1)
+            .ok_or_else(out_of_bounds)?;
+        let start_offset = to_offset(start)?;
+        let end_offset = to_offset(last)?
+            .checked_add(std::mem::size_of::<Entry>())
+            .ok_or_else(out_of_bounds)?;
+
+        if end_offset > self.data.len() {
+            return Err(out_of_bounds());
         }
 
-        let slice = &self.mmap[start_offset..end_offset];
+        let slice = &self.data[start_offset..end_offset];
 
         let (entries, _) = Ref::<&[u8], [Entry]>::from_prefix_with_elems(slice, count)
             .map_err(|_| std::io::Error::new(std::io::ErrorKind::InvalidData, "Cast failed"))?;
@@ -87,11 +492,169 @@ impl TraceLoader {
         Ok(Ref::into_ref(entries))
     }
 
+    pub fn command_runs(
+        &self,
+        start: u64,
+        count: usize,
+        split_by_bank: bool,
+    ) -> Result<Vec<Run>, std::io::Error> {
+        let entries = self.load_entry_slice(start, count)?;
+        let mut runs = runs::coalesce(entries, split_by_bank);
+        for run in &mut runs {
+            run.start_index += start;
+        }
+        Ok(runs)
+    }
+
+    /// Picks `k` entries spread evenly over the whole trace, at indices `i * num_entries / k`.
+    /// Traces with fewer than `k` entries are returned in full.
+    pub fn sample_entries(&self, k: u64) -> Result<Vec<Entry>, std::io::Error> {
+        let entries = self.entries()?;
+        let n = entries.len() as u64;
+
+        if n <= k {
+            return Ok(entries.to_vec());
+        }
+
+        Ok((0..k)
+            .map(|i| entries[(i as u128 * n as u128 / k as u128) as usize])
+            .collect())
+    }
+
+    /// Indices of up to `per_command` entries of every command id, evenly spaced among that
+    /// command's occurrences and in file order. Unlike `sample_entries` this keeps rare commands
+    /// in the sample; commands with fewer occurrences contribute all of them. Two passes: one
+    /// counting the occurrences, one picking the entries at the spaced ranks.
+    pub fn sample_stratified(&self, per_command: u64) -> Result<Vec<u64>, std::io::Error> {
+        let entries = self.entries()?;
+
+        let mut totals = [0u64; 256];
+        for entry in entries {
+            totals[entry.cmd_id as usize] += 1;
+        }
+
+        let mut seen = [0u64; 256];
+        let mut picked = [0u64; 256];
+        let mut indices = Vec::new();
+
+        for (index, entry) in entries.iter().enumerate() {
+            let cmd = entry.cmd_id as usize;
+            let (n, k) = (totals[cmd], per_command.min(totals[cmd]));
+
+            // The rank of the next pick among this command's occurrences, as in `sample_entries`.
+            if picked[cmd] < k && seen[cmd] == (picked[cmd] as u128 * n as u128 / k as u128) as u64
+            {
+                indices.push(index as u64);
+                picked[cmd] += 1;
+            }
+            seen[cmd] += 1;
+        }
+
+        Ok(indices)
+    }
+
+    /// The command ids that occur at least once. Stops scanning once every dictionary id was seen.
+    pub fn used_command_ids(&self) -> Result<BTreeSet<u8>, std::io::Error> {
+        let num_commands = self.header.num_commands() as usize;
+        let mut used = BTreeSet::new();
+
+        for entry in self.entries()? {
+            used.insert(entry.cmd_id);
+            if used.len() >= num_commands {
+                break;
+            }
+        }
+
+        Ok(used)
+    }
+
+    /// Indices of entries that are byte-identical to their predecessor (same clk, address, command
+    /// and flags), i.e. the second index of every duplicate pair. A run of three equal entries
+    /// reports the last two.
+    pub fn find_duplicate_entries(&self) -> Result<Vec<u64>, std::io::Error> {
+        Ok(self
+            .entries()?
+            .windows(2)
+            .enumerate()
+            .filter(|(_, pair)| pair[0].as_bytes() == pair[1].as_bytes())
+            .map(|(i, _)| i as u64 + 1)
+            .collect())
+    }
+
+    /// Number of entries matching the filter, without collecting their indices. A single pass
+    /// under the caller's lock: the chunked scan of `search` exists to release the lock between
+    /// chunks of a background search, which a synchronous count doesn't need.
+    pub fn count_matching(&self, filter: &EntryFilter) -> Result<u64, std::io::Error> {
+        Ok(self
+            .entries()?
+            .iter()
+            .filter(|entry| entry.matches(filter))
+            .count() as u64)
+    }
+
+    /// Returns the indices of up to `limit` entries matching the filter, in file order.
+    pub fn search(&self, filter: &EntryFilter, limit: usize) -> Result<Vec<u64>, std::io::Error> {
+        Ok(self
+            .entries()?
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.matches(filter))
+            .map(|(i, _)| i as u64)
+            .take(limit)
+            .collect())
+    }
+
+    /// Index of the `n`th (1-based) occurrence of `cmd_id` among the entries matching `filter`,
+    /// `None` if there are fewer than `n` (or `n` is 0). Stops scanning at the match.
+    pub fn nth_command(
+        &self,
+        cmd_id: u8,
+        n: u64,
+        filter: &EntryFilter,
+    ) -> Result<Option<u64>, std::io::Error> {
+        let Some(skip) = n.checked_sub(1) else {
+            return Ok(None);
+        };
+
+        Ok(self
+            .entries()?
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.cmd_id == cmd_id && entry.matches(filter))
+            .nth(usize::try_from(skip).unwrap_or(usize::MAX))
+            .map(|(i, _)| i as u64))
+    }
+
+    /// Index of the entry matching `filter` whose clk is closest to `target_clk`, preferring the
+    /// earlier one on a tie. Starts from `find_index_for_time` and scans outward in both directions
+    /// for the first match, so the cost depends on how sparse the matches are around the target.
+    pub fn nearest_matching(
+        &self,
+        target_clk: i64,
+        filter: &EntryFilter,
+    ) -> Result<Option<u64>, std::io::Error> {
+        let entries = self.entries()?;
+        let pivot = self.find_index_for_time(target_clk)? as usize;
+
+        let after = entries[pivot..]
+            .iter()
+            .position(|e| e.matches(filter))
+            .map(|i| pivot + i);
+        let before = entries[..pivot].iter().rposition(|e| e.matches(filter));
+
+        let distance = |i: usize| entries[i].clk.get().abs_diff(target_clk);
+        Ok(match (before, after) {
+            (Some(b), Some(a)) if distance(a) < distance(b) => Some(a as u64),
+            (Some(b), _) => Some(b as u64),
+            (None, a) => a.map(|a| a as u64),
+        })
+    }
+
     // Since the clk's aren't spaced evenly, we need to rely on index lookup and yet, the whole UI only makes sense in terms of time.
     // So we look for an entry with a given clk using binary search and obtain the index.
     // TODO(ziad): This is horrible. There's got to be a better way to do this.
     pub fn find_index_for_time(&self, target_clk: i64) -> Result<u64, std::io::Error> {
-        let num_entries = self.header.num_entries();
+        let num_entries = self.num_entries();
         if num_entries == 0 {
             return Ok(0);
         }
@@ -116,4 +679,482 @@ impl TraceLoader {
         }
         Ok(result)
     }
+
+    /// Index range of the entries with `start_clk <= clk <= end_clk`. Empty if `start_clk > end_clk`.
+    pub fn index_range_for_time(
+        &self,
+        start_clk: i64,
+        end_clk: i64,
+    ) -> Result<Range<u64>, std::io::Error> {
+        if start_clk > end_clk {
+            return Ok(0..0);
+        }
+
+        let start = self.find_index_for_time(start_clk)?;
+        let end = match end_clk.checked_add(1) {
+            Some(after_end) => self.find_index_for_time(after_end)?,
+            None => self.num_entries(),
+        };
+
+        Ok(start..end.max(start))
+    }
+
+    /// Like `find_index_for_time`, but also reports the clk of the entry it landed on.
+    /// Returns `None` for an empty trace.
+    pub fn jump_to_clk(&self, target_clk: i64) -> Result<Option<ClkJump>, std::io::Error> {
+        let num_entries = self.num_entries();
+        if num_entries == 0 {
+            return Ok(None);
+        }
+
+        let index = self.find_index_for_time(target_clk)?.min(num_entries - 1);
+        let actual_clk = self.load_entry(index)?.clk.get();
+
+        Ok(Some(ClkJump {
+            index,
+            actual_clk,
+            exact: actual_clk == target_clk,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::writer::{self, tests::scratch_path};
+
+    const COMMANDS: [&str; 4] = ["ACT", "RD", "WR", "PRE"];
+
+    /// Writes `entries` as the trace `name` and opens it.
+    fn load(name: &str, entries: &[Entry]) -> (PathBuf, TraceLoader) {
+        let path = scratch_path(name);
+        writer::write_trace(&path, 1, entries, &Dictionary::from_names(&COMMANDS)).unwrap();
+        let loader = TraceLoader::new(path.clone()).unwrap();
+        (path, loader)
+    }
+
+    #[test]
+    fn counts_match_the_filter_without_a_limit() {
+        let entries: Vec<Entry> = (0..100)
+            .map(|clk| Entry::new(clk, [0, 0, 0, clk % 4, 7, -1], (clk % 3) as u8))
+            .collect();
+        let (path, loader) = load("count-matching.bin", &entries);
+
+        let rd_to_bank_3 = EntryFilter {
+            cmd_id: Some(1),
+            bank: Some(3),
+            ..EntryFilter::default()
+        };
+        let expected = entries.iter().filter(|e| e.matches(&rd_to_bank_3)).count() as u64;
+        assert_eq!(loader.count_matching(&rd_to_bank_3).unwrap(), expected);
+        assert_eq!(expected, 8);
+        assert_eq!(loader.search(&rd_to_bank_3, 2).unwrap(), [7, 19]);
+
+        let none = EntryFilter {
+            bank: Some(9),
+            ..EntryFilter::default()
+        };
+        assert_eq!(loader.count_matching(&none).unwrap(), 0);
+        assert_eq!(loader.count_matching(&EntryFilter::default()).unwrap(), 100);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn reload_tells_appends_from_rewrites() {
+        let first = [Entry::new(0, [0; 6], 0), Entry::new(4, [0; 6], 1)];
+        let (path, mut loader) = load("reload.bin", &first);
+        assert_eq!(loader.reload().unwrap(), ReloadStatus::Unchanged);
+
+        let mut appender = writer::TraceAppender::open(path.clone()).unwrap();
+        appender.append(&[Entry::new(9, [0; 6], 3)]).unwrap();
+        assert_eq!(loader.reload().unwrap(), ReloadStatus::Grew);
+        assert_eq!(loader.num_entries(), 3);
+        assert_eq!(loader.entries().unwrap()[2].clk.get(), 9);
+
+        // Same length but a different first entry is a rewrite, as is a shorter file.
+        let rewritten = [Entry::new(1, [0; 6], 0), Entry::new(4, [0; 6], 1)];
+        writer::write_trace(&path, 1, &rewritten, &Dictionary::from_names(&COMMANDS)).unwrap();
+        assert_eq!(loader.reload().unwrap(), ReloadStatus::Rewritten);
+        assert_eq!(loader.entries().unwrap(), &rewritten);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn jump_to_clk_reports_where_it_landed() {
+        let entries: Vec<Entry> = [10, 20, 20, 45]
+            .iter()
+            .map(|&clk| Entry::new(clk, [1, 0, 2, 0, 300, 8], 1))
+            .collect();
+        let (path, loader) = load("jump-to-clk.bin", &entries);
+
+        let exact = loader.jump_to_clk(20).unwrap().unwrap();
+        assert_eq!((exact.index, exact.actual_clk, exact.exact), (1, 20, true));
+
+        let between = loader.jump_to_clk(21).unwrap().unwrap();
+        assert_eq!(
+            (between.index, between.actual_clk, between.exact),
+            (3, 45, false)
+        );
+
+        let past_end = loader.jump_to_clk(1_000).unwrap().unwrap();
+        assert_eq!(
+            (past_end.index, past_end.actual_clk, past_end.exact),
+            (3, 45, false)
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn big_endian_traces_decode_like_little_endian_ones() {
+        let entries = [
+            Entry::new(0x0102_0304, [1, 2, 0x0304, 5, 0x0a0b_0c0d, 9], 2),
+            Entry::new(-7, [0, 1, 0, 3, 17, -1], 3),
+        ];
+        let (path, little) = load("big-endian.bin", &entries);
+
+        // Byte-swap the file field by field, as a big-endian simulator build would write it.
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[..5].reverse();
+        bytes[8..16].reverse();
+        bytes[16..24].reverse();
+        for raw in bytes[24..24 + 2 * 32].chunks_exact_mut(32) {
+            entry::swap_to_little_endian(raw);
+        }
+        std::fs::write(&path, &bytes).unwrap();
+
+        let big = TraceLoader::new(path.clone()).unwrap();
+        assert_eq!(big.endianness(), Endianness::Big);
+        assert_eq!(little.endianness(), Endianness::Little);
+        assert_eq!(big.header().num_entries(), 2);
+        assert_eq!(big.entries().unwrap(), &entries);
+        assert_eq!(
+            big.load_dictionary().unwrap(),
+            little.load_dictionary().unwrap()
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn samples_spread_over_the_whole_trace() {
+        let entries: Vec<Entry> = (0..10)
+            .map(|i| Entry::new(i * 3, [0, 1, 1, 2, i, 0], 0))
+            .collect();
+        let (path, loader) = load("sample-entries.bin", &entries);
+
+        let clks = |k| -> Vec<i64> {
+            loader
+                .sample_entries(k)
+                .unwrap()
+                .iter()
+                .map(|e| e.clk.get())
+                .collect()
+        };
+        // Indices 0, 3, 6 for k = 3 and 0, 2, 5, 7 for k = 4.
+        assert_eq!(clks(3), [0, 9, 18]);
+        assert_eq!(clks(4), [0, 6, 15, 21]);
+        assert!(clks(0).is_empty());
+
+        // Asking for more entries than the trace holds returns all of them.
+        assert_eq!(loader.sample_entries(25).unwrap(), entries);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn empty_files_are_too_short_to_map() {
+        let path = scratch_path("empty.bin");
+        std::fs::write(&path, []).unwrap();
+
+        let err = TraceLoader::new(path.clone()).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert_eq!(err.to_string(), "file too short");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn used_command_ids_skip_unused_dictionary_entries() {
+        let entries = [
+            Entry::new(2, [0, 0, 1, 1, 5, 0], 3),
+            Entry::new(3, [0, 0, 1, 1, 5, 0], 1),
+            Entry::new(8, [0, 0, 1, 1, 5, 0], 3),
+        ];
+        let (path, loader) = load("used-commands.bin", &entries);
+        assert_eq!(loader.used_command_ids().unwrap(), BTreeSet::from([1, 3]));
+        std::fs::remove_file(&path).unwrap();
+
+        // Every command in use ends the scan early with the full set.
+        let all: Vec<Entry> = (0..40)
+            .map(|clk| Entry::new(clk, [0; 6], (clk % 4) as u8))
+            .collect();
+        let (path, loader) = load("all-commands-used.bin", &all);
+        assert_eq!(
+            loader.used_command_ids().unwrap(),
+            BTreeSet::from([0, 1, 2, 3])
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn sentinels_apply_to_every_read_and_can_be_undone() {
+        let entries = [
+            Entry::new(1, [0, 0, 4, 0, 999, 0], 0),
+            Entry::new(6, [0, 0, 4, 2, 999, 16], 1),
+        ];
+        let (path, mut loader) = load("sentinels.bin", &entries);
+
+        loader.set_sentinels(&[0, 999]).unwrap();
+        assert_eq!(
+            loader.entries().unwrap(),
+            [
+                Entry::new(1, [-1, -1, 4, -1, -1, -1], 0),
+                Entry::new(6, [-1, -1, 4, 2, -1, 16], 1),
+            ]
+        );
+        assert_eq!(loader.load_entry(1).unwrap().row.get(), -1);
+
+        // Clearing them re-reads the file rather than trying to undo the lossy mapping.
+        loader.set_sentinels(&[]).unwrap();
+        assert_eq!(loader.entries().unwrap(), &entries);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn prefetch_clamps_windows_to_the_trace() {
+        let entries: Vec<Entry> = (0..64)
+            .map(|i| Entry::new(i * 2, [0, 0, 0, i % 8, 3, -1], 2))
+            .collect();
+        let (path, loader) = load("prefetch.bin", &entries);
+
+        loader.prefetch(16, 32).unwrap();
+        assert_eq!(loader.load_entry_slice(16, 32).unwrap(), &entries[16..48]);
+
+        // Windows running off the end, starting past it, or empty are accepted as no-ops.
+        loader.prefetch(60, 1_000).unwrap();
+        loader.prefetch(64, 8).unwrap();
+        loader.prefetch(u64::MAX, usize::MAX).unwrap();
+        loader.prefetch(0, 0).unwrap();
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn identity_follows_content_not_path() {
+        let entries: Vec<Entry> = (0..500)
+            .map(|i| Entry::new(i, [0, i % 2, 1, 0, i / 7, 4], (i % 4) as u8))
+            .collect();
+        let (original, a) = load("identity-a.bin", &entries);
+        let (copy, b) = load("identity-b.bin", &entries);
+        assert_eq!(a.identity(), b.identity());
+
+        let mut changed = entries.clone();
+        changed[499].column = 5.into();
+        let (modified, c) = load("identity-c.bin", &changed);
+        assert_ne!(a.identity(), c.identity());
+
+        // Normalization happens after the identity is taken.
+        let mut normalized = TraceLoader::new(original.clone()).unwrap();
+        normalized.set_sentinels(&[0]).unwrap();
+        assert_eq!(normalized.identity(), a.identity());
+
+        for path in [original, copy, modified] {
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+
+    #[test]
+    fn time_ranges_are_inclusive_on_both_ends() {
+        let clks = [-4, 0, 5, 5, 9, 12, 12, 20];
+        let entries: Vec<Entry> = clks
+            .iter()
+            .map(|&clk| Entry::new(clk, [0, 0, 3, 1, 2, 8], 0))
+            .collect();
+        let (path, loader) = load("time-range.bin", &entries);
+
+        let clks_in = |start_clk, end_clk| -> Vec<i64> {
+            let range = loader.index_range_for_time(start_clk, end_clk).unwrap();
+            loader
+                .load_entry_slice(range.start, (range.end - range.start) as usize)
+                .unwrap()
+                .iter()
+                .map(|e| e.clk.get())
+                .collect()
+        };
+        assert_eq!(clks_in(5, 12), [5, 5, 9, 12, 12]);
+        assert_eq!(clks_in(6, 11), [9]);
+        assert_eq!(clks_in(i64::MIN, i64::MAX), clks);
+        assert!(clks_in(13, 19).is_empty());
+        assert!(clks_in(12, 5).is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn ranged_loaders_only_see_their_slice() {
+        let entries: Vec<Entry> = (0..20)
+            .map(|i| Entry::new(i * 10, [0, 0, i % 2, 0, 6, 0], (i % 4) as u8))
+            .collect();
+        let (path, _) = load("ranged.bin", &entries);
+
+        let ranged = TraceLoader::new_ranged(path.clone(), 45, 100).unwrap();
+        assert_eq!(ranged.num_entries(), 6);
+        assert_eq!(ranged.header().num_entries(), 6);
+        assert_eq!(ranged.entries().unwrap(), &entries[5..11]);
+        assert_eq!(ranged.load_entry(0).unwrap().clk.get(), 50);
+        assert!(ranged.load_entry(6).is_err());
+        assert_eq!(ranged.find_index_for_time(70).unwrap(), 2);
+        assert_eq!(ranged.load_dictionary().unwrap().commands.len(), 4);
+
+        let empty = TraceLoader::new_ranged(path.clone(), 41, 49).unwrap();
+        assert_eq!(empty.num_entries(), 0);
+        assert!(empty.entries().unwrap().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn duplicates_are_reported_at_their_second_index() {
+        let rd = Entry::new(4, [0, 0, 1, 2, 3, 8], 1);
+        let mut flagged = rd;
+        flagged.reserved[0] = 1;
+        let entries = [
+            Entry::new(0, [0, 0, 1, 2, 3, -1], 0),
+            rd,
+            rd,
+            rd,
+            // Only the flags differ, so this is a distinct event.
+            flagged,
+            Entry::new(4, [0, 0, 1, 2, 3, 8], 2),
+            Entry::new(4, [0, 0, 1, 2, 3, 8], 2),
+        ];
+        let (path, loader) = load("duplicates.bin", &entries);
+
+        assert_eq!(loader.find_duplicate_entries().unwrap(), [2, 3, 6]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn raw_header_bytes_come_from_disk() {
+        let entries = [Entry::new(12, [3, 1, 0, 0, 0, 0], 2)];
+        let (path, loader) = load("raw-header.bin", &entries);
+
+        let file = std::fs::read(&path).unwrap();
+        assert_eq!(loader.raw_header_bytes().unwrap(), &file[..24]);
+        assert_eq!(
+            loader.raw_header_bytes().unwrap(),
+            loader.header().as_bytes()
+        );
+
+        // Replaced by a file too short to hold a header; the loaded mapping stays valid.
+        let short = scratch_path("raw-header-short.bin");
+        std::fs::write(&short, &file[..10]).unwrap();
+        std::fs::rename(&short, &path).unwrap();
+        let err = loader.raw_header_bytes().unwrap_err();
+        assert_eq!(err.to_string(), "file too short");
+        assert_eq!(loader.load_entry(0).unwrap(), entries[0]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn nearest_matching_entry_in_a_bank() {
+        let entries = [
+            Entry::new(0, [0, 0, 0, 1, 2, 0], 1),
+            Entry::new(10, [0, 0, 0, 3, 2, 0], 1),
+            Entry::new(20, [0, 0, 0, 1, 2, 0], 1),
+            Entry::new(30, [0, 0, 0, 3, 2, 0], 1),
+            Entry::new(60, [0, 0, 0, 1, 2, 0], 1),
+        ];
+        let (path, loader) = load("nearest.bin", &entries);
+        let bank = |bank| EntryFilter {
+            bank: Some(bank),
+            ..EntryFilter::default()
+        };
+        let nearest = |clk, filter| loader.nearest_matching(clk, &filter).unwrap();
+
+        assert_eq!(nearest(22, bank(1)), Some(2));
+        assert_eq!(nearest(45, bank(1)), Some(4));
+        // 40 is as close to 20 as to 60; the earlier one wins.
+        assert_eq!(nearest(40, bank(1)), Some(2));
+        assert_eq!(nearest(-50, bank(3)), Some(1));
+        assert_eq!(nearest(1_000, bank(3)), Some(3));
+        assert_eq!(nearest(20, bank(7)), None);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn stratified_samples_keep_rare_commands() {
+        let entries: Vec<Entry> = (0..11)
+            .map(|i| Entry::new(i, [0, 0, 0, 0, 1, i], if i == 5 { 3 } else { 1 }))
+            .collect();
+        let (path, loader) = load("stratified.bin", &entries);
+
+        // RD occurs at 0-4 and 6-10; ranks 0, 3 and 6 of those are indices 0, 3 and 7.
+        assert_eq!(loader.sample_stratified(3).unwrap(), [0, 3, 5, 7]);
+        assert_eq!(loader.sample_stratified(50).unwrap().len(), 11);
+        assert!(loader.sample_stratified(0).unwrap().is_empty());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn nth_command_counts_from_one() {
+        let entries: Vec<Entry> = [0, 3, 0, 3, 3, 1]
+            .iter()
+            .enumerate()
+            .map(|(i, &cmd_id)| Entry::new(i as i64 * 5, [0, 0, 0, i as i64 % 2, 0, 0], cmd_id))
+            .collect();
+        let (path, loader) = load("nth-command.bin", &entries);
+        let any = EntryFilter::default();
+
+        assert_eq!(loader.nth_command(3, 1, &any).unwrap(), Some(1));
+        assert_eq!(loader.nth_command(3, 3, &any).unwrap(), Some(4));
+        assert_eq!(loader.nth_command(3, 4, &any).unwrap(), None);
+        assert_eq!(loader.nth_command(3, 0, &any).unwrap(), None);
+
+        let bank_1 = EntryFilter {
+            bank: Some(1),
+            ..EntryFilter::default()
+        };
+        assert_eq!(loader.nth_command(3, 2, &bank_1).unwrap(), Some(3));
+        assert_eq!(loader.nth_command(3, 3, &bank_1).unwrap(), None);
+        assert_eq!(loader.nth_command(1, 1, &bank_1).unwrap(), Some(5));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn only_files_starting_with_a_supported_header_are_traces() {
+        let entries = [Entry::new(0, [0, 0, 0, 0, 1, -1], 0)];
+        let (path, loader) = load("is-trace.bin", &entries);
+        drop(loader);
+        assert!(is_trace_file(&path));
+
+        let bytes = std::fs::read(&path).unwrap();
+        let other = scratch_path("is-trace-other.bin");
+        let check = |contents: &[u8]| {
+            std::fs::write(&other, contents).unwrap();
+            is_trace_file(&other)
+        };
+        assert!(!check(b"%PDF-1.7 definitely not a memory trace"));
+        assert!(!check(&bytes[..4]));
+        assert!(!check(&[]));
+        let mut future = bytes.clone();
+        future[5] = 99;
+        assert!(!check(&future));
+
+        // Unreadable paths are not traces either.
+        assert!(!is_trace_file(&scratch_path("is-trace-missing.bin")));
+        assert!(!is_trace_file(&std::env::temp_dir()));
+
+        std::fs::remove_file(&other).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
 }
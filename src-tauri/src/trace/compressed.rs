@@ -0,0 +1,287 @@
+/// The file implements a compact archival variant of the trace format. Clks are delta-encoded,
+/// since they are monotonic, and every integer is stored as a LEB128 varint, which brings the
+/// fixed 32-byte entries down to less than half for typical traces.
+///
+///  Layout:
+///  +--------------+------+---------------------------------------------+
+///  |     Name     | Size |                 Description                 |
+///  +--------------+------+---------------------------------------------+
+///  | magic        | 5B   | "RAMZ\0" (null-terminated)                  |
+///  | version      | 1B   | Version of the uncompressed format          |
+///  | num_commands | 1B   | Number of unique command strings            |
+///  | reserved     | 1B   | Copied from the uncompressed header         |
+///  | num_entries  | 8B   | Number of entries (little-endian)           |
+///  | entries      | v    | See below, one after another                |
+///  | dictionary   | v    | Same length-prefixed format as uncompressed |
+///  +--------------+------+---------------------------------------------+
+///
+///  Every entry is the zigzag varint of its clk minus the previous entry's clk (0 for the first),
+///  followed by the zigzag varints of channel, rank, bankgroup, bank, row and column, followed by
+///  the cmd_id and the 3 reserved bytes as-is.
+///
+/// The loader doesn't work on this layout directly; `decode` expands it into the regular one.
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+
+use zerocopy::byteorder::little_endian::{I16 as LeI16, I32 as LeI32, I64 as LeI64};
+use zerocopy::IntoBytes;
+
+use crate::trace::entry::Entry;
+use crate::trace::header::{self, Header};
+
+const MAGIC: [u8; 5] = *b"RAMZ\0";
+
+/// Size of the fixed part in front of the entries.
+const PREAMBLE_SIZE: usize = 16;
+
+/// Smallest possible encoded entry: seven one-byte varints plus cmd_id and reserved bytes.
+const MIN_ENTRY_SIZE: usize = 11;
+
+#[derive(Debug)]
+pub enum CompressedError {
+    Truncated,
+    VarintOverflow,
+    /// A decoded address component doesn't fit its field.
+    ValueOutOfRange,
+}
+
+impl Error for CompressedError {}
+
+impl fmt::Display for CompressedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressedError::Truncated => write!(f, "compressed trace is truncated"),
+            CompressedError::VarintOverflow => write!(f, "varint exceeds 64 bits"),
+            CompressedError::ValueOutOfRange => {
+                write!(f, "address component out of range of its field")
+            }
+        }
+    }
+}
+
+impl From<CompressedError> for std::io::Error {
+    fn from(err: CompressedError) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string())
+    }
+}
+
+/// Whether `data` starts with the compressed magic.
+pub fn is_compressed(data: &[u8]) -> bool {
+    data.starts_with(&MAGIC)
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut value: u64) -> std::io::Result<()> {
+    let mut bytes = [0u8; 10];
+    let mut len = 0;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes[len] = byte;
+            len += 1;
+            break;
+        }
+        bytes[len] = byte | 0x80;
+        len += 1;
+    }
+    writer.write_all(&bytes[..len])
+}
+
+/// Reads a varint at `*pos` and advances past it.
+fn read_varint(data: &[u8], pos: &mut usize) -> Result<u64, CompressedError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *data.get(*pos).ok_or(CompressedError::Truncated)?;
+        *pos += 1;
+
+        let bits = (byte & 0x7f) as u64;
+        if shift == 63 && bits > 1 {
+            return Err(CompressedError::VarintOverflow);
+        }
+        value |= bits << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(CompressedError::VarintOverflow)
+}
+
+/// Writes the compressed layout incrementally, so large exports can report progress in between.
+/// Call `entries` with consecutive chunks of exactly `num_entries` entries in total, then `finish`.
+pub struct Encoder<'a, W: Write> {
+    writer: &'a mut W,
+    prev_clk: i64,
+}
+
+impl<'a, W: Write> Encoder<'a, W> {
+    /// Writes the preamble. `header` provides the version, command count and reserved byte.
+    pub fn new(writer: &'a mut W, header: &Header, num_entries: u64) -> std::io::Result<Self> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[header.version, header.num_commands, header.reserved])?;
+        writer.write_all(&num_entries.to_le_bytes())?;
+
+        Ok(Self {
+            writer,
+            prev_clk: 0,
+        })
+    }
+
+    pub fn entries(&mut self, entries: &[Entry]) -> std::io::Result<()> {
+        for entry in entries {
+            let clk = entry.clk.get();
+            write_varint(self.writer, zigzag(clk.wrapping_sub(self.prev_clk)))?;
+            self.prev_clk = clk;
+
+            for value in [
+                entry.channel.get() as i64,
+                entry.rank.get() as i64,
+                entry.bankgroup.get() as i64,
+                entry.bank.get() as i64,
+                entry.row.get() as i64,
+                entry.column.get() as i64,
+            ] {
+                write_varint(self.writer, zigzag(value))?;
+            }
+
+            self.writer.write_all(&[entry.cmd_id])?;
+            self.writer.write_all(&entry.reserved)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the serialized dictionary behind the entries.
+    pub fn finish(self, dictionary_bytes: &[u8]) -> std::io::Result<()> {
+        self.writer.write_all(dictionary_bytes)
+    }
+}
+
+/// Expands a compressed trace into the regular little-endian layout (header, entries,
+/// dictionary), which is then parsed and validated like any other trace.
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, CompressedError> {
+    let preamble = data
+        .get(..PREAMBLE_SIZE)
+        .ok_or(CompressedError::Truncated)?;
+    let (version, num_commands, reserved) = (preamble[5], preamble[6], preamble[7]);
+    let mut num_entries = [0u8; 8];
+    num_entries.copy_from_slice(&preamble[8..16]);
+    let num_entries = u64::from_le_bytes(num_entries);
+
+    // The entry count comes from the file, so it only sizes the buffer once it's plausible.
+    let max_entries = ((data.len() - PREAMBLE_SIZE) / MIN_ENTRY_SIZE) as u64;
+    if num_entries > max_entries {
+        return Err(CompressedError::Truncated);
+    }
+
+    let header_size = std::mem::size_of::<Header>();
+    let entries_size = num_entries as usize * std::mem::size_of::<Entry>();
+    let mut bytes = Vec::with_capacity(header_size + entries_size);
+
+    let header = Header {
+        magic: header::MAGIC,
+        version,
+        num_commands,
+        reserved,
+        num_entries: num_entries.into(),
+        dict_offset: ((header_size + entries_size) as u64).into(),
+    };
+    bytes.extend_from_slice(header.as_bytes());
+
+    let mut pos = PREAMBLE_SIZE;
+    let mut clk = 0i64;
+    for _ in 0..num_entries {
+        clk = clk.wrapping_add(unzigzag(read_varint(data, &mut pos)?));
+
+        let mut fields = [0i64; 6];
+        for field in &mut fields {
+            *field = unzigzag(read_varint(data, &mut pos)?);
+        }
+        let narrow16 = |v: i64| i16::try_from(v).map_err(|_| CompressedError::ValueOutOfRange);
+        let narrow32 = |v: i64| i32::try_from(v).map_err(|_| CompressedError::ValueOutOfRange);
+
+        let tail = data.get(pos..pos + 4).ok_or(CompressedError::Truncated)?;
+        pos += 4;
+
+        let entry = Entry {
+            clk: LeI64::new(clk),
+            channel: LeI16::new(narrow16(fields[0])?),
+            rank: LeI16::new(narrow16(fields[1])?),
+            bankgroup: LeI32::new(narrow32(fields[2])?),
+            bank: LeI32::new(narrow32(fields[3])?),
+            row: LeI32::new(narrow32(fields[4])?),
+            column: LeI32::new(narrow32(fields[5])?),
+            cmd_id: tail[0],
+            reserved: [tail[1], tail[2], tail[3]],
+        };
+        bytes.extend_from_slice(entry.as_bytes());
+    }
+
+    bytes.extend_from_slice(&data[pos..]);
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use zerocopy::FromZeros;
+
+    use super::*;
+    use crate::trace::dictionary::Dictionary;
+    use crate::trace::writer::{self, tests::scratch_path};
+    use crate::trace::TraceLoader;
+
+    #[test]
+    fn compressed_traces_load_like_the_original() {
+        let path = scratch_path("compressed-source.bin");
+        let dict = Dictionary::from_names(&["ACT", "RD", "PRE"]);
+        let entries = [
+            Entry::new(-5, [0, 0, 0, 1, 70000, -1], 0),
+            Entry::new(12, [1, 0, 3, 2, 70000, 1023], 1),
+            Entry::new(12, [0, 1, 0, 1, -1, -1], 2),
+            Entry::new(i64::MAX, [0, 0, 0, 0, 0, 0], 1),
+        ];
+        writer::write_trace(&path, 1, &entries, &dict).unwrap();
+        let original = TraceLoader::new(path.clone()).unwrap();
+
+        let mut bytes = Vec::new();
+        let mut encoder = Encoder::new(&mut bytes, &original.header(), 4).unwrap();
+        encoder.entries(&entries[..1]).unwrap();
+        encoder.entries(&entries[1..]).unwrap();
+        encoder.finish(&dict.to_bytes().unwrap()).unwrap();
+        assert!(bytes.len() < fs::read(&path).unwrap().len());
+
+        let compressed_path = scratch_path("compressed.ramz");
+        fs::write(&compressed_path, &bytes).unwrap();
+        let loader = TraceLoader::new(compressed_path.clone()).unwrap();
+        assert_eq!(loader.entries().unwrap(), &entries);
+        assert_eq!(loader.load_dictionary().unwrap(), dict);
+
+        // The decoded header stands in for the missing on-disk one.
+        let raw = loader.raw_header_bytes().unwrap();
+        assert_eq!(raw, original.raw_header_bytes().unwrap());
+        assert_eq!(raw, original.header().as_bytes());
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&compressed_path).unwrap();
+    }
+
+    #[test]
+    fn truncated_entries_are_rejected() {
+        let mut bytes = Vec::new();
+        let mut encoder = Encoder::new(&mut bytes, &Header::new_zeroed(), 1).unwrap();
+        encoder.entries(&[Entry::new(1, [0; 6], 0)]).unwrap();
+        bytes.pop();
+
+        assert!(matches!(decode(&bytes), Err(CompressedError::Truncated)));
+    }
+}
@@ -0,0 +1,155 @@
+/// The file implements a generator of synthetic traces, for demos and as realistic test fixtures.
+/// The same seed always produces the same file.
+///
+/// The trace models a small DDR-like system (2 channels, 1 rank, 4 bank groups of 4 banks) with
+/// an open-page policy: every step picks a random bank, which is activated if closed and then
+/// either read, written or precharged. Every `REFRESH_INTERVAL` cycles each channel is refreshed,
+/// which closes all of its banks.
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::trace::dictionary::Dictionary;
+use crate::trace::entry::Entry;
+use crate::trace::writer;
+
+/// Upper bound on generated entries, since they are built up in memory (32 bytes each).
+pub const MAX_DEMO_ENTRIES: u64 = 1 << 22;
+
+const COMMANDS: [&str; 5] = ["ACT", "PRE", "RD", "WR", "REFab"];
+const ACT: u8 = 0;
+const PRE: u8 = 1;
+const RD: u8 = 2;
+const WR: u8 = 3;
+const REF: u8 = 4;
+
+const CHANNELS: u64 = 2;
+const BANKGROUPS: u64 = 4;
+const BANKS: u64 = 4;
+const ROWS: u64 = 1 << 14;
+const COLUMNS: u64 = 1 << 10;
+const BURST: u64 = 8;
+const REFRESH_INTERVAL: i64 = 3900;
+
+/// SplitMix64, small and good enough for a plausible command mix.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+}
+
+pub fn dictionary() -> Dictionary {
+    Dictionary::from_names(&COMMANDS)
+}
+
+/// `num_entries` clk-sorted entries, see the file comment for the model.
+pub fn generate(num_entries: u64, seed: u64) -> Vec<Entry> {
+    let mut rng = Rng(seed);
+    let mut entries = Vec::with_capacity(num_entries as usize);
+
+    // Open row per (channel, bankgroup, bank).
+    let mut open: HashMap<(i64, i64, i64), i64> = HashMap::new();
+    let mut clk = 0i64;
+    let mut next_refresh = REFRESH_INTERVAL;
+
+    while (entries.len() as u64) < num_entries {
+        clk += 1 + rng.below(8) as i64;
+
+        if clk >= next_refresh {
+            for channel in 0..CHANNELS as i64 {
+                if (entries.len() as u64) < num_entries {
+                    entries.push(Entry::new(clk, [channel, 0, -1, -1, -1, -1], REF));
+                }
+            }
+            open.clear();
+            next_refresh += REFRESH_INTERVAL;
+            continue;
+        }
+
+        let channel = rng.below(CHANNELS) as i64;
+        let bankgroup = rng.below(BANKGROUPS) as i64;
+        let bank = rng.below(BANKS) as i64;
+        let key = (channel, bankgroup, bank);
+
+        let Some(&row) = open.get(&key) else {
+            let row = rng.below(ROWS) as i64;
+            open.insert(key, row);
+            entries.push(Entry::new(clk, [channel, 0, bankgroup, bank, row, -1], ACT));
+            continue;
+        };
+
+        let column = (rng.below(COLUMNS / BURST) * BURST) as i64;
+        let address = [channel, 0, bankgroup, bank, row, column];
+        match rng.below(10) {
+            0..=5 => entries.push(Entry::new(clk, address, RD)),
+            6..=8 => entries.push(Entry::new(clk, address, WR)),
+            _ => {
+                open.remove(&key);
+                entries.push(Entry::new(clk, [channel, 0, bankgroup, bank, -1, -1], PRE));
+            }
+        }
+    }
+
+    entries
+}
+
+/// Generates a trace with `generate` and writes it as a v1 file at `path`.
+pub fn write(path: &Path, num_entries: u64, seed: u64) -> Result<(), std::io::Error> {
+    if num_entries > MAX_DEMO_ENTRIES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("demo traces hold at most {} entries", MAX_DEMO_ENTRIES),
+        ));
+    }
+
+    writer::write_trace(path, 1, &generate(num_entries, seed), &dictionary())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::trace::writer::tests::scratch_path;
+    use crate::trace::TraceLoader;
+
+    #[test]
+    fn demo_traces_load_sorted_and_deterministic() {
+        let (path, again) = (scratch_path("demo-a.bin"), scratch_path("demo-b.bin"));
+        write(&path, 10_000, 42).unwrap();
+        write(&again, 10_000, 42).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), fs::read(&again).unwrap());
+
+        let loader = TraceLoader::new(path.clone()).unwrap();
+        let entries = loader.entries().unwrap();
+        assert_eq!(loader.num_entries(), 10_000);
+        assert_eq!(loader.load_dictionary().unwrap(), dictionary());
+        assert!(entries.windows(2).all(|w| w[0].clk.get() <= w[1].clk.get()));
+        for cmd_id in [ACT, PRE, RD, WR, REF] {
+            assert!(entries.iter().any(|e| e.cmd_id == cmd_id), "{cmd_id}");
+        }
+
+        assert_ne!(generate(100, 42), generate(100, 43));
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&again).unwrap();
+    }
+
+    #[test]
+    fn oversized_demo_traces_are_rejected() {
+        let path = scratch_path("demo-too-big.bin");
+        let err = write(&path, MAX_DEMO_ENTRIES + 1, 0).unwrap_err();
+
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+}
@@ -19,10 +19,12 @@
 use std::error::Error;
 use std::fmt;
 
-use memmap2::Mmap;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Maps the command ids of one dictionary to those of another.
+pub type IdRemap = std::collections::HashMap<u8, u8>;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Dictionary {
     pub commands: std::collections::HashMap<u8, String>,
 }
@@ -32,6 +34,9 @@ pub enum DictionaryError {
     OffsetOutOfBounds,
     InvalidFormat,
     Utf8Error(std::str::Utf8Error),
+    NameTooLong(u8),
+    NonContiguousIds,
+    TooManyCommands,
 }
 
 impl Error for DictionaryError {}
@@ -42,6 +47,13 @@ impl fmt::Display for DictionaryError {
             DictionaryError::OffsetOutOfBounds => write!(f, "dictionary offset out of bounds"),
             DictionaryError::InvalidFormat => write!(f, "invalid dictionary format"),
             DictionaryError::Utf8Error(e) => write!(f, "UTF-8 error: {}", e),
+            DictionaryError::NameTooLong(id) => {
+                write!(f, "name of command {} exceeds 255 bytes", id)
+            }
+            DictionaryError::NonContiguousIds => {
+                write!(f, "command ids are not contiguous from 0")
+            }
+            DictionaryError::TooManyCommands => write!(f, "more than 255 commands"),
         }
     }
 }
@@ -52,15 +64,161 @@ impl From<DictionaryError> for std::io::Error {
     }
 }
 
-/// Parses the dictionary from a memory mapped trace file.
+impl Dictionary {
+    /// A dictionary of `names`, with ids in their order.
+    pub fn from_names(names: &[&str]) -> Dictionary {
+        Dictionary {
+            commands: names
+                .iter()
+                .enumerate()
+                .map(|(id, name)| (id as u8, name.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Serializes the dictionary into the on-disk length-prefixed format, in id order.
+    /// This is the inverse of `parse`.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DictionaryError> {
+        if self.commands.len() > u8::MAX as usize {
+            return Err(DictionaryError::TooManyCommands);
+        }
+
+        let mut bytes = Vec::new();
+        for cmd_id in 0..self.commands.len() as u8 {
+            let name = self
+                .commands
+                .get(&cmd_id)
+                .ok_or(DictionaryError::NonContiguousIds)?;
+            let len = u8::try_from(name.len()).map_err(|_| DictionaryError::NameTooLong(cmd_id))?;
+
+            bytes.push(len);
+            bytes.extend_from_slice(name.as_bytes());
+        }
+
+        Ok(bytes)
+    }
+
+    /// Serializes the dictionary as a JSON object mapping id to name, sorted by id.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        let sorted: std::collections::BTreeMap<u8, &String> =
+            self.commands.iter().map(|(id, name)| (*id, name)).collect();
+        serde_json::to_string_pretty(&sorted)
+    }
+
+    /// Keeps only the commands in `used`, renumbered from 0 in their original order, and returns
+    /// them with the table mapping old ids to new ones. Ids without a name in the dictionary are
+    /// dropped and have no entry in the table.
+    pub fn compact(&self, used: &std::collections::BTreeSet<u8>) -> (Dictionary, IdRemap) {
+        let mut commands = std::collections::HashMap::new();
+        let mut remap = std::collections::HashMap::new();
+
+        for old_id in used {
+            if let Some(name) = self.commands.get(old_id) {
+                let new_id = commands.len() as u8;
+                commands.insert(new_id, name.clone());
+                remap.insert(*old_id, new_id);
+            }
+        }
+
+        (Dictionary { commands }, remap)
+    }
+
+    /// The union of two dictionaries by name: this one's commands keep their order, followed by
+    /// the commands only `other` has, all renumbered from 0. Returns the union and the tables
+    /// mapping the ids of `self` and of `other` to it.
+    pub fn union(
+        &self,
+        other: &Dictionary,
+    ) -> Result<(Dictionary, IdRemap, IdRemap), DictionaryError> {
+        let mut commands = std::collections::HashMap::new();
+        let mut by_name = std::collections::HashMap::new();
+
+        let mut remap = |dictionary: &Dictionary| {
+            let mut ids: Vec<(&u8, &String)> = dictionary.commands.iter().collect();
+            ids.sort_by_key(|(id, _)| **id);
+
+            let mut table = std::collections::HashMap::new();
+            for (id, name) in ids {
+                let new_id = match by_name.get(name) {
+                    Some(new_id) => *new_id,
+                    None => {
+                        let new_id = u8::try_from(commands.len())
+                            .ok()
+                            .filter(|id| *id < u8::MAX)
+                            .ok_or(DictionaryError::TooManyCommands)?;
+                        commands.insert(new_id, name.clone());
+                        by_name.insert(name.clone(), new_id);
+                        new_id
+                    }
+                };
+                table.insert(*id, new_id);
+            }
+            Ok(table)
+        };
+
+        let self_ids = remap(self)?;
+        let other_ids = remap(other)?;
+        Ok((Dictionary { commands }, self_ids, other_ids))
+    }
+
+    /// Returns the commands whose name contains `query`, sorted by id.
+    /// Matching is case-insensitive, so "rd" finds both "RD" and "RDA". An empty query returns all commands.
+    pub fn search(&self, query: &str) -> Vec<(u8, String)> {
+        let query = query.to_lowercase();
+
+        let mut matches: Vec<(u8, String)> = self
+            .commands
+            .iter()
+            .filter(|(_, name)| name.to_lowercase().contains(&query))
+            .map(|(id, name)| (*id, name.clone()))
+            .collect();
+        matches.sort_by_key(|(id, _)| *id);
+
+        matches
+    }
+}
+
+/// Parses the dictionary from a trace file.
 /// We obtain dict_offset and num_commands from the header.
+/// Fails on the first command name that isn't valid UTF-8; see `parse_lossy` for a forgiving variant.
 pub fn parse(
-    mmap: &Mmap,
+    data: &[u8],
+    dict_offset: u64,
+    num_commands: u8,
+) -> Result<Dictionary, DictionaryError> {
+    parse_with(data, dict_offset, num_commands, |_, bytes| {
+        std::str::from_utf8(bytes)
+            .map(str::to_string)
+            .map_err(DictionaryError::Utf8Error)
+    })
+}
+
+/// Like `parse`, but replaces invalid UTF-8 in command names with U+FFFD instead of failing.
+/// Also returns the ids of the commands whose names had to be recovered.
+pub fn parse_lossy(
+    data: &[u8],
+    dict_offset: u64,
+    num_commands: u8,
+) -> Result<(Dictionary, Vec<u8>), DictionaryError> {
+    let mut recovered = Vec::new();
+
+    let dictionary = parse_with(data, dict_offset, num_commands, |cmd_id, bytes| {
+        if std::str::from_utf8(bytes).is_err() {
+            recovered.push(cmd_id);
+        }
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    })?;
+
+    Ok((dictionary, recovered))
+}
+
+fn parse_with(
+    data: &[u8],
     dict_offset: u64,
     num_commands: u8,
+    mut decode: impl FnMut(u8, &[u8]) -> Result<String, DictionaryError>,
 ) -> Result<Dictionary, DictionaryError> {
-    let data = mmap.as_ref();
-    let offset = dict_offset as usize;
+    let offset = usize::try_from(dict_offset).map_err(|_| DictionaryError::OffsetOutOfBounds)?;
 
     if offset >= data.len() {
         return Err(DictionaryError::OffsetOutOfBounds);
@@ -74,24 +232,161 @@ pub fn parse(
             return Err(DictionaryError::OffsetOutOfBounds);
         }
 
-        if pos >= data.len() {
-            return Err(DictionaryError::OffsetOutOfBounds);
-        }
-
         let str_len = data[pos] as usize;
         pos += 1;
 
-        if pos + str_len > data.len() {
-            return Err(DictionaryError::OffsetOutOfBounds);
-        }
+        let end = pos
+            .checked_add(str_len)
+            .filter(|end| *end <= data.len())
+            .ok_or(DictionaryError::OffsetOutOfBounds)?;
 
-        let name = std::str::from_utf8(&data[pos..pos + str_len])
-            .map_err(DictionaryError::Utf8Error)?
-            .to_string();
-        pos += str_len;
+        let name = decode(cmd_id, &data[pos..end])?;
+        pos = end;
 
         commands.insert(cmd_id, name);
     }
 
     Ok(Dictionary { commands })
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeSet, HashMap};
+
+    use super::*;
+
+    #[test]
+    fn to_bytes_round_trips_through_parse() {
+        let original = Dictionary::from_names(&["ACT", "RD", "WRA", "PREA", ""]);
+        let bytes = original.to_bytes().unwrap();
+
+        assert_eq!(&bytes[..4], &[3, b'A', b'C', b'T']);
+        let parsed = parse(&bytes, 0, original.commands.len() as u8).unwrap();
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn to_bytes_rejects_long_names() {
+        let long = "X".repeat(256);
+        let dict = Dictionary::from_names(&["ACT", &long]);
+
+        assert!(matches!(
+            dict.to_bytes(),
+            Err(DictionaryError::NameTooLong(1))
+        ));
+        assert!(Dictionary::from_names(&[&"X".repeat(255)])
+            .to_bytes()
+            .is_ok());
+    }
+
+    #[test]
+    fn to_bytes_rejects_gaps_in_ids() {
+        let mut dict = Dictionary::from_names(&["ACT", "RD"]);
+        let name = dict.commands.remove(&0).unwrap();
+        dict.commands.insert(2, name);
+
+        assert!(matches!(
+            dict.to_bytes(),
+            Err(DictionaryError::NonContiguousIds)
+        ));
+    }
+
+    #[test]
+    fn compact_renumbers_used_ids_with_gaps() {
+        let dict = Dictionary::from_names(&["ACT", "RD", "WR", "PRE", "REF"]);
+        let used = BTreeSet::from([1, 3, 4, 9]);
+
+        let (compacted, remap) = dict.compact(&used);
+        assert_eq!(compacted, Dictionary::from_names(&["RD", "PRE", "REF"]));
+        // Id 9 has no name, so it has no new id either.
+        assert_eq!(remap, HashMap::from([(1, 0), (3, 1), (4, 2)]));
+        assert!(compacted.to_bytes().is_ok());
+
+        let (empty, remap) = dict.compact(&BTreeSet::new());
+        assert!(empty.commands.is_empty() && remap.is_empty());
+    }
+
+    #[test]
+    fn union_shares_names_and_remaps_both_sides() {
+        let a = Dictionary::from_names(&["ACT", "RD", "PRE"]);
+        let b = Dictionary::from_names(&["WR", "PRE", "ACT"]);
+
+        let (union, a_ids, b_ids) = a.union(&b).unwrap();
+        assert_eq!(union, Dictionary::from_names(&["ACT", "RD", "PRE", "WR"]));
+        assert_eq!(a_ids, HashMap::from([(0, 0), (1, 1), (2, 2)]));
+        assert_eq!(b_ids, HashMap::from([(0, 3), (1, 2), (2, 0)]));
+    }
+
+    #[test]
+    fn union_rejects_more_than_255_commands() {
+        let names: Vec<String> = (0..200).map(|i| format!("A{i}")).collect();
+        let others: Vec<String> = (0..100).map(|i| format!("B{i}")).collect();
+        let as_strs = |names: &[String]| -> Dictionary {
+            Dictionary::from_names(&names.iter().map(String::as_str).collect::<Vec<_>>())
+        };
+
+        assert!(matches!(
+            as_strs(&names).union(&as_strs(&others)),
+            Err(DictionaryError::TooManyCommands)
+        ));
+        assert!(as_strs(&names).union(&as_strs(&names)).is_ok());
+    }
+
+    #[test]
+    fn search_matches_substrings_case_insensitively() {
+        let dict = Dictionary::from_names(&["ACT", "RDA", "WRA", "PREA", "REF"]);
+
+        assert_eq!(dict.search("ra"), vec![(2, "WRA".to_string())],);
+        assert_eq!(dict.search("rEa"), vec![(3, "PREA".to_string())],);
+        assert!(dict.search("ZQ").is_empty());
+        assert_eq!(dict.search("").len(), 5);
+    }
+
+    #[test]
+    fn lengths_and_offsets_past_the_end_are_rejected() {
+        // The second name claims 255 bytes but only two follow.
+        let data = [2, b'R', b'D', 255, b'W', b'R'];
+        assert!(matches!(
+            parse(&data, 0, 2),
+            Err(DictionaryError::OffsetOutOfBounds)
+        ));
+        assert!(matches!(
+            parse(&data, u64::MAX, 1),
+            Err(DictionaryError::OffsetOutOfBounds)
+        ));
+        assert_eq!(parse(&data, 0, 1).unwrap().commands[&0], "RD");
+    }
+
+    #[test]
+    fn lossy_parse_recovers_invalid_names() {
+        let data = [3, b'A', b'C', b'T', 2, 0xff, b'D', 3, b'R', 0xc3, 0x28];
+
+        assert!(matches!(
+            parse(&data, 0, 3),
+            Err(DictionaryError::Utf8Error(_))
+        ));
+
+        let (dict, recovered) = parse_lossy(&data, 0, 3).unwrap();
+        assert_eq!(recovered, [1, 2]);
+        assert_eq!(dict.commands[&0], "ACT");
+        assert_eq!(dict.commands[&1], "\u{fffd}D");
+        assert_eq!(dict.commands[&2], "R\u{fffd}(");
+    }
+
+    #[test]
+    fn json_round_trips_sorted_by_id() {
+        let names = [
+            "ACT", "PRE", "PREA", "RD", "RDA", "WR", "WRA", "REFab", "REFsb", "RFMab", "NOP",
+        ];
+        let dict = Dictionary::from_names(&names);
+
+        let json = dict.to_json().unwrap();
+        let parsed: HashMap<u8, String> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, dict.commands);
+
+        // Numeric order, so "10" comes after "9" rather than after "1".
+        let nine = json.find("\"9\"").unwrap();
+        let ten = json.find("\"10\"").unwrap();
+        assert!(json.find("\"1\"").unwrap() < nine && nine < ten);
+    }
+}
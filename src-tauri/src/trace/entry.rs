@@ -17,9 +17,11 @@
 /// | row         | 4B   | Row                                         |
 /// | column      | 4B   | Column                                      |
 /// | cmd_id      | 1B   | Command ID (index in the dictionary)        |
-/// | reserved    | 3B   | Padding to align struct to 32 bytes         |
+/// | reserved    | 3B   | Byte 0: flags, bytes 1-2: padding to 32B    |
 /// +-------------+------+---------------------------------------------+
-///  
+///
+/// The first reserved byte carries writer-defined flag bits (e.g. speculative/retired markers).
+/// Writers that predate flags leave it zeroed, so a zero flags byte means "no flags set".
 /// We also add abstractions to transform the entry into a more WebGL-friendly Structure of Arrays format.
 /// TODO(ziad): Implement
 ///
@@ -27,17 +29,19 @@
 /// Author: Ziad Malik
 /// Email: zmalik@ethz.ch
 /// ----
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
 
-use memmap2::Mmap;
 use serde::{Deserialize, Serialize};
-use zerocopy::{FromBytes, Immutable, KnownLayout, Unaligned};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
 
 use zerocopy::byteorder::little_endian::I16 as LeI16;
 use zerocopy::byteorder::little_endian::I32 as LeI32;
 use zerocopy::byteorder::little_endian::I64 as LeI64;
 
+use crate::trace::dictionary::Dictionary;
+use crate::trace::filter::EntryFilter;
 use crate::trace::header::Header;
 
 use crate::trace::serialize::{
@@ -46,7 +50,16 @@ use crate::trace::serialize::{
 };
 
 #[derive(
-    FromBytes, Unaligned, KnownLayout, Immutable, Debug, Copy, Clone, Serialize, Deserialize,
+    FromBytes,
+    IntoBytes,
+    Unaligned,
+    KnownLayout,
+    Immutable,
+    Debug,
+    Copy,
+    Clone,
+    Serialize,
+    Deserialize,
 )]
 #[repr(C)]
 pub struct Entry {
@@ -89,10 +102,114 @@ pub struct Entry {
     pub reserved: [u8; 3],
 }
 
+/// Entries order by clk first, then by address (channel, rank, bankgroup, bank, row, column), then
+/// by cmd_id and the reserved bytes. So sorting yields the trace's clk order, and only entries that
+/// are equal in every field compare as equal, which keeps `dedup` from merging distinct events.
+/// Fields are compared as decoded integers (`.get()`), not as raw little-endian bytes.
+impl Entry {
+    fn sort_key(&self) -> (i64, i16, i16, i32, i32, i32, i32, u8, [u8; 3]) {
+        (
+            self.clk.get(),
+            self.channel.get(),
+            self.rank.get(),
+            self.bankgroup.get(),
+            self.bank.get(),
+            self.row.get(),
+            self.column.get(),
+            self.cmd_id,
+            self.reserved,
+        )
+    }
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.sort_key() == other.sort_key()
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.sort_key().cmp(&other.sort_key())
+    }
+}
+
 impl Entry {
+    /// An entry without flags. `address` is (channel, rank, bankgroup, bank, row, column), with -1
+    /// for invalid components; values are truncated to the width of their field.
+    pub fn new(clk: i64, address: [i64; 6], cmd_id: u8) -> Entry {
+        Entry {
+            clk: LeI64::new(clk),
+            channel: LeI16::new(address[0] as i16),
+            rank: LeI16::new(address[1] as i16),
+            bankgroup: LeI32::new(address[2] as i32),
+            bank: LeI32::new(address[3] as i32),
+            row: LeI32::new(address[4] as i32),
+            column: LeI32::new(address[5] as i32),
+            cmd_id,
+            reserved: [0; 3],
+        }
+    }
+
     pub fn cmd_id(&self) -> u8 {
         self.cmd_id
     }
+
+    /// Flag bits stored in the first reserved byte.
+    pub fn flags(&self) -> u8 {
+        self.reserved[0]
+    }
+
+    /// Returns true if all bits in `mask` are set in the entry's flags.
+    pub fn has_flags(&self, mask: u8) -> bool {
+        self.flags() & mask == mask
+    }
+
+    pub fn matches(&self, filter: &EntryFilter) -> bool {
+        filter.matches(self)
+    }
+
+    /// Maps address components equal to one of `sentinels` to the canonical invalid value -1.
+    /// Ramulator versions disagree on how "not applicable" is encoded (-1, 0, max-int, ...), so
+    /// this makes traces from different sources comparable. Values are compared after widening to
+    /// i64, i.e. max-int has to be listed per width (32767 for channel/rank, 2147483647 for the rest).
+    /// The clk and command id are never touched.
+    pub fn normalized(&self, sentinels: &[i64]) -> Entry {
+        let is_sentinel = |v: i64| sentinels.contains(&v);
+        let mut entry = *self;
+
+        if is_sentinel(entry.channel.get() as i64) {
+            entry.channel = LeI16::new(-1);
+        }
+        if is_sentinel(entry.rank.get() as i64) {
+            entry.rank = LeI16::new(-1);
+        }
+        for field in [
+            &mut entry.bankgroup,
+            &mut entry.bank,
+            &mut entry.row,
+            &mut entry.column,
+        ] {
+            if is_sentinel(field.get() as i64) {
+                *field = LeI32::new(-1);
+            }
+        }
+
+        entry
+    }
+}
+
+/// An address component, or `None` if it is invalid (negative).
+pub fn valid<T: Default + PartialOrd>(value: T) -> Option<T> {
+    (value >= T::default()).then_some(value)
 }
 
 #[derive(Debug)]
@@ -118,16 +235,16 @@ impl From<EntryError> for std::io::Error {
     }
 }
 
-pub fn parse(mmap: &Mmap, header: &Header, index: u64) -> Result<Entry, EntryError> {
-    if index >= header.num_entries() {
-        return Err(EntryError::InvalidIndex);
-    }
-
-    let offset = std::mem::size_of::<Header>() + (index as usize * std::mem::size_of::<Entry>());
-    let slice = mmap.get(offset..).ok_or(EntryError::InvalidIndex)?;
+pub fn parse(data: &[u8], header: &Header, index: u64) -> Result<Entry, EntryError> {
+    let offset = header
+        .offset_for_entry(index)
+        .ok_or(EntryError::InvalidIndex)?;
+    let offset = usize::try_from(offset).map_err(|_| EntryError::InvalidIndex)?;
+    let slice = data.get(offset..).ok_or(EntryError::InvalidIndex)?;
 
+    // A header can claim more entries than the file holds, so a short tail is out of range too.
     let (entry, _) =
-        zerocopy::Ref::<&[u8], Entry>::from_prefix(slice).map_err(|_| EntryError::InvalidCmdId)?;
+        zerocopy::Ref::<&[u8], Entry>::from_prefix(slice).map_err(|_| EntryError::InvalidIndex)?;
 
     if entry.cmd_id() >= header.num_commands() {
         return Err(EntryError::InvalidCmdId);
@@ -136,40 +253,621 @@ pub fn parse(mmap: &Mmap, header: &Header, index: u64) -> Result<Entry, EntryErr
     Ok(*entry)
 }
 
-pub fn get_entry_range_bytes(entries: &[Entry]) -> Vec<u8> {
-    let n = entries.len();
+/// Rewrites one big-endian entry in place into the little-endian layout.
+/// `raw` must be exactly one entry (32 bytes); cmd_id and the reserved bytes are single bytes and stay as-is.
+pub fn swap_to_little_endian(raw: &mut [u8]) {
+    raw[0..8].reverse(); // clk
+    raw[8..10].reverse(); // channel
+    raw[10..12].reverse(); // rank
+    raw[12..16].reverse(); // bankgroup
+    raw[16..20].reverse(); // bank
+    raw[20..24].reverse(); // row
+    raw[24..28].reverse(); // column
+}
 
-    // Layout: 
+/// Start lane of the view buffer: [Start CLKs (N * 4 bytes)] as f32.
+pub fn start_lane(entries: &[Entry]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(entries.len() * 4);
+    for entry in entries {
+        bytes.extend_from_slice(&(entry.clk.get() as f32).to_le_bytes());
+    }
+    bytes
+}
+
+/// Largest magnitude up to which f32 represents every integer, i.e. every clk in the start lane.
+pub const F32_EXACT_LIMIT: i64 = 1 << 24;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenderPrecision {
+    /// Some clk is beyond `F32_EXACT_LIMIT`, so `start_lane` rounds it.
+    pub exceeds: bool,
+    /// Offset to subtract from clks before packing them: the first clk.
+    pub origin_clk: i64,
+    /// The trace's clk span fits the exact range once `origin_clk` is subtracted. If not, even
+    /// an offset start lane loses precision and the frontend needs a wider format.
+    pub fits_with_origin: bool,
+}
+
+/// Whether the clks in `[min, max]` survive the f32 start lane exactly.
+pub fn render_precision(min: i64, max: i64) -> RenderPrecision {
+    let exceeds = min.unsigned_abs().max(max.unsigned_abs()) > F32_EXACT_LIMIT as u64;
+
+    RenderPrecision {
+        exceeds,
+        origin_clk: min,
+        fits_with_origin: max.abs_diff(min) <= F32_EXACT_LIMIT as u64,
+    }
+}
+
+/// Address lanes of the view buffer: [Channels (N * 1 byte)][Bankgroups (N * 1 byte)][Banks (N * 1 byte)].
+pub fn address_lanes(entries: &[Entry]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(entries.len() * 3);
+    bytes.extend(entries.iter().map(|e| e.channel.get() as u8));
+    bytes.extend(entries.iter().map(|e| e.bankgroup.get() as u8));
+    bytes.extend(entries.iter().map(|e| e.bank.get() as u8));
+    bytes
+}
+
+pub fn get_entry_range_bytes(entries: &[Entry]) -> Vec<u8> {
+    // Layout:
     // [Start CLKs (N * 4 bytes)][Command IDs (N * 1 byte)][Channels (N * 1 byte)][Bankgroups (N * 1 byte)][Banks (N * 1 byte)]
     // TODO(ziad): Finally pin a number on the minimum & maximum values for each field. currently assuming addr vec fields fit into 1 byte.
     // Total size: N * 8 bytes.
-    let mut bytes = vec![0u8; n * 8];
+    let mut bytes = start_lane(entries);
+    bytes.reserve(entries.len() * 4);
+    bytes.extend(entries.iter().map(|e| e.cmd_id));
+    bytes.extend_from_slice(&address_lanes(entries));
+
+    bytes
+}
+
+/// Optional bounds applied to the duration lane. Purely a render concern: it keeps commands with
+/// huge clock periods from visually dominating the timeline and never affects analyses.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DurationClamp {
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+}
 
-    for (i, entry) in entries.iter().enumerate() {
-        let start_val = entry.clk.get() as f32;
-        let s_offset = i * 4;
-        bytes[s_offset..s_offset + 4].copy_from_slice(&start_val.to_le_bytes());
+impl DurationClamp {
+    pub fn is_active(&self) -> bool {
+        self.min.is_some() || self.max.is_some()
     }
 
-    let cmd_offset = n * 4;
-    for (i, entry) in entries.iter().enumerate() {
-        bytes[cmd_offset + i] = entry.cmd_id;
+    pub fn apply(&self, duration: f32) -> f32 {
+        let duration = self.min.map_or(duration, |min| duration.max(min));
+        self.max.map_or(duration, |max| duration.min(max))
     }
+}
 
-    let channel_offset = cmd_offset + n;
-    for (i, entry) in entries.iter().enumerate() {
-        bytes[channel_offset + i] = entry.channel.get() as u8;
+/// Appends a duration lane to a buffer produced by `get_entry_range_bytes`.
+/// Layout of the lane: [Durations (N * 4 bytes)], each the command's clock period after clamping.
+pub fn append_duration_lane(
+    bytes: &mut Vec<u8>,
+    entries: &[Entry],
+    periods: &[f32; 256],
+    clamp: DurationClamp,
+) {
+    bytes.reserve(entries.len() * 4);
+    for entry in entries {
+        let duration = clamp.apply(periods[entry.cmd_id as usize]);
+        bytes.extend_from_slice(&duration.to_le_bytes());
     }
+}
+
+/// Appends a picking lane carrying the absolute index (`start + i`) of every entry.
+/// f32 only represents integers exactly up to 2^24, so the index is split into two f32 lanes:
+/// [Index high (N * 4 bytes)][Index low (N * 4 bytes)] with `index = high * 2^24 + low`.
+pub fn append_index_lane(bytes: &mut Vec<u8>, start: u64, count: usize) {
+    push_index_lane(bytes, start..start + count as u64, count);
+}
+
+/// Like `append_index_lane`, for entries that aren't contiguous in the trace.
+pub fn append_sparse_index_lane(bytes: &mut Vec<u8>, indices: &[u64]) {
+    push_index_lane(bytes, indices.iter().copied(), indices.len());
+}
+
+fn push_index_lane(bytes: &mut Vec<u8>, indices: impl Iterator<Item = u64> + Clone, count: usize) {
+    const SPLIT: u64 = 1 << 24;
 
-    let bankgroup_offset = channel_offset + n;
-    for (i, entry) in entries.iter().enumerate() {
-        bytes[bankgroup_offset + i] = entry.bankgroup.get() as u8;
+    bytes.reserve(count * 8);
+    for index in indices.clone() {
+        let high = (index / SPLIT) as f32;
+        bytes.extend_from_slice(&high.to_le_bytes());
+    }
+    for index in indices {
+        let low = (index % SPLIT) as f32;
+        bytes.extend_from_slice(&low.to_le_bytes());
     }
+}
+
+/// Size of the header of `get_entry_lanes`: the entry count and four lane offsets, all u32.
+pub const LANES_HEADER_SIZE: usize = 20;
+
+/// The lanes of the view buffer, each stored contiguously behind a header of their byte offsets,
+/// so each can be wrapped as its own typed array without per-entry offset arithmetic. All values
+/// are little-endian:
+///
+/// +--------+------+-----------------------------------------------------------+
+/// | Offset | Size | Description                                               |
+/// +--------+------+-----------------------------------------------------------+
+/// | 0      | u32  | N, the number of entries                                  |
+/// | 4      | u32  | Offset of the starts: N * f32, as in `start_lane`         |
+/// | 8      | u32  | Offset of the durations: N * f32, unclamped clock periods |
+/// | 12     | u32  | Offset of the rows: 3 * N * u8, as in `address_lanes`     |
+/// | 16     | u32  | Offset of the colors: N * RGBA8 from the command colors   |
+/// | 20     |      | The lanes, in this order                                  |
+/// +--------+------+-----------------------------------------------------------+
+///
+/// The header is a multiple of 4 bytes and the f32 lanes come first, so both are aligned for a
+/// `Float32Array` over the same buffer.
+pub fn get_entry_lanes(
+    entries: &[Entry],
+    periods: &[f32; 256],
+    colors: &[[u8; 3]; 256],
+) -> Vec<u8> {
+    let n = entries.len();
+    let starts = LANES_HEADER_SIZE;
+    let durations = starts + n * 4;
+    let rows = durations + n * 4;
+    let rgba = rows + n * 3;
+
+    let mut bytes = Vec::with_capacity(rgba + n * 4);
+    for value in [n, starts, durations, rows, rgba] {
+        bytes.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+
+    bytes.extend_from_slice(&start_lane(entries));
+    append_duration_lane(&mut bytes, entries, periods, DurationClamp::default());
+    bytes.extend_from_slice(&address_lanes(entries));
+    for entry in entries {
+        let [r, g, b] = colors[entry.cmd_id as usize];
+        bytes.extend_from_slice(&[r, g, b, 0xff]);
+    }
+
+    bytes
+}
+
+/// Size of one instance in `get_entry_instances`: six f32.
+pub const INSTANCE_SIZE: usize = 24;
+
+/// The view as one interleaved per-instance buffer for a single instanced draw call, as an
+/// alternative to the separate lanes of `get_entry_lanes`. Each instance is six little-endian f32:
+///
+/// +--------+---------+--------------------------------------------------------+
+/// | Offset | Field   | Description                                            |
+/// +--------+---------+--------------------------------------------------------+
+/// | 0      | start_x | Start clk, as in `start_lane`                          |
+/// | 4      | width   | Clock period, as in `append_duration_lane` (unclamped) |
+/// | 8      | row     | Swimlane from `row_of`, -1 if the entry has none       |
+/// | 12     | r       | Command color, normalized to [0, 1]                    |
+/// | 16     | g       |                                                        |
+/// | 20     | b       |                                                        |
+/// +--------+---------+--------------------------------------------------------+
+pub fn get_entry_instances(
+    entries: &[Entry],
+    periods: &[f32; 256],
+    colors: &[[u8; 3]; 256],
+    row_of: impl Fn(&Entry) -> Option<usize>,
+) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(entries.len() * INSTANCE_SIZE);
 
-    let bank_offset = bankgroup_offset + n;
-    for (i, entry) in entries.iter().enumerate() {
-        bytes[bank_offset + i] = entry.bank.get() as u8;
+    for entry in entries {
+        let [r, g, b] = colors[entry.cmd_id as usize].map(|c| c as f32 / 255.0);
+        let row = row_of(entry).map_or(-1.0, |row| row as f32);
+
+        for value in [
+            entry.clk.get() as f32,
+            periods[entry.cmd_id as usize],
+            row,
+            r,
+            g,
+            b,
+        ] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
     }
 
     bytes
 }
+
+/// Upper bound of records returned by one `get_entries_decoded` call, sized for a details table.
+pub const MAX_DECODED_ENTRIES: usize = 10_000;
+
+/// Indices of the entries within `radius` of `index`, clamped to a trace of `num_entries` entries
+/// (`index` must be below it). The radius is capped so the range stays within
+/// `MAX_DECODED_ENTRIES`.
+pub fn context_range(index: u64, radius: u64, num_entries: u64) -> std::ops::RangeInclusive<u64> {
+    let radius = radius.min((MAX_DECODED_ENTRIES as u64 - 1) / 2);
+    let start = index.saturating_sub(radius);
+    let end = index.saturating_add(radius).min(num_entries - 1);
+    start..=end
+}
+
+/// An entry with its fields widened to plain integers and its command resolved to a name, for
+/// tables in the UI. Invalid (negative) address components are `None`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecodedEntry {
+    pub index: u64,
+    pub clk: i64,
+    pub channel: Option<i64>,
+    pub rank: Option<i64>,
+    pub bankgroup: Option<i64>,
+    pub bank: Option<i64>,
+    pub row: Option<i64>,
+    pub column: Option<i64>,
+    pub cmd_id: u8,
+    /// `None` if the id is missing from the dictionary.
+    pub command: Option<String>,
+    /// Display name: the command's alias if one is given, else `command`.
+    pub label: Option<String>,
+    pub flags: u8,
+}
+
+/// Decodes `entries`, the first of which has index `start`. `aliases` provides display names
+/// overriding the dictionary's.
+pub fn decode_entries(
+    entries: &[Entry],
+    start: u64,
+    dictionary: &Dictionary,
+    aliases: &HashMap<u8, String>,
+) -> Vec<DecodedEntry> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| DecodedEntry {
+            index: start + i as u64,
+            clk: entry.clk.get(),
+            channel: valid(entry.channel.get().into()),
+            rank: valid(entry.rank.get().into()),
+            bankgroup: valid(entry.bankgroup.get().into()),
+            bank: valid(entry.bank.get().into()),
+            row: valid(entry.row.get().into()),
+            column: valid(entry.column.get().into()),
+            cmd_id: entry.cmd_id,
+            command: dictionary.commands.get(&entry.cmd_id).cloned(),
+            label: aliases
+                .get(&entry.cmd_id)
+                .filter(|alias| !alias.is_empty())
+                .or_else(|| dictionary.commands.get(&entry.cmd_id))
+                .cloned(),
+            flags: entry.flags(),
+        })
+        .collect()
+}
+
+/// Field to order decoded entries by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SortKey {
+    Clk,
+    Channel,
+    Rank,
+    Bankgroup,
+    Bank,
+    Row,
+    Column,
+    /// By dictionary name, not by id.
+    Command,
+}
+
+/// Sorts decoded entries by `key`. Invalid address components and unknown commands sort before
+/// every valid value (after them if `descending`); ties keep their file order either way.
+pub fn sort_decoded(entries: &mut [DecodedEntry], key: SortKey, descending: bool) {
+    entries.sort_by(|a, b| {
+        let ordering = match key {
+            SortKey::Clk => a.clk.cmp(&b.clk),
+            SortKey::Channel => a.channel.cmp(&b.channel),
+            SortKey::Rank => a.rank.cmp(&b.rank),
+            SortKey::Bankgroup => a.bankgroup.cmp(&b.bankgroup),
+            SortKey::Bank => a.bank.cmp(&b.bank),
+            SortKey::Row => a.row.cmp(&b.row),
+            SortKey::Column => a.column.cmp(&b.column),
+            SortKey::Command => a.command.cmp(&b.command),
+        };
+        let ordering = if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        };
+        ordering.then(a.index.cmp(&b.index))
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn u32_at(bytes: &[u8], offset: usize) -> usize {
+        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap()) as usize
+    }
+
+    fn f32s(bytes: &[u8]) -> Vec<f32> {
+        bytes
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn entry_lanes_follow_their_header() {
+        let mut periods = [1.0; 256];
+        periods[2] = 2.5;
+        let mut colors = [[0; 3]; 256];
+        colors[2] = [10, 20, 30];
+        let entries = [
+            Entry::new(4, [1, 0, 2, 3, 0, 0], 2),
+            Entry::new(6, [0, 0, 1, 7, 0, 0], 0),
+        ];
+
+        let bytes = get_entry_lanes(&entries, &periods, &colors);
+        let offsets: Vec<usize> = (0..5).map(|i| u32_at(&bytes, i * 4)).collect();
+        assert_eq!(offsets, [2, LANES_HEADER_SIZE, 28, 36, 42]);
+        assert_eq!(bytes.len(), 42 + 2 * 4);
+
+        assert_eq!(f32s(&bytes[offsets[1]..offsets[2]]), [4.0, 6.0]);
+        assert_eq!(f32s(&bytes[offsets[2]..offsets[3]]), [2.5, 1.0]);
+        assert_eq!(&bytes[offsets[3]..offsets[4]], &[1, 0, 2, 1, 3, 7]);
+        assert_eq!(&bytes[offsets[4]..], &[10, 20, 30, 0xff, 0, 0, 0, 0xff]);
+    }
+
+    #[test]
+    fn empty_view_is_only_the_header() {
+        let bytes = get_entry_lanes(&[], &[1.0; 256], &[[0; 3]; 256]);
+
+        assert_eq!(bytes.len(), LANES_HEADER_SIZE);
+        assert_eq!(u32_at(&bytes, 0), 0);
+        assert_eq!(u32_at(&bytes, 16), LANES_HEADER_SIZE);
+    }
+
+    #[test]
+    fn context_is_centered_mid_trace_and_clamped_at_the_ends() {
+        assert_eq!(context_range(50, 3, 100), 47..=53);
+        assert_eq!(context_range(1, 3, 100), 0..=4);
+        assert_eq!(context_range(98, 3, 100), 95..=99);
+        assert_eq!(context_range(0, 0, 1), 0..=0);
+    }
+
+    #[test]
+    fn context_radius_is_capped_to_the_decoded_limit() {
+        let range = context_range(1 << 20, u64::MAX, 1 << 40);
+        assert_eq!(range.clone().count(), MAX_DECODED_ENTRIES - 1);
+        assert!(range.contains(&(1 << 20)));
+    }
+
+    #[test]
+    fn absurd_entry_counts_fail_instead_of_wrapping() {
+        let header = Header {
+            magic: crate::trace::header::MAGIC,
+            version: 1,
+            num_commands: 2,
+            reserved: 0,
+            num_entries: u64::MAX.into(),
+            dict_offset: u64::MAX.into(),
+        };
+        let mut data = header.as_bytes().to_vec();
+        data.extend_from_slice(Entry::new(3, [0, 1, 0, 2, 11, 4], 1).as_bytes());
+
+        assert_eq!(parse(&data, &header, 0).unwrap().clk.get(), 3);
+        for index in [1, u64::MAX / 32, u64::MAX - 1] {
+            assert!(matches!(
+                parse(&data, &header, index),
+                Err(EntryError::InvalidIndex)
+            ));
+        }
+    }
+
+    #[test]
+    fn clamped_durations_stay_within_bounds() {
+        let mut periods = [4.0; 256];
+        periods[0] = 0.25;
+        periods[3] = 900.0;
+        let entries = [
+            Entry::new(0, [0, 0, 0, 0, 0, 0], 0),
+            Entry::new(1, [0, 0, 0, 0, 0, 0], 1),
+            Entry::new(2, [0, 0, 0, 0, 0, 0], 3),
+        ];
+        let clamp = DurationClamp {
+            min: Some(1.0),
+            max: Some(64.0),
+        };
+
+        let mut clamped = Vec::new();
+        append_duration_lane(&mut clamped, &entries, &periods, clamp);
+        assert_eq!(f32s(&clamped), [1.0, 4.0, 64.0]);
+
+        let mut unclamped = Vec::new();
+        append_duration_lane(&mut unclamped, &entries, &periods, DurationClamp::default());
+        assert_eq!(f32s(&unclamped), [0.25, 4.0, 900.0]);
+        assert!(!DurationClamp::default().is_active());
+    }
+
+    #[test]
+    fn index_lanes_decode_to_absolute_indices() {
+        let decode = |bytes: &[u8]| -> Vec<u64> {
+            let lanes = f32s(bytes);
+            let (high, low) = lanes.split_at(lanes.len() / 2);
+            high.iter()
+                .zip(low)
+                .map(|(h, l)| *h as u64 * (1 << 24) + *l as u64)
+                .collect()
+        };
+
+        // Past 2^24 a single f32 would round, so this range only survives the split.
+        let start = (1 << 24) + 5;
+        let mut bytes = Vec::new();
+        append_index_lane(&mut bytes, start, 3);
+        assert_eq!(bytes.len(), 3 * 8);
+        assert_eq!(decode(&bytes), [start, start + 1, start + 2]);
+
+        let sparse = [7, 1 << 30, (1 << 24) - 1];
+        let mut bytes = Vec::new();
+        append_sparse_index_lane(&mut bytes, &sparse);
+        assert_eq!(decode(&bytes), sparse);
+    }
+
+    #[test]
+    fn sentinels_collapse_to_minus_one() {
+        let entry = Entry::new(77, [32767, 0, 2147483647, 3, 0, 65535], 4);
+
+        let normalized = entry.normalized(&[0, 32767, 2147483647]);
+        assert_eq!(normalized, Entry::new(77, [-1, -1, -1, 3, -1, 65535], 4));
+        // max-int of the wider fields is not the sentinel of the narrow ones, and vice versa.
+        assert_eq!(
+            entry.normalized(&[2147483647]),
+            Entry::new(77, [32767, 0, -1, 3, 0, 65535], 4)
+        );
+        assert_eq!(entry.normalized(&[]), entry);
+    }
+
+    #[test]
+    fn entries_sort_by_clk_then_address() {
+        // Raw little-endian bytes would put 256 before 1 and -1 after both.
+        let late = Entry::new(256, [0, 0, 0, 0, 0, 0], 0);
+        let early = Entry::new(1, [0, 0, 0, 0, 0, 0], 0);
+        let before = Entry::new(-1, [0, 0, 0, 0, 0, 0], 0);
+        let tie_bank_0 = Entry::new(1, [0, 0, 0, 0, 9, 0], 1);
+        let tie_bank_2 = Entry::new(1, [0, 0, 0, 2, 0, 0], 0);
+
+        let mut entries = vec![late, tie_bank_2, early, before, tie_bank_0, early];
+        entries.sort();
+        entries.dedup();
+        assert_eq!(entries, [before, early, tie_bank_0, tie_bank_2, late]);
+    }
+
+    #[test]
+    fn entries_differing_only_in_flags_are_distinct() {
+        let plain = Entry::new(8, [1, 0, 3, 1, 40, 16], 2);
+        let mut flagged = plain;
+        flagged.reserved[0] = 0b10;
+
+        assert_ne!(plain, flagged);
+        assert!(plain < flagged);
+        assert_eq!(plain, Entry::new(8, [1, 0, 3, 1, 40, 16], 2));
+    }
+
+    #[test]
+    fn decoded_entries_carry_names_and_absolute_indices() {
+        let dictionary = Dictionary::from_names(&["ACT", "RD"]);
+        let aliases = HashMap::from([(0, "Activate".to_string()), (1, String::new())]);
+        let mut flagged = Entry::new(9, [0, 1, 2, 3, 4, 5], 1);
+        flagged.reserved[0] = 0b11;
+        let entries = [
+            Entry::new(8, [0, -1, 2, 3, 4, -1], 0),
+            flagged,
+            Entry::new(10, [0; 6], 7),
+        ];
+
+        let decoded = decode_entries(&entries, 500, &dictionary, &aliases);
+        assert_eq!(
+            decoded.iter().map(|d| d.index).collect::<Vec<_>>(),
+            [500, 501, 502]
+        );
+
+        assert_eq!((decoded[0].rank, decoded[0].column), (None, None));
+        assert_eq!(decoded[0].row, Some(4));
+        assert_eq!(decoded[0].label.as_deref(), Some("Activate"));
+        assert_eq!(decoded[0].command.as_deref(), Some("ACT"));
+
+        // An empty alias falls back to the dictionary name.
+        assert_eq!(decoded[1].label.as_deref(), Some("RD"));
+        assert_eq!(decoded[1].flags, 0b11);
+        assert_eq!(
+            (decoded[2].command.clone(), decoded[2].label.clone()),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn duration_buffer_pairs_starts_with_configured_periods() {
+        let mut config = crate::session::CommandConfig::default();
+        config.clock_periods.insert(1, 8.0);
+        let periods = crate::session::period_table(Some(&config));
+        let entries = [
+            Entry::new(-2, [0, 0, 0, 0, 0, 0], 1),
+            Entry::new(1 << 20, [0, 0, 0, 0, 0, 0], 0),
+        ];
+
+        let mut bytes = start_lane(&entries);
+        append_duration_lane(&mut bytes, &entries, &periods, DurationClamp::default());
+        assert_eq!(
+            f32s(&bytes),
+            [
+                -2.0,
+                (1 << 20) as f32,
+                8.0,
+                crate::session::DEFAULT_CLOCK_PERIOD
+            ]
+        );
+    }
+
+    #[test]
+    fn instances_interleave_six_floats_per_entry() {
+        let mut periods = [1.0; 256];
+        periods[4] = 3.0;
+        let mut colors = [[0; 3]; 256];
+        colors[4] = [255, 0, 51];
+        let entries = [
+            Entry::new(30, [0, 0, 1, 2, 0, 0], 4),
+            Entry::new(31, [0, 0, -1, -1, 0, 0], 0),
+        ];
+        // Lane per valid bank group/bank pair, none for all-bank commands.
+        let row_of = |e: &Entry| {
+            let lane = valid(e.bankgroup.get())? * 4 + valid(e.bank.get())?;
+            Some(lane as usize)
+        };
+
+        let bytes = get_entry_instances(&entries, &periods, &colors, row_of);
+        assert_eq!(bytes.len(), 2 * INSTANCE_SIZE);
+        assert_eq!(
+            f32s(&bytes),
+            [30.0, 3.0, 6.0, 1.0, 0.0, 0.2, 31.0, 1.0, -1.0, 0.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn render_precision_at_the_f32_limit() {
+        let verdict = |min, max| {
+            let p = render_precision(min, max);
+            (p.exceeds, p.fits_with_origin)
+        };
+
+        assert_eq!(verdict(0, F32_EXACT_LIMIT), (false, true));
+        assert_eq!(verdict(-F32_EXACT_LIMIT, 5), (false, false));
+        // A late window of a long run only needs an origin.
+        assert_eq!(verdict(1 << 40, (1 << 40) + 1_000), (true, true));
+        assert_eq!(verdict(i64::MIN, i64::MAX), (true, false));
+        assert_eq!(render_precision(1 << 40, 1 << 41).origin_clk, 1 << 40);
+
+        // One past the limit is where f32 starts rounding.
+        let past = F32_EXACT_LIMIT + 1;
+        assert_ne!(past as f32 as i64, past);
+        assert_eq!(verdict(0, past), (true, false));
+    }
+
+    #[test]
+    fn sorted_windows_keep_file_order_among_ties() {
+        // Names sort differently from ids, and id 7 has no name.
+        let dictionary = Dictionary::from_names(&["WR", "ACT", "RD"]);
+        let entries = [
+            Entry::new(10, [0, 0, 0, 3, 5, 0], 2),
+            Entry::new(11, [0, 0, 0, -1, -1, -1], 7),
+            Entry::new(12, [0, 0, 1, 1, 5, -1], 1),
+            Entry::new(13, [0, 0, 1, 3, 5, 8], 0),
+            Entry::new(14, [0, 0, 0, 1, 5, 16], 2),
+        ];
+        let mut window = decode_entries(&entries, 40, &dictionary, &HashMap::new());
+        let indices = |window: &[DecodedEntry]| window.iter().map(|e| e.index).collect::<Vec<_>>();
+
+        sort_decoded(&mut window, SortKey::Command, false);
+        assert_eq!(indices(&window), [41, 42, 40, 44, 43]);
+
+        sort_decoded(&mut window, SortKey::Bank, true);
+        assert_eq!(indices(&window), [40, 43, 42, 44, 41]);
+
+        sort_decoded(&mut window, SortKey::Clk, false);
+        assert_eq!(indices(&window), [40, 41, 42, 43, 44]);
+    }
+}
@@ -0,0 +1,75 @@
+/// The file implements a simple conjunctive filter over trace entries.
+/// Every field is optional; an unset field matches anything, a set field must match exactly.
+/// The `flags` field is a mask: all of its bits must be set in the entry's flags byte.
+use serde::{Deserialize, Serialize};
+
+use crate::trace::entry::Entry;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntryFilter {
+    #[serde(rename = "cmdId", default)]
+    pub cmd_id: Option<u8>,
+    #[serde(default)]
+    pub channel: Option<i16>,
+    #[serde(default)]
+    pub rank: Option<i16>,
+    #[serde(default)]
+    pub bankgroup: Option<i32>,
+    #[serde(default)]
+    pub bank: Option<i32>,
+    #[serde(default)]
+    pub row: Option<i32>,
+    #[serde(default)]
+    pub column: Option<i32>,
+    #[serde(default)]
+    pub flags: Option<u8>,
+}
+
+impl EntryFilter {
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.cmd_id.map_or(true, |v| v == entry.cmd_id)
+            && self.channel.map_or(true, |v| v == entry.channel.get())
+            && self.rank.map_or(true, |v| v == entry.rank.get())
+            && self.bankgroup.map_or(true, |v| v == entry.bankgroup.get())
+            && self.bank.map_or(true, |v| v == entry.bank.get())
+            && self.row.map_or(true, |v| v == entry.row.get())
+            && self.column.map_or(true, |v| v == entry.column.get())
+            && self.flags.map_or(true, |mask| entry.has_flags(mask))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flagged(flags: u8) -> Entry {
+        let mut entry = Entry::new(0, [0, 0, 1, 2, 3, 4], 1);
+        entry.reserved = [flags, 0xff, 0xff];
+        entry
+    }
+
+    #[test]
+    fn flags_come_from_the_first_reserved_byte() {
+        assert_eq!(flagged(0b101).flags(), 0b101);
+        assert!(flagged(0b101).has_flags(0b100));
+        assert!(flagged(0b101).has_flags(0));
+        assert!(!flagged(0b101).has_flags(0b110));
+        assert_eq!(flagged(0).flags(), 0);
+    }
+
+    #[test]
+    fn flag_masks_combine_with_the_other_fields() {
+        let filter = EntryFilter {
+            bank: Some(2),
+            flags: Some(0b11),
+            ..EntryFilter::default()
+        };
+
+        assert!(filter.matches(&flagged(0b111)));
+        assert!(!filter.matches(&flagged(0b01)));
+        let mut other_bank = flagged(0b11);
+        other_bank.bank = 5.into();
+        assert!(!filter.matches(&other_bank));
+        assert!(EntryFilter::default().matches(&flagged(0)));
+    }
+}
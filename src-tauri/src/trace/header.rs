@@ -21,18 +21,39 @@
 use std::error::Error;
 use std::fmt;
 
-use memmap2::Mmap;
 use serde::{Deserialize, Serialize};
 use zerocopy::byteorder::little_endian::U64 as LeU64;
-use zerocopy::{FromBytes, Immutable, KnownLayout, Unaligned};
+use zerocopy::{FromBytes, Immutable, IntoBytes, KnownLayout, Unaligned};
 
+use crate::trace::entry::Entry;
 use crate::trace::serialize::{deserialize_leu64, serialize_leu64};
 
-const SUPPORTED_VERSION: u8 = 1;
-const MAGIC: [u8; 5] = *b"RAM2\0";
+/// Format versions this build can read.
+const SUPPORTED_VERSIONS: &[u8] = &[1];
+pub const MAGIC: [u8; 5] = *b"RAM2\0";
+
+/// Big-endian simulator builds write the magic byte-reversed, which is how we detect them.
+const MAGIC_REVERSED: [u8; 5] = [b'\0', b'2', b'M', b'A', b'R'];
+
+/// Byte order of the multi-byte fields in a trace file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Endianness {
+    Little,
+    Big,
+}
 
 #[derive(
-    FromBytes, Unaligned, KnownLayout, Immutable, Debug, Copy, Clone, Serialize, Deserialize,
+    FromBytes,
+    IntoBytes,
+    Unaligned,
+    KnownLayout,
+    Immutable,
+    Debug,
+    Copy,
+    Clone,
+    Serialize,
+    Deserialize,
 )]
 #[repr(C)]
 pub struct Header {
@@ -65,12 +86,23 @@ impl Header {
         self.dict_offset.get()
     }
 
+    /// Byte offset of the entry at `index` from the start of the file, or `None` if out of range.
+    pub fn offset_for_entry(&self, index: u64) -> Option<u64> {
+        if index >= self.num_entries() {
+            return None;
+        }
+        // A crafted header can claim an absurd num_entries, so the arithmetic must not wrap.
+        index
+            .checked_mul(std::mem::size_of::<Entry>() as u64)?
+            .checked_add(std::mem::size_of::<Header>() as u64)
+    }
+
     pub fn is_valid_magic(&self) -> bool {
         self.magic == MAGIC
     }
 
     pub fn is_supported_version(&self) -> bool {
-        self.version == SUPPORTED_VERSION
+        is_supported_version(self.version)
     }
 }
 
@@ -78,7 +110,12 @@ impl Header {
 pub enum HeaderError {
     FileTooShort,
     InvalidMagic,
-    UnsupportedVersion,
+    UnsupportedVersion {
+        found: u8,
+        supported: Vec<u8>,
+    },
+    /// `dict_offset` points into the entry region instead of behind it.
+    DictOverlapsEntries,
 }
 
 impl Error for HeaderError {}
@@ -88,7 +125,18 @@ impl fmt::Display for HeaderError {
         match self {
             HeaderError::FileTooShort => write!(f, "file too short"),
             HeaderError::InvalidMagic => write!(f, "invalid magic number"),
-            HeaderError::UnsupportedVersion => write!(f, "unsupported version"),
+            HeaderError::UnsupportedVersion { found, supported } => {
+                let supported: Vec<String> = supported.iter().map(|v| v.to_string()).collect();
+                write!(
+                    f,
+                    "unsupported version {} (supported: {})",
+                    found,
+                    supported.join(", ")
+                )
+            }
+            HeaderError::DictOverlapsEntries => {
+                write!(f, "dictionary offset lies inside the entry region")
+            }
         }
     }
 }
@@ -98,18 +146,123 @@ impl From<HeaderError> for std::io::Error {
     }
 }
 
-/// Parses the header from a memory mapped trace file.
-pub fn parse(mmap: &Mmap) -> Result<Header, HeaderError> {
-    let (header, _) = zerocopy::Ref::<&[u8], Header>::from_prefix(mmap.as_ref())
-        .map_err(|_| HeaderError::FileTooShort)?;
+/// Detects the byte order of a trace file from its magic.
+pub fn detect_endianness(data: &[u8]) -> Result<Endianness, HeaderError> {
+    if data.len() < std::mem::size_of::<Header>() {
+        return Err(HeaderError::FileTooShort);
+    }
+
+    let magic = &data[..MAGIC.len()];
+    if magic == MAGIC {
+        Ok(Endianness::Little)
+    } else if magic == MAGIC_REVERSED {
+        Ok(Endianness::Big)
+    } else {
+        Err(HeaderError::InvalidMagic)
+    }
+}
+
+/// Whether `version` is a format version this build can read.
+pub fn is_supported_version(version: u8) -> bool {
+    SUPPORTED_VERSIONS.contains(&version)
+}
+
+/// Rewrites a big-endian header in place into the little-endian layout `parse` expects.
+pub fn swap_to_little_endian(raw: &mut [u8]) -> Result<(), HeaderError> {
+    let raw = raw
+        .get_mut(..std::mem::size_of::<Header>())
+        .ok_or(HeaderError::FileTooShort)?;
+
+    raw[..MAGIC.len()].copy_from_slice(&MAGIC);
+    raw[8..16].reverse(); // num_entries
+    raw[16..24].reverse(); // dict_offset
+
+    Ok(())
+}
+
+/// Parses the header from a (little-endian) trace file.
+pub fn parse(data: &[u8]) -> Result<Header, HeaderError> {
+    let (header, _) =
+        zerocopy::Ref::<&[u8], Header>::from_prefix(data).map_err(|_| HeaderError::FileTooShort)?;
 
     if !header.is_valid_magic() {
         return Err(HeaderError::InvalidMagic);
     }
 
     if !header.is_supported_version() {
-        return Err(HeaderError::UnsupportedVersion);
+        return Err(HeaderError::UnsupportedVersion {
+            found: header.version,
+            supported: SUPPORTED_VERSIONS.to_vec(),
+        });
+    }
+
+    // The dictionary trails the entries; an offset before their end means the two would overlap.
+    let entries_end = (std::mem::size_of::<Entry>() as u64)
+        .checked_mul(header.num_entries())
+        .and_then(|bytes| bytes.checked_add(std::mem::size_of::<Header>() as u64));
+    if entries_end.map_or(true, |end| header.dict_offset() < end) {
+        return Err(HeaderError::DictOverlapsEntries);
     }
 
     Ok(*header)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(num_entries: u64, dict_offset: u64) -> Header {
+        Header {
+            magic: MAGIC,
+            version: 1,
+            num_commands: 4,
+            reserved: 0,
+            num_entries: num_entries.into(),
+            dict_offset: dict_offset.into(),
+        }
+    }
+
+    #[test]
+    fn entry_offsets_follow_the_header() {
+        let header = header(10, 24 + 10 * 32);
+        assert_eq!(header.offset_for_entry(0), Some(24));
+        assert_eq!(header.offset_for_entry(3), Some(24 + 3 * 32));
+        assert_eq!(header.offset_for_entry(9), Some(24 + 9 * 32));
+        assert_eq!(header.offset_for_entry(10), None);
+    }
+
+    #[test]
+    fn absurd_entry_counts_do_not_wrap() {
+        let header = header(u64::MAX, u64::MAX);
+        assert_eq!(header.offset_for_entry(u64::MAX / 32 + 1), None);
+        assert_eq!(header.offset_for_entry(u64::MAX - 1), None);
+    }
+
+    #[test]
+    fn dictionaries_inside_the_entry_region_are_rejected() {
+        let entries_end = 24 + 3 * 32;
+        assert!(parse(header(3, entries_end).as_bytes()).is_ok());
+        assert!(matches!(
+            parse(header(3, entries_end - 1).as_bytes()),
+            Err(HeaderError::DictOverlapsEntries)
+        ));
+        assert!(matches!(
+            parse(header(u64::MAX / 16, u64::MAX).as_bytes()),
+            Err(HeaderError::DictOverlapsEntries)
+        ));
+    }
+
+    #[test]
+    fn unsupported_versions_report_what_was_found() {
+        let mut future = header(0, 24);
+        future.version = 7;
+
+        let err = parse(future.as_bytes()).unwrap_err();
+        assert!(matches!(
+            &err,
+            HeaderError::UnsupportedVersion { found: 7, supported } if supported == &[1]
+        ));
+        assert_eq!(err.to_string(), "unsupported version 7 (supported: 1)");
+        assert!(!is_supported_version(0));
+    }
+}
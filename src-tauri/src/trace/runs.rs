@@ -0,0 +1,209 @@
+/// The file implements coalescing of consecutive entries into runs of the same command.
+/// Long runs of e.g. RDs are drawn as a single span instead of one rectangle per entry,
+/// which cuts down the number of instances the renderer has to push per frame.
+///
+/// A run is formed by merging consecutive entries that share a command id (and, if requested,
+/// the same bank address). A single entry is a run of length 1.
+use serde::{Deserialize, Serialize};
+
+use crate::trace::entry::Entry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Run {
+    pub cmd_id: u8,
+    pub start_clk: i64,
+    pub end_clk: i64,
+    pub count: u64,
+    /// Index of the run's first entry, relative to the coalesced slice.
+    pub start_index: u64,
+}
+
+fn same_bank(a: &Entry, b: &Entry) -> bool {
+    a.channel.get() == b.channel.get()
+        && a.rank.get() == b.rank.get()
+        && a.bankgroup.get() == b.bankgroup.get()
+        && a.bank.get() == b.bank.get()
+}
+
+/// Merges consecutive entries with the same command id into runs.
+/// If `split_by_bank` is set, a change of bank address also starts a new run.
+pub fn coalesce(entries: &[Entry], split_by_bank: bool) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    let mut prev: Option<&Entry> = None;
+
+    for (i, entry) in entries.iter().enumerate() {
+        let extends = match (prev, runs.last()) {
+            (Some(p), Some(run)) => {
+                run.cmd_id == entry.cmd_id && (!split_by_bank || same_bank(p, entry))
+            }
+            _ => false,
+        };
+
+        if extends {
+            let run = runs.last_mut().expect("extends implies a previous run");
+            run.end_clk = entry.clk.get();
+            run.count += 1;
+        } else {
+            runs.push(Run {
+                cmd_id: entry.cmd_id,
+                start_clk: entry.clk.get(),
+                end_clk: entry.clk.get(),
+                count: 1,
+                start_index: i as u64,
+            });
+        }
+
+        prev = Some(entry);
+    }
+
+    runs
+}
+
+/// The longest run of `entries`, optionally only among runs of `cmd_id`. Ties go to the earliest
+/// run. Runs are not split by bank. Scans without collecting all runs.
+pub fn longest(entries: &[Entry], cmd_id: Option<u8>) -> Option<Run> {
+    let mut best: Option<Run> = None;
+    let mut current: Option<Run> = None;
+
+    let mut finish = |run: Run| {
+        let eligible = cmd_id.map_or(true, |id| id == run.cmd_id);
+        if eligible && best.map_or(true, |b| run.count > b.count) {
+            best = Some(run);
+        }
+    };
+
+    for (i, entry) in entries.iter().enumerate() {
+        match current.as_mut() {
+            Some(run) if run.cmd_id == entry.cmd_id => {
+                run.end_clk = entry.clk.get();
+                run.count += 1;
+            }
+            _ => {
+                if let Some(run) = current.take() {
+                    finish(run);
+                }
+                current = Some(Run {
+                    cmd_id: entry.cmd_id,
+                    start_clk: entry.clk.get(),
+                    end_clk: entry.clk.get(),
+                    count: 1,
+                    start_index: i as u64,
+                });
+            }
+        }
+    }
+
+    if let Some(run) = current {
+        finish(run);
+    }
+
+    best
+}
+
+pub fn get_run_bytes(runs: &[Run]) -> Vec<u8> {
+    let n = runs.len();
+
+    // Layout:
+    // [Start CLKs (N * 4 bytes)][End CLKs (N * 4 bytes)][Counts (N * 4 bytes)][Command IDs (N * 1 byte)]
+    // Total size: N * 13 bytes.
+    let mut bytes = vec![0u8; n * 13];
+
+    for (i, run) in runs.iter().enumerate() {
+        let offset = i * 4;
+        bytes[offset..offset + 4].copy_from_slice(&(run.start_clk as f32).to_le_bytes());
+    }
+
+    let end_offset = n * 4;
+    for (i, run) in runs.iter().enumerate() {
+        let offset = end_offset + i * 4;
+        bytes[offset..offset + 4].copy_from_slice(&(run.end_clk as f32).to_le_bytes());
+    }
+
+    let count_offset = end_offset + n * 4;
+    for (i, run) in runs.iter().enumerate() {
+        let offset = count_offset + i * 4;
+        let count = u32::try_from(run.count).unwrap_or(u32::MAX);
+        bytes[offset..offset + 4].copy_from_slice(&count.to_le_bytes());
+    }
+
+    let cmd_offset = count_offset + n * 4;
+    for (i, run) in runs.iter().enumerate() {
+        bytes[cmd_offset + i] = run.cmd_id;
+    }
+
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rd(clk: i64, bank: i64) -> Entry {
+        Entry::new(clk, [0, 0, 0, bank, 1, 0], 2)
+    }
+
+    fn spans(runs: &[Run]) -> Vec<(u8, i64, i64, u64, u64)> {
+        runs.iter()
+            .map(|r| (r.cmd_id, r.start_clk, r.end_clk, r.count, r.start_index))
+            .collect()
+    }
+
+    #[test]
+    fn consecutive_commands_merge_into_runs() {
+        let act = Entry::new(0, [0, 0, 0, 0, 1, -1], 0);
+        let entries = [act, rd(4, 0), rd(8, 1), rd(12, 1), act, rd(20, 0)];
+
+        assert_eq!(
+            spans(&coalesce(&entries, false)),
+            [
+                (0, 0, 0, 1, 0),
+                (2, 4, 12, 3, 1),
+                (0, 0, 0, 1, 4),
+                (2, 20, 20, 1, 5)
+            ]
+        );
+        assert_eq!(
+            spans(&coalesce(&entries[1..4], true)),
+            [(2, 4, 4, 1, 0), (2, 8, 12, 2, 1)]
+        );
+        assert!(coalesce(&[], true).is_empty());
+    }
+
+    #[test]
+    fn run_bytes_are_laid_out_lane_by_lane() {
+        let runs = coalesce(&[rd(4, 0), rd(8, 0), Entry::new(9, [0; 6], 5)], false);
+        let bytes = get_run_bytes(&runs);
+
+        assert_eq!(bytes.len(), 2 * 13);
+        let f32_at = |i: usize| f32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().unwrap());
+        assert_eq!((f32_at(0), f32_at(4)), (4.0, 9.0));
+        assert_eq!((f32_at(8), f32_at(12)), (8.0, 9.0));
+        assert_eq!((u32_at(16), u32_at(20)), (2, 1));
+        assert_eq!(&bytes[24..], &[2, 5]);
+    }
+
+    #[test]
+    fn longest_run_prefers_the_earliest_on_ties() {
+        let wr = |clk| Entry::new(clk, [0, 1, 2, 0, 33, 8], 3);
+        let entries = [
+            wr(0),
+            wr(1),
+            rd(2, 0),
+            rd(3, 5),
+            wr(4),
+            wr(5),
+            rd(6, 1),
+            rd(7, 1),
+            rd(8, 1),
+        ];
+
+        let span = |run: Option<Run>| run.map(|r| (r.cmd_id, r.start_clk, r.count, r.start_index));
+        assert_eq!(span(longest(&entries, None)), Some((2, 6, 3, 6)));
+        assert_eq!(span(longest(&entries, Some(3))), Some((3, 0, 2, 0)));
+        assert_eq!(span(longest(&entries[..6], None)), Some((3, 0, 2, 0)));
+        assert_eq!(span(longest(&entries, Some(0))), None);
+        assert_eq!(span(longest(&[], None)), None);
+    }
+}
@@ -0,0 +1,545 @@
+/// The file implements writing trace files: an atomic write helper, `write_trace`, which writes a
+/// complete trace, `write_clip`, which writes a window of a trace as a standalone one,
+/// `write_merged`, which interleaves two traces, and `TraceAppender`, which grows an existing
+/// trace by new entries.
+///
+/// Since the dictionary trails the entries, appending means everything from the old dictionary on
+/// has to be rewritten. All writes go to a temporary file next to the target that is renamed over it
+/// once complete, so a crash leaves either the old or the new file, never a mix of both.
+use std::collections::BTreeSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use memmap2::Mmap;
+use zerocopy::byteorder::little_endian::{I64 as LeI64, U64 as LeU64};
+use zerocopy::IntoBytes;
+
+use crate::trace::dictionary::{self, Dictionary, IdRemap};
+use crate::trace::entry::Entry;
+use crate::trace::header::{self, Endianness, Header};
+
+/// A sibling of `path` no other write uses: the name carries the process id and a counter, so
+/// concurrent writes to the same target (from this or another instance) don't share a file.
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+/// Writes a file through `write` into a temporary sibling, syncs it and renames it over `path`.
+/// On failure the temporary file is removed and `path` is left untouched.
+pub fn write_atomic<F>(path: &Path, write: F) -> Result<(), std::io::Error>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<(), std::io::Error>,
+{
+    let temp = temp_path(path);
+
+    let result = (|| {
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        let mut writer = BufWriter::new(file);
+        write(&mut writer)?;
+
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::rename(&temp, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Byte offset just past `num_entries` entries, which is where the dictionary starts.
+fn entries_end(num_entries: u64) -> Option<u64> {
+    (std::mem::size_of::<Entry>() as u64)
+        .checked_mul(num_entries)?
+        .checked_add(std::mem::size_of::<Header>() as u64)
+}
+
+fn invalid_input(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message.to_string())
+}
+
+/// Writes `entries` as a complete trace like `write_trace`, but only the commands the entries use
+/// are kept in the dictionary, renumbered from 0 in their original order, and the entries' ids are
+/// remapped to match. With `rebase_by`, that value is subtracted from every clk, e.g. to make a
+/// clip start at 0.
+pub fn write_clip(
+    path: &Path,
+    version: u8,
+    entries: &[Entry],
+    dictionary: &Dictionary,
+    rebase_by: Option<i64>,
+) -> Result<(), std::io::Error> {
+    let used: BTreeSet<u8> = entries.iter().map(|entry| entry.cmd_id).collect();
+    let (clip_dictionary, new_ids) = dictionary.compact(&used);
+    if new_ids.len() != used.len() {
+        return Err(invalid_input(
+            "entry uses a command id outside the dictionary",
+        ));
+    }
+
+    let clip_entries: Vec<Entry> = entries
+        .iter()
+        .map(|entry| {
+            let mut entry = *entry;
+            entry.cmd_id = new_ids[&entry.cmd_id];
+            if let Some(offset) = rebase_by {
+                entry.clk = LeI64::new(entry.clk.get().wrapping_sub(offset));
+            }
+            entry
+        })
+        .collect();
+
+    write_trace(path, version, &clip_entries, &clip_dictionary)
+}
+
+/// Writes `entries` and `dictionary` as they are as a complete little-endian trace of format
+/// `version` at `path`. The dictionary's ids must be contiguous from 0.
+pub fn write_trace(
+    path: &Path,
+    version: u8,
+    entries: &[Entry],
+    dictionary: &Dictionary,
+) -> Result<(), std::io::Error> {
+    let dictionary_bytes = dictionary.to_bytes()?;
+
+    let num_entries = entries.len() as u64;
+    let header = Header {
+        magic: header::MAGIC,
+        version,
+        num_commands: dictionary.commands.len() as u8,
+        reserved: 0,
+        num_entries: LeU64::new(num_entries),
+        dict_offset: LeU64::new(
+            entries_end(num_entries).ok_or_else(|| invalid_input("too many entries"))?,
+        ),
+    };
+
+    write_atomic(path, |writer| {
+        writer.write_all(header.as_bytes())?;
+        writer.write_all(entries.as_bytes())?;
+        writer.write_all(&dictionary_bytes)
+    })
+}
+
+/// Writes the chronological merge of two traces as one trace of format `version`. The
+/// dictionaries are united by name (see `Dictionary::union`) and both inputs' ids remapped to it,
+/// then trimmed to the commands the merged entries use like `write_clip`. Entries with equal clks
+/// keep their order, with those of `a` first.
+pub fn write_merged(
+    path: &Path,
+    version: u8,
+    (a, a_dictionary): (&[Entry], &Dictionary),
+    (b, b_dictionary): (&[Entry], &Dictionary),
+) -> Result<(), std::io::Error> {
+    let (dictionary, a_ids, b_ids) = a_dictionary.union(b_dictionary)?;
+
+    let remap = |entry: &Entry, ids: &IdRemap| {
+        let mut entry = *entry;
+        entry.cmd_id = *ids
+            .get(&entry.cmd_id)
+            .ok_or_else(|| invalid_input("entry uses a command id outside the dictionary"))?;
+        Ok::<_, std::io::Error>(entry)
+    };
+
+    let mut merged = Vec::with_capacity(a.len() + b.len());
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if j == b.len() || (i < a.len() && a[i].clk.get() <= b[j].clk.get()) {
+            merged.push(remap(&a[i], &a_ids)?);
+            i += 1;
+        } else {
+            merged.push(remap(&b[j], &b_ids)?);
+            j += 1;
+        }
+    }
+
+    write_clip(path, version, &merged, &dictionary, None)
+}
+
+pub struct TraceAppender {
+    path: PathBuf,
+    header: Header,
+    dictionary: Dictionary,
+    last_clk: Option<i64>,
+}
+
+impl TraceAppender {
+    /// Opens an existing little-endian v1 trace. The header, dictionary and last entry are
+    /// validated up front so `append` can't produce a file the loader rejects.
+    pub fn open(path: PathBuf) -> Result<Self, std::io::Error> {
+        let file = File::open(&path)?;
+        if file.metadata()?.len() == 0 {
+            return Err(header::HeaderError::FileTooShort.into());
+        }
+
+        // SAFETY: as for `TraceLoader`, the file must not be truncated while mapped. The mapping
+        // only lives for the duration of this function.
+        let data = unsafe { Mmap::map(&file)? };
+
+        if header::detect_endianness(&data)? == Endianness::Big {
+            return Err(invalid_input(
+                "appending to big-endian traces is not supported",
+            ));
+        }
+        let header = header::parse(&data)?;
+
+        let dictionary = dictionary::parse(&data, header.dict_offset(), header.num_commands())?;
+        if dictionary.commands.len() != header.num_commands() as usize {
+            return Err(dictionary::DictionaryError::InvalidFormat.into());
+        }
+
+        let last_clk = match header.num_entries().checked_sub(1) {
+            Some(last) => Some(crate::trace::entry::parse(&data, &header, last)?.clk.get()),
+            None => None,
+        };
+
+        Ok(Self {
+            path,
+            header,
+            dictionary,
+            last_clk,
+        })
+    }
+
+    pub fn num_entries(&self) -> u64 {
+        self.header.num_entries()
+    }
+
+    /// Appends `entries` after the existing ones and rewrites the dictionary behind them.
+    /// Entries must use ids of the existing dictionary and keep the trace sorted by clk.
+    pub fn append(&mut self, entries: &[Entry]) -> Result<(), std::io::Error> {
+        if entries
+            .iter()
+            .any(|e| e.cmd_id() >= self.header.num_commands())
+        {
+            return Err(invalid_input(
+                "entry uses a command id outside the dictionary",
+            ));
+        }
+
+        let mut prev = self.last_clk;
+        for entry in entries {
+            let clk = entry.clk.get();
+            if prev.is_some_and(|p| clk < p) {
+                return Err(invalid_input("appended entries must not go back in clk"));
+            }
+            prev = Some(clk);
+        }
+
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let old_entries_len = (std::mem::size_of::<Entry>() as u64)
+            .checked_mul(self.header.num_entries())
+            .ok_or_else(|| invalid_input("trace too large"))?;
+
+        let num_entries = self
+            .header
+            .num_entries()
+            .checked_add(entries.len() as u64)
+            .ok_or_else(|| invalid_input("too many entries"))?;
+        let dict_offset =
+            entries_end(num_entries).ok_or_else(|| invalid_input("too many entries"))?;
+
+        let mut header = self.header;
+        header.num_entries = LeU64::new(num_entries);
+        header.dict_offset = LeU64::new(dict_offset);
+
+        let dictionary_bytes = self.dictionary.to_bytes()?;
+
+        // The old entries are copied through a plain reader rather than a mapping: a file that is
+        // still mapped can't be replaced on Windows. The reader is closed before the rename.
+        write_atomic(&self.path, |writer| {
+            let mut old = File::open(&self.path)?;
+            old.seek(SeekFrom::Start(std::mem::size_of::<Header>() as u64))?;
+
+            writer.write_all(header.as_bytes())?;
+            if std::io::copy(&mut old.take(old_entries_len), writer)? != old_entries_len {
+                return Err(header::HeaderError::FileTooShort.into());
+            }
+            writer.write_all(entries.as_bytes())?;
+            writer.write_all(&dictionary_bytes)
+        })?;
+
+        self.header = header;
+        self.last_clk = prev;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::trace::TraceLoader;
+
+    /// A path in the system temp dir that no other test uses.
+    pub(crate) fn scratch_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ramwiz-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn temp_paths_are_unique_siblings() {
+        let path = Path::new("/traces/run.bin");
+        let (a, b) = (temp_path(path), temp_path(path));
+
+        assert_ne!(a, b);
+        assert_eq!(a.parent(), path.parent());
+        assert!(a.to_string_lossy().ends_with(".tmp"));
+    }
+
+    #[test]
+    fn appended_entries_follow_the_existing_ones() {
+        let path = scratch_path("append.bin");
+        let dict = Dictionary::from_names(&["ACT", "RD", "PRE"]);
+        let first = [
+            Entry::new(10, [0, 0, 0, 1, 5, -1], 0),
+            Entry::new(14, [0, 0, 0, 1, 5, 3], 1),
+        ];
+        write_trace(&path, 1, &first, &dict).unwrap();
+
+        let mut appender = TraceAppender::open(path.clone()).unwrap();
+        let more = [
+            Entry::new(14, [0, 0, 0, 1, 5, 4], 1),
+            Entry::new(30, [0, 0, 0, 1, -1, -1], 2),
+        ];
+        appender.append(&more).unwrap();
+        assert_eq!(appender.num_entries(), 4);
+
+        let loader = TraceLoader::new(path.clone()).unwrap();
+        assert_eq!(loader.num_entries(), 4);
+        assert_eq!(loader.load_dictionary().unwrap(), dict);
+        let entries = loader.entries().unwrap();
+        assert_eq!(&entries[..2], &first);
+        assert_eq!(&entries[2..], &more);
+        drop(loader);
+
+        // The appender tracks the new last clk across calls.
+        let err = appender
+            .append(&[Entry::new(29, [0, 0, 0, 0, 0, 0], 1)])
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(appender
+            .append(&[Entry::new(0, [0; 6], 3)])
+            .is_err_and(|e| e.to_string().contains("outside the dictionary")));
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_append_leaves_the_file_untouched() {
+        let path = scratch_path("append-untouched.bin");
+        write_trace(
+            &path,
+            1,
+            &[Entry::new(100, [0; 6], 0)],
+            &Dictionary::from_names(&["RD"]),
+        )
+        .unwrap();
+        let before = fs::read(&path).unwrap();
+
+        let mut appender = TraceAppender::open(path.clone()).unwrap();
+        assert!(appender.append(&[Entry::new(99, [0; 6], 0)]).is_err());
+        assert_eq!(fs::read(&path).unwrap(), before);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn clips_reload_with_rebased_clks_and_a_trimmed_dictionary() {
+        let path = scratch_path("clip-source.bin");
+        let dict = Dictionary::from_names(&["ACT", "RD", "WR", "PRE"]);
+        let entries = [
+            Entry::new(100, [0, 0, 0, 1, 5, -1], 0),
+            Entry::new(200, [0, 0, 0, 1, 5, 3], 2),
+            Entry::new(250, [0, 0, 0, 1, 5, 4], 2),
+            Entry::new(300, [0, 0, 0, 1, -1, -1], 3),
+            Entry::new(400, [0, 0, 0, 1, 5, 5], 1),
+        ];
+        write_trace(&path, 1, &entries, &dict).unwrap();
+
+        let loader = TraceLoader::new(path.clone()).unwrap();
+        let range = loader.index_range_for_time(150, 300).unwrap();
+        let window = loader
+            .load_entry_slice(range.start, (range.end - range.start) as usize)
+            .unwrap();
+
+        let clip_path = scratch_path("clip.bin");
+        write_clip(&clip_path, 1, window, &dict, Some(150)).unwrap();
+        let clip = TraceLoader::new(clip_path.clone()).unwrap();
+
+        assert_eq!(
+            clip.load_dictionary().unwrap(),
+            Dictionary::from_names(&["WR", "PRE"])
+        );
+        assert_eq!(
+            clip.entries().unwrap(),
+            [
+                Entry::new(50, [0, 0, 0, 1, 5, 3], 0),
+                Entry::new(100, [0, 0, 0, 1, 5, 4], 0),
+                Entry::new(150, [0, 0, 0, 1, -1, -1], 1),
+            ]
+        );
+
+        // Without rebasing the clks are kept as they are.
+        write_clip(&clip_path, 1, window, &dict, None).unwrap();
+        let clip = TraceLoader::new(clip_path.clone()).unwrap();
+        assert_eq!(clip.entries().unwrap()[0].clk.get(), 200);
+
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&clip_path).unwrap();
+    }
+
+    #[test]
+    fn clips_reject_ids_outside_the_dictionary() {
+        let path = scratch_path("clip-bad-id.bin");
+        let err = write_clip(
+            &path,
+            1,
+            &[Entry::new(0, [0; 6], 4)],
+            &Dictionary::from_names(&["RD"]),
+            None,
+        )
+        .unwrap_err();
+
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn merged_traces_drop_commands_neither_input_uses() {
+        let path = scratch_path("merge-compact.bin");
+        let a_dict = Dictionary::from_names(&["ACT", "RD", "REF"]);
+        let b_dict = Dictionary::from_names(&["WR", "ACT"]);
+        let a = [Entry::new(0, [0; 6], 0), Entry::new(3, [0; 6], 1)];
+        let b = [Entry::new(1, [0; 6], 1)];
+
+        write_merged(&path, 1, (&a, &a_dict), (&b, &b_dict)).unwrap();
+        let loader = TraceLoader::new(path.clone()).unwrap();
+
+        // REF and WR are unused, so only ACT and RD remain, renumbered in union order.
+        assert_eq!(
+            loader.load_dictionary().unwrap(),
+            Dictionary::from_names(&["ACT", "RD"])
+        );
+        let ids: Vec<u8> = loader.entries().unwrap().iter().map(|e| e.cmd_id).collect();
+        assert_eq!(ids, [0, 0, 1]);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn merged_traces_interleave_by_clk_with_consistent_names() {
+        let (a_path, b_path, out) = (
+            scratch_path("merge-a.bin"),
+            scratch_path("merge-b.bin"),
+            scratch_path("merge-out.bin"),
+        );
+        let a_dict = Dictionary::from_names(&["ACT", "RD"]);
+        let b_dict = Dictionary::from_names(&["WR", "ACT"]);
+        write_trace(
+            &a_path,
+            1,
+            &[
+                Entry::new(0, [0, 0, 0, 0, 1, -1], 0),
+                Entry::new(5, [0, 0, 0, 0, 1, 2], 1),
+                Entry::new(9, [0, 0, 0, 0, 1, 3], 1),
+            ],
+            &a_dict,
+        )
+        .unwrap();
+        write_trace(
+            &b_path,
+            1,
+            &[
+                Entry::new(2, [1, 0, 0, 0, 4, -1], 1),
+                Entry::new(5, [1, 0, 0, 0, 4, 6], 0),
+            ],
+            &b_dict,
+        )
+        .unwrap();
+
+        let a = TraceLoader::new(a_path.clone()).unwrap();
+        let b = TraceLoader::new(b_path.clone()).unwrap();
+        write_merged(
+            &out,
+            1,
+            (a.entries().unwrap(), &a_dict),
+            (b.entries().unwrap(), &b_dict),
+        )
+        .unwrap();
+
+        let merged = TraceLoader::new(out.clone()).unwrap();
+        let dict = merged.load_dictionary().unwrap();
+        let rows: Vec<(i64, i16, &str)> = merged
+            .entries()
+            .unwrap()
+            .iter()
+            .map(|e| {
+                (
+                    e.clk.get(),
+                    e.channel.get(),
+                    dict.commands[&e.cmd_id].as_str(),
+                )
+            })
+            .collect();
+        // The tie at clk 5 keeps `a`'s entry first.
+        assert_eq!(
+            rows,
+            [
+                (0, 0, "ACT"),
+                (2, 1, "ACT"),
+                (5, 0, "RD"),
+                (5, 1, "WR"),
+                (9, 0, "RD")
+            ]
+        );
+
+        for path in [a_path, b_path, out] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn failed_atomic_writes_leave_the_target_alone() {
+        let path = scratch_path("atomic.bin");
+        write_atomic(&path, |w| w.write_all(b"first")).unwrap();
+        write_atomic(&path, |w| w.write_all(b"second")).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+
+        let failed = write_atomic(&path, |w| {
+            w.write_all(b"partial")?;
+            Err(std::io::Error::other("disk full"))
+        });
+        assert_eq!(failed.unwrap_err().to_string(), "disk full");
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+
+        // No temporary sibling survives, successful or not.
+        let prefix = path.file_name().unwrap().to_string_lossy().into_owned() + ".";
+        let leftovers = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .filter(|e| {
+                let name = e.as_ref().unwrap().file_name();
+                name.to_string_lossy().starts_with(&prefix)
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,260 @@
+/// This file implements streaming of the `get_trace_view` buffer for large windows: a background
+/// task sends the buffer in chunks over a Tauri channel, so neither side has to hold a second copy
+/// of the whole window and the frontend can upload it progressively.
+///
+/// Every channel message is one binary chunk, the regular 8-byte view buffer
+/// (`trace::entry::get_entry_range_bytes`) of `count` window entries from `first`, behind a header
+/// of seven little-endian u64s:
+///
+///  +--------------+------+----------------------------------------------------+
+///  |     Name     | Size |                    Description                     |
+///  +--------------+------+----------------------------------------------------+
+///  | first        | 8B   | Index of the chunk's first entry within the window |
+///  | count        | 8B   | Number of entries in the chunk                     |
+///  | lane_offsets | 40B  | Offsets of the five lanes in the window's buffer   |
+///  | lanes        | v    | Start clks, command ids, channels, bankgroups and  |
+///  |              |      | banks of the chunk, `count * 8` bytes              |
+///  +--------------+------+----------------------------------------------------+
+///
+/// Writing lane `i` of a chunk at `lane_offsets[i]` reassembles exactly what `get_trace_view`
+/// returns. The end of every stream is announced once by a `trace-view-done` event,
+/// `{ total, cancelled, error }`. Tauri doesn't order events against channel messages, so the
+/// stream is complete once that event arrived and chunks of `total` entries were received.
+///
+/// As for the search, the loader is locked per chunk and a changed trace ends the stream with an
+/// error. Starting a stream cancels the running one.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use serde::Serialize;
+use tauri::ipc::{Channel, Response};
+use tauri::{AppHandle, Emitter, Manager, Runtime};
+
+use crate::session::SessionState;
+use crate::trace::entry;
+
+pub const DEFAULT_CHUNK_ENTRIES: u64 = 1 << 16;
+
+/// Size of the header in front of every chunk's lanes.
+pub const CHUNK_HEADER_SIZE: usize = 7 * 8;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ViewDone {
+    total: u64,
+    cancelled: bool,
+    error: Option<String>,
+}
+
+/// Byte offsets of the lanes of the window entries from `first` in the buffer of a window of
+/// `window` entries, as laid out by `get_entry_range_bytes`.
+fn lane_offsets(window: u64, first: u64) -> [u64; 5] {
+    [
+        first * 4,
+        window * 4 + first,
+        window * 5 + first,
+        window * 6 + first,
+        window * 7 + first,
+    ]
+}
+
+/// The channel message of the `count` window entries from `first`, see the file comment.
+fn load_chunk(
+    session: &SessionState,
+    identity: u64,
+    (start, window): (u64, u64),
+    first: u64,
+    count: u64,
+) -> Result<Vec<u8>, String> {
+    let guard = session.loader.lock().map_err(|e| e.to_string())?;
+    let loader = guard
+        .as_ref()
+        .ok_or_else(|| "No trace loaded".to_string())?;
+    if loader.identity() != identity {
+        return Err("The trace changed during the stream".to_string());
+    }
+
+    let entries = loader
+        .load_entry_slice(start + first, count as usize)
+        .map_err(|e| e.to_string())?;
+
+    let mut message = Vec::with_capacity(CHUNK_HEADER_SIZE + entries.len() * 8);
+    for value in [first, count]
+        .into_iter()
+        .chain(lane_offsets(window, first))
+    {
+        message.extend_from_slice(&value.to_le_bytes());
+    }
+    message.extend_from_slice(&entry::get_entry_range_bytes(entries));
+    Ok(message)
+}
+
+fn run(
+    session: &SessionState,
+    identity: u64,
+    (start, window): (u64, u64),
+    chunk_entries: u64,
+    cancel: &AtomicBool,
+    mut send: impl FnMut(Vec<u8>) -> Result<(), String>,
+) -> ViewDone {
+    let mut first = 0;
+
+    while first < window {
+        if cancel.load(Ordering::Relaxed) {
+            return ViewDone {
+                total: first,
+                cancelled: true,
+                error: None,
+            };
+        }
+
+        let count = chunk_entries.min(window - first);
+        let chunk =
+            load_chunk(session, identity, (start, window), first, count).and_then(&mut send);
+        if let Err(error) = chunk {
+            return ViewDone {
+                total: first,
+                cancelled: false,
+                error: Some(error),
+            };
+        }
+
+        first += count;
+    }
+
+    ViewDone {
+        total: window,
+        cancelled: false,
+        error: None,
+    }
+}
+
+/// Starts streaming the view buffer of `count` entries from `start` to `channel` in a background
+/// thread and returns immediately. The window is validated up front, so a stream that starts also
+/// covers it.
+pub fn spawn<R: Runtime>(
+    app: AppHandle<R>,
+    session: &SessionState,
+    (start, count): (u64, u64),
+    chunk_entries: u64,
+    channel: Channel<Response>,
+) -> Result<(), String> {
+    let identity = {
+        let guard = session.loader.lock().map_err(|e| e.to_string())?;
+        let loader = guard
+            .as_ref()
+            .ok_or_else(|| "No trace loaded".to_string())?;
+        if start
+            .checked_add(count)
+            .map_or(true, |end| end > loader.num_entries())
+        {
+            return Err("Out of bounds".to_string());
+        }
+        loader.identity()
+    };
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut guard = session.view_cancel.lock().map_err(|e| e.to_string())?;
+        if let Some(previous) = guard.replace(cancel.clone()) {
+            previous.store(true, Ordering::Relaxed);
+        }
+    }
+
+    std::thread::spawn(move || {
+        let session = app.state::<SessionState>();
+        let done = run(
+            &session,
+            identity,
+            (start, count),
+            chunk_entries.max(1),
+            &cancel,
+            |message| {
+                channel
+                    .send(Response::new(message))
+                    .map_err(|e| e.to_string())
+            },
+        );
+        if let Err(e) = app.emit("trace-view-done", done) {
+            log::warn!("Failed to emit trace-view-done: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+/// Cancels the running stream, if any. It still ends with a `trace-view-done` event.
+pub fn cancel(session: &SessionState) -> Result<(), String> {
+    let guard = session.view_cancel.lock().map_err(|e| e.to_string())?;
+    if let Some(cancel) = guard.as_ref() {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trace::writer::{self, tests::scratch_path};
+    use crate::trace::{demo, TraceLoader};
+
+    fn u64_at(bytes: &[u8], field: usize) -> u64 {
+        u64::from_le_bytes(bytes[field * 8..field * 8 + 8].try_into().unwrap())
+    }
+
+    #[test]
+    fn reassembled_chunks_match_the_view_buffer() {
+        let path = scratch_path("view-stream.bin");
+        writer::write_trace(&path, 1, &demo::generate(1000, 3), &demo::dictionary()).unwrap();
+        let session = SessionState::new();
+        let loader = TraceLoader::new(path.clone()).unwrap();
+        let identity = loader.identity();
+        let expected = entry::get_entry_range_bytes(loader.load_entry_slice(100, 750).unwrap());
+        *session.loader.lock().unwrap() = Some(loader);
+
+        let mut messages = Vec::new();
+        let done = run(
+            &session,
+            identity,
+            (100, 750),
+            300,
+            &AtomicBool::new(false),
+            |message| {
+                messages.push(message);
+                Ok(())
+            },
+        );
+        assert_eq!((done.total, done.cancelled, done.error), (750, false, None));
+        assert_eq!(messages.len(), 3);
+
+        let mut buffer = vec![0u8; expected.len()];
+        for message in &messages {
+            let count = u64_at(message, 1) as usize;
+            let mut lanes = &message[CHUNK_HEADER_SIZE..];
+            for (lane, width) in [4, 1, 1, 1, 1].into_iter().enumerate() {
+                let offset = u64_at(message, 2 + lane) as usize;
+                let (chunk_lane, rest) = lanes.split_at(count * width);
+                buffer[offset..offset + chunk_lane.len()].copy_from_slice(chunk_lane);
+                lanes = rest;
+            }
+            assert!(lanes.is_empty());
+        }
+        assert_eq!(
+            messages.iter().map(|m| u64_at(m, 0)).collect::<Vec<_>>(),
+            [0, 300, 600]
+        );
+        assert_eq!(buffer, expected);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn cancelled_and_stale_streams_end_early() {
+        let session = SessionState::new();
+        let cancelled = run(&session, 0, (0, 10), 4, &AtomicBool::new(true), |_| Ok(()));
+        assert_eq!((cancelled.total, cancelled.cancelled), (0, true));
+
+        let no_trace = run(&session, 0, (0, 10), 4, &AtomicBool::new(false), |_| Ok(()));
+        assert_eq!(no_trace.error.as_deref(), Some("No trace loaded"));
+    }
+}